@@ -0,0 +1,64 @@
+//! Compares the CPU time spent submitting the same [`Function`] many times, adding it again to an [`Algorithm`]
+//! at each iteration or replaying it with a [`CompiledAlgorithm`]
+//!
+//! Run it with `cargo run --release --example compiled_step`.
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use ndarray::Array2;
+use wgpu_calc::algorithm::{Algorithm, Function, VariableBind};
+use wgpu_calc::coding::Shader;
+use wgpu_calc::translator::GpuArray2;
+
+const ITERATIONS: usize = 500;
+
+const SHADER: &str = "
+@group(0) @binding(0)
+var<storage, read_write> a: array<f32>;
+
+@compute @workgroup_size(64, 1, 1)
+fn add_1(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (id.x < arrayLength(&a)) {
+        a[id.x] = a[id.x] + 1.0;
+    }
+}
+";
+
+#[tokio::main]
+async fn main() -> Result<(), anyhow::Error> {
+    let shader = Shader::from_content(SHADER);
+    let var = Arc::new(Mutex::new(GpuArray2::from_ndarray(
+        Array2::zeros((1, 4096)),
+        Some("counter"),
+    )?));
+    let bindings = || vec![VariableBind::new(Arc::clone(&var), 0)];
+
+    let mut algorithm = Algorithm::new(Some("re-added")).await?;
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        algorithm.add_fun(Function::new(&shader, "add_1", bindings()))?;
+        algorithm.run().await?;
+    }
+    algorithm.get_output_unmap(&var).await?;
+    let re_added = start.elapsed();
+
+    let mut compiled = algorithm.with_shared_executor(Some("compiled"));
+    compiled.add_fun(Function::new(&shader, "add_1", bindings()))?;
+    let mut compiled = compiled.compile()?;
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        compiled.step()?;
+    }
+    compiled.get_output(&var).await?;
+    let replayed = start.elapsed();
+
+    println!(
+        "{} iterations re-adding the Function: {:?}",
+        ITERATIONS, re_added
+    );
+    println!(
+        "{} iterations of a CompiledAlgorithm: {:?}",
+        ITERATIONS, replayed
+    );
+    Ok(())
+}
@@ -8,25 +8,32 @@
 //!
 //! # Principal components:
 //! - [`Function`] is a struct which holds the definition of a function. It's a [`Shader`]
-//!     with an ['entry point'] which performs operations on some [`Variable`]
+//!   with an ['entry point'] which performs operations on some [`Variable`]
 //! - [`VariableBind`] is the link between a [`Variable`] and a `bind group` defined in the shader code.
-//!     Each [`Variable`] can potentially have more than one bind in the [`Shader`] and the definition
-//!     is held in this struct
+//!   Each [`Variable`] can potentially have more than one bind in the [`Shader`] and the definition
+//!   is held in this struct
 //! - [`Algorithm`] is the operational part of this library, it collects instances of [`Function`]
-//!     and tries to translate them as efficiently as possible to a series of [`Solver`].
-//!     Once every function is inserted in the Algorithm, the [`Algorithm::run`] method is used
-//!     to perform the calculation on the GPU
+//!   and tries to translate them as efficiently as possible to a series of [`Solver`].
+//!   Once every function is inserted in the Algorithm, the [`Algorithm::run`] method is used
+//!   to perform the calculation on the GPU
 //!
 //!
 #![allow(dead_code)]
 use anyhow::anyhow;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
 use std::num::NonZeroU64;
-use std::sync::{Arc, Mutex};
+use std::ops::Deref;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
 
 use crate::coding::Shader;
-use crate::interface::Executor;
-use crate::variable::Variable;
+use crate::errors::{BindingError, LockError, VariableError};
+use crate::interface::{Executor, ExecutorOptions, MappedBuffer};
+use crate::variable::{TextureVariable, Variable};
+
+pub mod ops;
 
 /// This struct is the container for the different operations to perform
 ///
@@ -46,10 +53,64 @@ pub struct Algorithm<'a, V: Variable> {
     variables: Vec<StoredVariable<V>>,
     modules: Vec<Module<'a>>,
     buffers: Vec<wgpu::Buffer>,
+    textures: Vec<StoredTexture>,
     // operations: Vec<Operation<'a>>,
-    label: Option<&'a str>,
-    executor: Executor<'a>,
+    label: Option<String>,
+    executor: Arc<Executor>,
     solvers: Vec<Solver<V>>,
+    pending_stats: RunStats,
+    last_run_stats: RunStats,
+    // best workgroup size found by [`Algorithm::autotune`] for each shader template and entry point
+    tuned_sizes: HashMap<(Shader, String), [u32; 3]>,
+    // logs the binding decisions of [`Algorithm::add_fun`]
+    verbose: bool,
+    // submits the consecutive serial functions together, see [`Algorithm::set_fuse_serial`]
+    fuse_serial: bool,
+    // the flags set by the functions with a sentinel, checked by [`Algorithm::verify_executed`]
+    sentinels: Vec<Sentinel>,
+    // reads back every upload to compare it with the host data, see [`Algorithm::debug_verify_uploads`]
+    verify_uploads: bool,
+    // the variables and textures uploaded and not yet verified
+    unverified_uploads: Vec<Resource>,
+    // the pipeline and bind groups of each function not yet run, replayed by [`CompiledAlgorithm::step`]
+    recorded: HashMap<FunctionHandle, RecordedPass>,
+    // the flag set when the work of the last run is done on the GPU, or the error of the run
+    last_run: Option<Result<Arc<AtomicBool>, String>>,
+    // the timestamps of the functions not yet run, when the device supports them
+    timestamp_queries: Vec<TimestampQuery>,
+    // the GPU time of each function of the last run, see [`Algorithm::timings`]
+    timings: Vec<(String, Duration)>,
+    // the functions and the reads of the last run, replayed by [`Algorithm::rerun`]
+    last_passes: Vec<RecordedPass>,
+    last_reads: Vec<usize>,
+    // whether the last run had only functions and reads, so that [`Algorithm::rerun`] can replay all of it
+    last_replayable: bool,
+    // the variables bound with [`VariableBind::as_output`] by each function, see [`Algorithm::read_outputs`]
+    outputs: HashMap<FunctionHandle, Vec<Arc<Mutex<V>>>>,
+    // the functions added, with the number of variables, buffers and textures before each of them, so that
+    // [`Algorithm::pop_last_function`] can unwind what it stored
+    added: Vec<(FunctionHandle, [usize; 3])>,
+}
+
+/// This struct holds the statistics of the workload submitted to the GPU by an [`Algorithm::run`]
+///
+/// It describes the shape of the work done on the CPU side, i.e. how many operations have been
+/// submitted and how many bytes moved between the CPU and the GPU, not how long the GPU took to execute them.
+/// The uploads are accounted to the run following the [`Algorithm::add_fun`] which wrote the buffers.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RunStats {
+    /// the number of compute dispatches submitted
+    pub dispatches: usize,
+    /// the total number of workgroups launched by the dispatches
+    pub workgroups: u64,
+    /// the bytes written from the CPU to the GPU buffers
+    pub bytes_uploaded: u64,
+    /// the bytes read back from the GPU buffers to the CPU
+    pub bytes_read: u64,
+    /// the number of distinct pipelines (shader and entry point) used
+    pub pipelines: usize,
+    /// the number of submissions to the queue, see [`Algorithm::set_fuse_serial`]
+    pub submissions: usize,
 }
 
 /// This struct is responsible of defining the operation to perform on the GPU
@@ -62,24 +123,137 @@ pub struct Algorithm<'a, V: Variable> {
 ///
 /// Multiple [`Function`]s can reference the same [`Shader`] and `entry point`, but one [`VariableBind`] must be
 /// created for each of them
+///
+/// A [`Function`] can also receive the dimensions of its first [`Variable`] as data (see [`Function::with_dimensions`]),
+/// so that the same [`Shader`] works for any size without templating it.
 pub struct Function<'a, V: Variable> {
-    shader: &'a Shader,
+    shader: ShaderRef<'a>,
     entry_point: &'a str,
     variables: Vec<VariableBind<V>>,
+    dimensions_binding: Option<u32>,
+    dispatch_offset: [u32; 3],
+    tile_size: Option<[u32; 3]>,
+    group_labels: Vec<(u32, &'a str)>,
+    textures: Vec<TextureBind>,
+    sentinel_binding: Option<u32>,
+    // the stages dispatched after the first one with the same bind group, see [`Function::fused`]
+    fused: Vec<(ShaderRef<'a>, &'a str)>,
+    // the (group, binding) of the variables added with [`Function::with_immutable`]
+    read_only_bindings: Vec<(u32, u32)>,
+    // the workgroup count set with [`Function::with_workgroups`], instead of the one from the first variable
+    workgroups: Option<[u32; 3]>,
+    // the bytes set with [`Function::with_push_constants`]
+    push_constants: Vec<u8>,
+    // the binding and the bytes of a uniform owned by the function, see [`Function::with_uniform_data`]
+    uniform_data: Option<(u32, Vec<u8>)>,
+}
+
+/// Describes how a [`TextureVariable`] is accessed by the shader, see [`Function::with_texture`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextureAccess {
+    /// the texture is declared as `texture_2d<f32>` and read with `textureLoad`
+    Read,
+    /// the texture is declared as a write only `texture_storage_2d` and written with `textureStore`
+    Write,
+}
+
+/// A description of an operation scheduled in an [`Algorithm`], given by [`Algorithm::get_operations`]
+///
+/// It mirrors the plan which will be submitted by the following [`Algorithm::run`], so that the schedule can be
+/// checked without running anything on the GPU.
+/// Variables are identified by their index in the [`Algorithm`], in the order they were first added.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OperationInfo {
+    /// Commands recorded in one command encoder: the dispatches of a [`Function`], or a buffer
+    /// operation (like [`Algorithm::reset_accumulator`]) with no dispatches
+    Serial {
+        variables: Vec<usize>,
+        dispatches: Vec<DispatchInfo>,
+    },
+    /// Operations submitted together to the GPU
+    Parallel(Vec<OperationInfo>),
+    /// The read back of a variable (see [`Algorithm::read_variable`])
+    ReadBuffer { variable: usize },
+}
+
+/// A description of a dispatch of a compute pipeline, part of an [`OperationInfo::Serial`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DispatchInfo {
+    pub entry_point: String,
+    /// pairs of (binding, variable index) of the bind group used by the dispatch
+    pub bindings: Vec<(u32, usize)>,
+    pub workgroups: [u32; 3],
+}
+
+/// The GPU buffer of a [`Variable`] mapped to be read row by row, given by [`Algorithm::read_rows`]
+///
+/// The buffer stays mapped until this is dropped. The rows are read through a [`RowsView`].
+#[derive(Debug)]
+pub struct MappedRows<'e> {
+    mapped: MappedBuffer<'e>,
+    row_len: usize,
+}
+
+/// A view over the rows of a [`MappedRows`], see [`RowsView::rows`]
+#[derive(Debug)]
+pub struct RowsView<'m> {
+    view: wgpu::BufferView<'m>,
+    row_len: usize,
+}
+
+impl MappedRows<'_> {
+    /// Gives a view over the mapped rows
+    ///
+    /// Only one view at a time can exist, asking for a second one while the first is alive panics.
+    pub fn view(&self) -> RowsView<'_> {
+        RowsView {
+            view: self.mapped.view(),
+            row_len: self.row_len,
+        }
+    }
+}
+
+impl RowsView<'_> {
+    /// Gives an iterator over the rows, read in place from the mapped memory
+    pub fn rows(&self) -> impl Iterator<Item = &[f32]> {
+        bytemuck::cast_slice::<u8, f32>(&self.view).chunks(self.row_len)
+    }
+}
+
+/// The kind of resource expected by a binding of a shader
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BindingKind {
+    /// a `var<storage>` binding, `read_only` if declared with the `read` access mode
+    Storage { read_only: bool },
+    /// a `var<uniform>` binding
+    Uniform,
+}
+
+/// This struct declares a binding expected by the entry point of a [`Shader`]
+///
+/// A list of them makes the schema of an entry point, which is checked against the [`VariableBind`]s
+/// given to [`Function::new_checked`].
+/// The `wgsl_type` is the type as written in the shader (e.g. `array<f32>`), and it's compared
+/// with [`Variable::wgsl_type`] when the variable gives one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BindingSchema {
+    pub group: u32,
+    pub binding: u32,
+    pub kind: BindingKind,
+    pub wgsl_type: String,
 }
 
 /// Unit struct only for defining a [`VariableBind`] as mutable during the GPU calculations.
 ///
-/// Currently all the [`VariableBind`] are created as mutable, until I become
-/// smart enough to implement the immutable side of it, which could potentilly
-/// make some more parallelisation possible
+/// All the [`VariableBind`] are created as mutable, and can be set as [`Immutable`] with
+/// [`VariableBind::set_immutable`].
 #[derive(Debug)]
 pub struct Mutable;
 
 /// Unit struct to define a [`VariableBind`] as immutable during the GPU calculations.
 ///
-/// Currently it's impossible to create an immutable [`VariableBind`], but in the future it
-/// might be possible
+/// An immutable [`VariableBind`] is given by [`VariableBind::set_immutable`] and bound as read only by
+/// [`Function::with_immutable`], which lets the GPU know the buffer isn't written.
 #[derive(Debug)]
 pub struct Immutable;
 
@@ -88,18 +262,74 @@ pub struct Immutable;
 /// It holds an Arc<Mutex> to the [`Variable`] so that multiple binds can be created for the
 /// same [`Variable`].
 ///
-/// Currently all the [`VariableBind`] are [`Mutable`], i.e. they are trated like they will mutate during the
-/// GPU operation.
+/// By default the [`VariableBind`] are [`Mutable`], i.e. they are trated like they will mutate during the
+/// GPU operation, while the [`Immutable`] ones are bound as read only (see [`Function::with_immutable`]).
 #[derive(Debug)]
 pub struct VariableBind<V, Type = Mutable>
 where
     V: Variable,
 {
     variable: Arc<Mutex<V>>,
+    group: u32,
     bind_group: u32,
+    view: Option<(u64, u64)>,
+    uniform: bool,
+    output: bool,
     mutable: std::marker::PhantomData<Type>,
 }
 
+/// This struct defines a subrange of the buffer of a [`Variable`]
+///
+/// It allows to bind `[offset, offset + len)` bytes of a bigger [`Variable`] (e.g. an arena holding many values)
+/// as if it was a variable on its own, without any further allocation or copy on the GPU.
+/// The view is bound with [`VariableBind::from_view`] and shares the buffer of its parent [`Variable`].
+#[derive(Debug)]
+pub struct VariableView<V>
+where
+    V: Variable,
+{
+    parent: Arc<Mutex<V>>,
+    offset: u64,
+    len: u64,
+}
+
+/// This struct holds two [`Variable`]s used alternately as the input and the output of an iteration
+///
+/// Stencil and iterative kernels read the values of the previous step from a buffer and write the new ones to
+/// another, then swap the two. The [`PingPong`] keeps track of which side is read and which is written, and
+/// [`PingPong::swap`] flips them, so that the same [`Function`] can be added for each iteration with
+/// [`Algorithm::add_iterations`] without any copy between the buffers.
+#[derive(Debug)]
+pub struct PingPong<V>
+where
+    V: Variable,
+{
+    sides: [Arc<Mutex<V>>; 2],
+    // the index of the side read by the next iteration
+    read: usize,
+}
+
+/// The placeholder replaced by [`Algorithm::autotune`] with the candidate workgroup sizes
+///
+/// The [`Shader`] template declares the entry point with `@workgroup_size(€workgroup_size)`.
+pub const WORKGROUP_SIZE_PLACEHOLDER: &str = "€workgroup_size";
+
+// the handle given to the next [`Function`] added to any [`Algorithm`], so that handles stay unique
+// when an [`Algorithm`] is appended to another one
+static NEXT_HANDLE: AtomicUsize = AtomicUsize::new(0);
+
+// total size in bytes of the pending variables from which [`Algorithm::upload_all`] writes them from several threads
+const PARALLEL_UPLOAD_BYTES: u64 = 1 << 20;
+
+// number of timed runs of each candidate of [`Algorithm::autotune`], the fastest one is kept
+const AUTOTUNE_REPEATS: usize = 3;
+
+/// The alignment in bytes required for the offset of a [`VariableView`]
+///
+/// This is the largest value allowed for [`wgpu::Limits::min_storage_buffer_offset_alignment`],
+/// so an aligned offset is valid on every device.
+pub const VIEW_OFFSET_ALIGNMENT: u64 = 256;
+
 // holds the buffer references of the variable
 #[derive(Debug)]
 struct StoredVariable<V>
@@ -109,15 +339,171 @@ where
     variable: Arc<Mutex<V>>,
     binds: Vec<usize>,
     buffer_index: usize,
+    // the buffer is owned by the GPU, the host data is never uploaded
+    accumulator: bool,
+    // the buffer was seeded at allocation and can't be written again (see [`Algorithm::add_constant_mapped`])
+    constant: bool,
+    // the host data still needs to be written to the buffer (see [`Algorithm::upload_all`])
+    pending_upload: bool,
+}
+
+// holds the texture of a [`TextureVariable`] and the view bound to the functions
+#[derive(Debug)]
+struct StoredTexture {
+    variable: Arc<Mutex<dyn TextureVariable>>,
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+    // the host data still needs to be written to the texture (see [`Algorithm::upload_all`])
+    pending_upload: bool,
+}
+
+// holds a variable used by two [`Algorithm`]s merged with [`Algorithm::append`], with the buffers of both
+#[derive(Debug, Clone, Copy)]
+struct SharedVariable {
+    variable: usize,
+    own_buffer: usize,
+    their_buffer: usize,
+}
+
+// holds the flag buffer of a function added with [`Function::with_sentinel`]
+#[derive(Debug, Clone)]
+struct Sentinel {
+    handle: FunctionHandle,
+    entry_point: String,
+    buffer_index: usize,
+}
+
+// holds the timestamps written around the compute pass of a function, see [`Algorithm::timings`]
+#[derive(Debug)]
+struct TimestampQuery {
+    handle: FunctionHandle,
+    entry_point: String,
+    query_set: wgpu::QuerySet,
+    // the buffer the two timestamps are resolved to
+    buffer_index: usize,
+}
+
+// holds what is needed to record again the dispatches of a function, see [`Algorithm::compile`]
+#[derive(Debug)]
+struct RecordedPass {
+    // one pipeline for each stage of the function, each dispatched with all the bind groups
+    pipelines: Vec<Arc<wgpu::ComputePipeline>>,
+    // set after each pipeline, if any
+    push_constants: Vec<u8>,
+    // the bind groups, by their index, and the workgroup count of each dispatch
+    dispatches: Vec<(Vec<wgpu::BindGroup>, [u32; 3])>,
+}
+
+// identifies a variable or a texture stored in an [`Algorithm`], by its index
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Resource {
+    Variable(usize),
+    Texture(usize),
+}
+
+// holds a [`TextureVariable`] bound to a [`Function`]
+struct TextureBind {
+    texture: Arc<Mutex<dyn TextureVariable>>,
+    binding: u32,
+    access: TextureAccess,
+}
+
+// a shader borrowed by a [`Function`], or rendered from a template and owned by the [`Algorithm`] (see
+// [`ops::matmul`]), so that the rendered shaders live as long as the modules using them
+#[derive(Debug, Clone)]
+enum ShaderRef<'a> {
+    Borrowed(&'a Shader),
+    Owned(Arc<Shader>),
+}
+
+impl Deref for ShaderRef<'_> {
+    type Target = Shader;
+
+    fn deref(&self) -> &Shader {
+        match self {
+            ShaderRef::Borrowed(shader) => shader,
+            ShaderRef::Owned(shader) => shader,
+        }
+    }
+}
+
+impl PartialEq for ShaderRef<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
 }
 
 // holds the information of the inserted modules, shaders with different entry points
 #[derive(Debug, PartialEq, Clone)]
 struct Module<'a> {
-    shader: &'a Shader,
+    shader: ShaderRef<'a>,
     entry_point: Vec<&'a str>,
 }
 
+// holds the information of a dispatch recorded in a command encoder
+#[derive(Debug, Clone, PartialEq)]
+struct Dispatch {
+    module: usize,
+    entry_point: usize,
+    workgroups: [u32; 3],
+    // (binding, variable index) of the bind group set for the dispatch
+    bindings: Vec<(u32, usize)>,
+    // whether each of the `bindings` is only read, i.e. [`Immutable`] or uniform
+    read_only: Vec<bool>,
+    // (texture index, access) of the textures bound for the dispatch
+    textures: Vec<(usize, TextureAccess)>,
+}
+
+/// The outcome of an [`Algorithm::run_with_deadline`]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DeadlineReport {
+    /// the [`Function`]s executed before the deadline, in order
+    pub completed: Vec<FunctionHandle>,
+    /// the [`Function`]s left scheduled since the deadline had passed, in order
+    pub skipped: Vec<FunctionHandle>,
+}
+
+/// An [`Algorithm`] whose operations are recorded once and run again at each [`CompiledAlgorithm::step`]
+///
+/// It's made by [`Algorithm::compile`], and it's meant for the iterative calculations which run the same
+/// [`Function`]s many times (e.g. time steps of a simulation): the pipelines and bind groups are created once,
+/// and each step only records the dispatches in a single command buffer and submits it, while the
+/// [`Variable`]s are written to the GPU only when they're marked as changed with [`CompiledAlgorithm::mark_dirty`].
+#[derive(Debug)]
+pub struct CompiledAlgorithm<V: Variable> {
+    variables: Vec<StoredVariable<V>>,
+    buffers: Vec<wgpu::Buffer>,
+    // kept alive for the bind groups of the passes
+    _textures: Vec<StoredTexture>,
+    passes: Vec<RecordedPass>,
+    label: Option<String>,
+    executor: Arc<Executor>,
+}
+
+/// The state of the last run of an [`Algorithm`], given by [`Algorithm::poll_run`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RunState {
+    /// the GPU is still executing the submitted work
+    Pending,
+    /// all the submitted work is done, or nothing was submitted yet
+    Done,
+    /// the run failed before submitting all its work, with the error it returned
+    Error(String),
+}
+
+/// Identifies a [`Function`] added to an [`Algorithm`], given by [`Algorithm::add_fun`]
+///
+/// It's used to select the [`Function`]s to execute with [`Algorithm::run_selected`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FunctionHandle(usize);
+
+// holds the region covered by one of the dispatches of a function
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Tile {
+    offset: [u32; 3],
+    workgroups: [u32; 3],
+}
+
 // Enum to deal in the future with the parallelisation of some [`Function`] execution
 #[derive(Debug)]
 enum Solver<V>
@@ -127,6 +513,9 @@ where
     Serial {
         command_encoder: wgpu::CommandEncoder,
         variables: Vec<Arc<Mutex<V>>>,
+        dispatches: Vec<Dispatch>,
+        // set only for the solvers of a [`Function`]
+        handle: Option<FunctionHandle>,
     },
     Parallel(Vec<Solver<V>>),
 
@@ -141,19 +530,66 @@ impl<'a, V: Variable> Algorithm<'a, V> {
     /// # Arguments
     ///* - `label` - an optional string reference to use for debugging purposes.
     ///
+    /// The `label` is copied, so it can be built at runtime (e.g. `&format!("run {}", i)`) and dropped right after.
+    ///
     /// Returns an [`anyhow::Error`] if the [`Executor`] fails to instantiate
     /// # Panics
     /// if the [`Executor`] initialisation
-    pub async fn new(label: Option<&'a str>) -> Result<Algorithm<'a, V>, anyhow::Error> {
+    pub async fn new(label: Option<&str>) -> Result<Algorithm<'a, V>, anyhow::Error> {
         let executor = Executor::new(label).await?;
-        Ok(Algorithm {
+        Ok(Algorithm::from_executor(Arc::new(executor), label))
+    }
+
+    /// Creates a new empty [`Algorithm`] like [`Algorithm::new`], whose [`Executor`] is created with the given
+    /// [`ExecutorOptions`] (e.g. to exclude the backends with known bad drivers)
+    ///
+    /// Returns an [`anyhow::Error`] if the [`Executor`] fails to instantiate
+    pub async fn with_options(
+        label: Option<&str>,
+        options: ExecutorOptions,
+    ) -> Result<Algorithm<'a, V>, anyhow::Error> {
+        let executor = Executor::with_options(label, options).await?;
+        Ok(Algorithm::from_executor(Arc::new(executor), label))
+    }
+
+    /// Creates a new empty [`Algorithm`] sharing the [`Executor`] of `self`
+    ///
+    /// No new connection to the GPU is made, and the two [`Algorithm`]s work on the same device, so that one can be
+    /// merged into the other with [`Algorithm::append`] (if they work on the same type of [`Variable`]). This is the way to build reusable sub-algorithms and compose
+    /// them into a bigger one.
+    /// # Arguments
+    ///* - `label` - an optional string reference to use for debugging purposes.
+    pub fn with_shared_executor<W: Variable>(&self, label: Option<&str>) -> Algorithm<'a, W> {
+        Algorithm::from_executor(Arc::clone(&self.executor), label)
+    }
+
+    fn from_executor(executor: Arc<Executor>, label: Option<&str>) -> Algorithm<'a, V> {
+        Algorithm {
             variables: Vec::new(),
             modules: Vec::new(),
             buffers: Vec::new(),
+            textures: Vec::new(),
             solvers: Vec::new(),
-            label,
+            label: label.map(str::to_owned),
             executor,
-        })
+            pending_stats: RunStats::default(),
+            last_run_stats: RunStats::default(),
+            tuned_sizes: HashMap::new(),
+            verbose: false,
+            fuse_serial: false,
+            sentinels: Vec::new(),
+            verify_uploads: false,
+            unverified_uploads: Vec::new(),
+            recorded: HashMap::new(),
+            last_run: None,
+            timestamp_queries: Vec::new(),
+            timings: Vec::new(),
+            last_passes: Vec::new(),
+            last_reads: Vec::new(),
+            last_replayable: false,
+            outputs: HashMap::new(),
+            added: Vec::new(),
+        }
     }
 
     /// This still needs implementations
@@ -169,237 +605,3058 @@ impl<'a, V: Variable> Algorithm<'a, V> {
     ///
     /// With this method the operation defined in the [`Function`] is added to the list of
     /// operations which will be carried on the GPU.
-    /// The only action this takes is to write the [`Variable`] contained in the [`Function`] to
-    /// the GPU buffer.
+    /// The only action this takes is to create the GPU buffers of the new [`Variable`]s contained in the [`Function`],
+    /// which are written all together by [`Algorithm::upload_all`] (called at the latest by [`Algorithm::run`]).
     ///
     /// Notice that buffer writing only takes place once for every builted [`Variable`], to avoid multiplication
     /// of this operation.
     ///
+    /// The entry point is read from the [`Shader`] (see [`Shader::entry_point_info`]): the dispatch is made of enough
+    /// workgroups of its `@workgroup_size` to cover the dimensions of the first [`Variable`], one invocation per element,
+    /// and the device must support the features the [`Shader`] needs.
+    /// The count is rounded up in each dimension, so the shader must skip the invocations past the end of the last
+    /// workgroup (see [`Function::with_dimensions`]). It can also be set explicitly with [`Function::with_workgroups`].
+    ///
+    /// The [`Function`]s run one after the other, so a [`Function`] always sees what the previous ones wrote to the same
+    /// [`Variable`]: the operations grouped to run in parallel are checked by [`Algorithm::run`] not to depend on each
+    /// other in the same way (see [`Algorithm::to_dot`]).
+    ///
     /// Takes a mutable reference to `self`.
     ///
     /// # Arguments
     /// * - `function` - the [`Function`] to add to the [`Algorithm`]
-    pub fn add_fun(&mut self, function: Function<'a, V>) {
-        let f_label = stringify!(function);
+    ///
+    /// Returns a [`FunctionHandle`] to select the [`Function`] with [`Algorithm::run_selected`].
+    ///
+    /// # Errors
+    /// - if a [`Variable`] of the [`Function`] fails [`Variable::validate_self`]
+    /// - if the workgroups covering the first [`Variable`] exceed the device limit of workgroups per dimension
+    /// - if the [`Function`] has neither a [`Variable`] nor a [`TextureVariable`]
+    /// - if the data of a [`TextureVariable`] doesn't match its size and format
+    /// - if the [`Shader`] can't be parsed, isn't valid (see [`Shader::validate`]) or has no such compute entry point
+    /// - if a storage or uniform binding used by the entry point isn't given a [`Variable`] (see [`Shader::bindings`])
+    /// - if the device doesn't support the features required by the [`Shader`]
+    /// - if a stage of a [`Function::fused`] has a different `@workgroup_size` than the first one
+    /// - if the workgroups of [`Function::with_workgroups`] exceed the device limit, or are set on a tiled dispatch
+    /// - if a [`Variable`] added before without the uniform usage is bound with [`VariableBind::as_uniform`]
+    /// - if the [`Function`] has push constants the device doesn't support, or of a size not multiple of 4
+    /// - if a [`VariableBind::as_output`] is also bound with [`VariableBind::as_uniform`]
+    /// - if the lock of a [`Variable`] or [`TextureVariable`] is poisoned, i.e. a thread panicked while holding it
+    pub fn add_fun(&mut self, function: Function<'a, V>) -> Result<FunctionHandle, anyhow::Error> {
+        let f_label = function.entry_point;
+        // the bind groups go from 0 to the highest one used by a variable, the textures and uniforms are in the group 0
+        let group_count = function
+            .variables
+            .iter()
+            .map(|var| var.group as usize + 1)
+            .max()
+            .unwrap_or(1);
+        let group_labels: Vec<&str> = (0..group_count as u32)
+            .map(|group| function.group_label(group).unwrap_or(f_label))
+            .collect();
+        let storage = [
+            self.variables.len(),
+            self.buffers.len(),
+            self.textures.len(),
+        ];
         let f_var = function.variables;
+        let f_textures = function.textures;
+        for var in &f_var {
+            lock_variable(&var.variable)?.validate_self()?;
+            if var.output && var.uniform {
+                return Err(BindingError::UniformOutput {
+                    group: var.group,
+                    binding: var.bind_group,
+                }
+                .into());
+            }
+        }
+        let outputs: Vec<Arc<Mutex<V>>> = f_var
+            .iter()
+            .filter(|var| var.output)
+            .map(|var| Arc::clone(&var.variable))
+            .collect();
+        if function.dimensions_binding.is_none()
+            && (function.tile_size.is_some() || function.dispatch_offset != [0; 3])
+        {
+            return Err(anyhow!(
+                "The dispatch offset needs the dimensions uniform, see Function::with_dimensions"
+            ));
+        }
+        // each buffer used by the stages needs a variable, the dimensions, the sentinel or the uniform bound to it
+        let bound: Vec<(u32, u32)> = f_var
+            .iter()
+            .map(|var| (var.group, var.bind_group))
+            .chain(function.dimensions_binding.map(|binding| (0, binding)))
+            .chain(function.sentinel_binding.map(|binding| (0, binding)))
+            .chain(
+                function
+                    .uniform_data
+                    .as_ref()
+                    .map(|&(binding, _)| (0, binding)),
+            )
+            .collect();
+        for (shader, entry_point) in std::iter::once((&function.shader, function.entry_point))
+            .chain(
+                function
+                    .fused
+                    .iter()
+                    .map(|(shader, entry_point)| (shader, *entry_point)),
+            )
+        {
+            for (group, binding, _) in shader.entry_point_bindings(entry_point)? {
+                if !bound.contains(&(group, binding)) {
+                    return Err(BindingError::MissingBinding {
+                        entry_point: entry_point.to_string(),
+                        group,
+                        binding,
+                    }
+                    .into());
+                }
+            }
+        }
+        let info = function.shader.entry_point_info(function.entry_point)?;
+        let features = self.executor.with_device(|device, _| device.features());
+        for (shader, entry_point) in &function.fused {
+            let stage_info = shader.entry_point_info(entry_point)?;
+            if stage_info.workgroup_size != info.workgroup_size {
+                return Err(anyhow!(
+                    "Function {} has workgroup size {:?}, but its fused stage {} has {:?}",
+                    function.entry_point,
+                    info.workgroup_size,
+                    entry_point,
+                    stage_info.workgroup_size
+                ));
+            }
+            if !features.contains(stage_info.required_features) {
+                return Err(anyhow!(
+                    "Function {} needs the features {:?}, which are not supported by the device",
+                    entry_point,
+                    stage_info.required_features.difference(features)
+                ));
+            }
+        }
+        if !features.contains(info.required_features) {
+            return Err(anyhow!(
+                "Function {} needs the features {:?}, which are not supported by the device",
+                function.entry_point,
+                info.required_features.difference(features)
+            ));
+        }
+        if !function.push_constants.is_empty() {
+            let max_size = self
+                .executor
+                .with_device(|device, _| device.limits().max_push_constant_size);
+            if !features.contains(wgpu::Features::PUSH_CONSTANTS) {
+                return Err(anyhow!(
+                    "Function {} has push constants, but the device wasn't created with the PUSH_CONSTANTS feature",
+                    function.entry_point
+                ));
+            }
+            if !function.push_constants.len().is_multiple_of(4)
+                || function.push_constants.len() > max_size as usize
+            {
+                return Err(anyhow!(
+                    "Function {} has {} bytes of push constants, but they must be a multiple of 4 up to the device limit of {}",
+                    function.entry_point,
+                    function.push_constants.len(),
+                    max_size
+                ));
+            }
+        }
         let mut command_encoder = self.executor.create_encoder(Some(f_label));
-        // drop(executor);
 
         let variables: Vec<Arc<Mutex<V>>> =
             f_var.iter().map(|var| Arc::clone(&var.variable)).collect();
 
-        let workgroups = variables[0].lock().unwrap().get_workgroup().unwrap();
+        // the extent covered by the function, of its first variable or else of its first texture
+        let extent = match (variables.first(), f_textures.first()) {
+            (Some(var), _) => lock_variable(var)?.dimension_sizes(),
+            (None, Some(bind)) => {
+                let [width, height] = lock_texture(&bind.texture)?.texture_size();
+                [width, height, 1]
+            }
+            (None, None) => {
+                return Err(anyhow!(
+                    "Function {} has no variable nor texture to work on",
+                    function.entry_point
+                ))
+            }
+        };
+
+        // the offset and workgroups of every dispatch of the function
+        let max_workgroups = self
+            .executor
+            .with_device(|device, _| device.limits().max_compute_workgroups_per_dimension);
+        let tiles = match (function.tile_size, function.workgroups) {
+            (Some(_), Some(_)) => {
+                return Err(anyhow!(
+                "Function {} has an explicit workgroup count, so it can't be dispatched in tiles",
+                function.entry_point
+            ))
+            }
+            (Some(tile_size), None) => tiles(
+                extent,
+                info.workgroup_size,
+                tile_size,
+                function.dispatch_offset,
+                max_workgroups,
+            )?,
+            (None, Some(workgroups)) => {
+                if workgroups.iter().any(|&count| count > max_workgroups) {
+                    return Err(anyhow!(
+                        "Function {} dispatches {:?} workgroups, which exceeds the device limit of {} per dimension",
+                        function.entry_point,
+                        workgroups,
+                        max_workgroups
+                    ));
+                }
+                vec![Tile {
+                    offset: function.dispatch_offset,
+                    workgroups,
+                }]
+            }
+            (None, None) => {
+                // rounded up, so that the last partial workgroup of each dimension is dispatched too
+                let workgroups: [u32; 3] =
+                    std::array::from_fn(|id| extent[id].div_ceil(info.workgroup_size[id]));
+                if let Some(id) = workgroups.iter().position(|&count| count > max_workgroups) {
+                    return Err(anyhow!(
+                        "Function {} needs {} workgroups of size {} in dimension {}, which exceeds the device limit of {}, \
+                        please use Algorithm::add_function_tiled",
+                        function.entry_point,
+                        workgroups[id],
+                        info.workgroup_size[id],
+                        id,
+                        max_workgroups
+                    ));
+                }
+                vec![Tile {
+                    offset: function.dispatch_offset,
+                    workgroups,
+                }]
+            }
+        };
 
         let mut new_vars = Vec::new();
         let mut new_binds = Vec::new();
         let mut new_vars_count = 0;
+        // whether each bind found its variable already stored, only used by the verbose mode
+        let mut matched = Vec::new();
 
         for var in f_var {
-            if let Some(pos) = self
-                .variables
-                .iter()
-                .position(|sto_var| Arc::ptr_eq(&sto_var.variable, &var.variable))
-            {
-                new_binds.push([pos, var.bind_group as usize]);
+            let binding_type = if var.uniform {
+                wgpu::BufferBindingType::Uniform
+            } else {
+                wgpu::BufferBindingType::Storage {
+                    read_only: function
+                        .read_only_bindings
+                        .contains(&(var.group, var.bind_group)),
+                }
+            };
+            if let Some(pos) = self.find_variable(&var.variable) {
+                let buffer = &self.buffers[self.variables[pos].buffer_index];
+                if var.uniform && !buffer.usage().contains(wgpu::BufferUsages::UNIFORM) {
+                    return Err(anyhow!(
+                        "Variable {:?} was added without the uniform usage, so it can't be bound as uniform at binding {}",
+                        variable_name(&var.variable),
+                        var.bind_group
+                    ));
+                }
+                matched.push(true);
+                new_binds.push((pos, var.group, var.bind_group, var.view, binding_type));
             } else {
-                new_vars.push(Arc::clone(&var.variable));
-                new_binds.push([
+                matched.push(false);
+                new_vars.push((Arc::clone(&var.variable), var.bind_group, var.uniform));
+                new_binds.push((
                     self.variables.len() + new_vars_count,
-                    var.bind_group as usize,
-                ]);
+                    var.group,
+                    var.bind_group,
+                    var.view,
+                    binding_type,
+                ));
                 new_vars_count += 1;
             }
         }
 
-        for (sto_var, [_, var_bind]) in new_vars.iter().zip(&new_binds) {
-            let var = Arc::clone(&sto_var);
-            let var_lock = var.lock().unwrap();
-            let buffer_descriptor = var_lock.to_buffer_descriptor();
-
-            let buffer = self.executor.get_buffer(&buffer_descriptor);
+        // the buffers are created before storing anything, so that a poisoned lock leaves the storage as it was
+        let new_buffers = new_vars
+            .iter()
+            .map(|(sto_var, _, uniform)| {
+                let var_lock = lock_variable(sto_var)?;
+                let mut buffer_descriptor = var_lock.to_buffer_descriptor();
+                if *uniform {
+                    buffer_descriptor.usage |= wgpu::BufferUsages::UNIFORM;
+                }
+                Ok(self.executor.get_buffer(&buffer_descriptor))
+            })
+            .collect::<Result<Vec<_>, anyhow::Error>>()?;
 
+        for ((sto_var, var_bind, _), buffer) in new_vars.into_iter().zip(new_buffers) {
             self.variables.push(StoredVariable {
-                variable: Arc::clone(&sto_var),
-                binds: vec![*var_bind],
+                variable: sto_var,
+                binds: vec![var_bind as usize],
                 buffer_index: self.buffers.len(),
+                accumulator: false,
+                constant: false,
+                pending_upload: true,
             });
 
-            self.executor.write_buffer(&buffer, var_lock.byte_data());
-
             self.buffers.push(buffer);
         }
 
-        let mut operation_bind_layout_entries = Vec::new();
-        let mut operation_bind_entries = Vec::new();
-
-        for [var_pos, bind_group] in new_binds {
-            let sto_var = &mut self.variables[var_pos];
-            operation_bind_layout_entries
-                .push(sto_var.get_bind_group_layout_entry(bind_group as u32));
-            // let buffer = &buffers[sto_var.buffer_index];
-
-            operation_bind_entries.push(wgpu::BindGroupEntry {
-                binding: bind_group as u32,
-                resource: self.buffers[sto_var.buffer_index].as_entire_binding(),
-            });
+        let bindings: Vec<(u32, usize)> = new_binds
+            .iter()
+            .map(|&(var_pos, _, bind_group, _, _)| (bind_group, var_pos))
+            .collect();
+        let read_only: Vec<bool> = new_binds
+            .iter()
+            .map(|&(_, _, _, _, binding_type)| {
+                binding_type != wgpu::BufferBindingType::Storage { read_only: false }
+            })
+            .collect();
+        if self.verbose {
+            let groups: Vec<u32> = new_binds.iter().map(|&(_, group, _, _, _)| group).collect();
+            self.log_binds(function.entry_point, &bindings, &groups, &matched);
         }
 
-        let bind_layout_descriptor = wgpu::BindGroupLayoutDescriptor {
-            label: Some(f_label),
-            entries: &operation_bind_layout_entries,
+        let texture_indices = match f_textures
+            .iter()
+            .map(|bind| self.find_or_store_texture(&bind.texture))
+            .collect::<Result<Vec<_>, _>>()
+        {
+            Ok(texture_indices) => texture_indices,
+            Err(error) => {
+                // the variables and textures stored for the function would never be unwound otherwise
+                self.truncate_storage(storage);
+                return Err(error);
+            }
         };
-        let bind_layout = self.executor.get_bind_group_layout(&bind_layout_descriptor);
 
-        let bind_group_desriptor = wgpu::BindGroupDescriptor {
-            label: Some(f_label),
-            layout: &bind_layout,
-            entries: &operation_bind_entries,
-        };
-        let bind_group = self.executor.get_bind_group(&bind_group_desriptor);
+        // the layout entries and the entries of each bind group, by the index of the group
+        let mut operation_bind_layout_entries = vec![Vec::new(); group_count];
+        let mut operation_bind_entries = vec![Vec::new(); group_count];
 
-        let module_pos;
-        let entry_point_pos;
+        // one uniform for each tile, since the offset changes between them
+        let mut dimensions_buffer_indices = vec![None; tiles.len()];
+        if function.dimensions_binding.is_some() {
+            for (tile, buffer_index) in tiles.iter().zip(&mut dimensions_buffer_indices) {
+                let dimensions = dimensions_uniform(extent, tile.offset);
+                let buffer = self
+                    .executor
+                    .get_buffer_init(&wgpu::util::BufferInitDescriptor {
+                        label: Some(f_label),
+                        contents: bytemuck::cast_slice(&dimensions),
+                        usage: wgpu::BufferUsages::UNIFORM,
+                    });
+                self.buffers.push(buffer);
+                *buffer_index = Some(self.buffers.len() - 1);
+            }
+        }
 
-        if let Some(pos) = self
-            .modules
-            .iter()
-            .position(|existing_module| existing_module.shader == function.shader)
-        {
-            module_pos = pos;
-            if let Some(index) = self.modules[pos].find_entry_point(function.entry_point) {
-                entry_point_pos = index;
-            } else {
-                self.modules[pos].add_entry_point(function.entry_point);
-                entry_point_pos = self.modules[pos].entry_point.len() - 1;
+        // the flag of the sentinel, set by the shader when it runs
+        let sentinel_buffer_index = function.sentinel_binding.map(|_| {
+            let buffer = self
+                .executor
+                .get_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("sentinel"),
+                    contents: bytemuck::bytes_of(&0u32),
+                    usage: wgpu::BufferUsages::STORAGE
+                        | wgpu::BufferUsages::COPY_SRC
+                        | wgpu::BufferUsages::COPY_DST,
+                });
+            self.buffers.push(buffer);
+            self.buffers.len() - 1
+        });
+
+        // the uniform owned by the function, written once at its creation
+        let uniform_buffer_index = function.uniform_data.as_ref().map(|(_, data)| {
+            let buffer = self
+                .executor
+                .get_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some(f_label),
+                    contents: data,
+                    usage: wgpu::BufferUsages::UNIFORM,
+                });
+            self.buffers.push(buffer);
+            self.buffers.len() - 1
+        });
+
+        for (var_pos, group, bind_group, view, binding_type) in new_binds {
+            let sto_var = &self.variables[var_pos];
+            let mut layout_entry = sto_var.get_bind_group_layout_entry(
+                bind_group,
+                view.map(|(_, len)| len),
+                binding_type,
+            );
+            if let (
+                Some(_),
+                wgpu::BindingType::Buffer {
+                    min_binding_size, ..
+                },
+            ) = (function.dimensions_binding, &mut layout_entry.ty)
+            {
+                *min_binding_size = None;
             }
-        } else {
-            self.modules.push(Module {
-                shader: function.shader,
-                entry_point: vec![function.entry_point],
+            operation_bind_layout_entries[group as usize].push(layout_entry);
+            let buffer = &self.buffers[sto_var.buffer_index];
+
+            let resource = match view {
+                Some((offset, len)) => wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                    buffer,
+                    offset,
+                    size: NonZeroU64::new(len),
+                }),
+                None => buffer.as_entire_binding(),
+            };
+            operation_bind_entries[group as usize].push(wgpu::BindGroupEntry {
+                binding: bind_group,
+                resource,
             });
-            module_pos = self.modules.len() - 1;
-            entry_point_pos = 0;
         }
 
-        let shader = self.modules[module_pos].shader;
-        let entry_point = self.modules[module_pos].entry_point[entry_point_pos];
+        for (bind, &index) in f_textures.iter().zip(&texture_indices) {
+            let format = self.textures[index].texture.format();
+            operation_bind_layout_entries[0].push(texture_layout_entry(
+                bind.binding,
+                bind.access,
+                format,
+            ));
+            operation_bind_entries[0].push(wgpu::BindGroupEntry {
+                binding: bind.binding,
+                resource: wgpu::BindingResource::TextureView(&self.textures[index].view),
+            });
+        }
 
-        let pipeline_layout_descriptor = wgpu::PipelineLayoutDescriptor {
-            label: Some(f_label),
-            bind_group_layouts: &[&bind_layout],
-            push_constant_ranges: &[],
-        };
+        if let Some(binding) = function.dimensions_binding {
+            operation_bind_layout_entries[0].push(dimensions_layout_entry(binding));
+        }
 
-        let pipeline_layout = self
-            .executor
-            .get_pipeline_layout(&pipeline_layout_descriptor);
+        if let (Some(binding), Some(_)) = (function.sentinel_binding, sentinel_buffer_index) {
+            operation_bind_layout_entries[0].push(wgpu::BindGroupLayoutEntry {
+                binding,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Storage { read_only: false },
+                    has_dynamic_offset: false,
+                    min_binding_size: NonZeroU64::new(std::mem::size_of::<u32>() as u64),
+                },
+                count: None,
+            });
+        }
+        if let (Some(binding), Some(index)) = (function.sentinel_binding, sentinel_buffer_index) {
+            operation_bind_entries[0].push(wgpu::BindGroupEntry {
+                binding,
+                resource: self.buffers[index].as_entire_binding(),
+            });
+        }
+        if let (Some((binding, data)), Some(index)) = (&function.uniform_data, uniform_buffer_index)
+        {
+            operation_bind_layout_entries[0].push(wgpu::BindGroupLayoutEntry {
+                binding: *binding,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: NonZeroU64::new(data.len() as u64),
+                },
+                count: None,
+            });
+            operation_bind_entries[0].push(wgpu::BindGroupEntry {
+                binding: *binding,
+                resource: self.buffers[index].as_entire_binding(),
+            });
+        }
+
+        let bind_layouts: Vec<Arc<wgpu::BindGroupLayout>> = operation_bind_layout_entries
+            .iter()
+            .zip(&group_labels)
+            .map(|(entries, &label)| {
+                self.executor
+                    .get_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                        label: Some(label),
+                        entries,
+                    })
+            })
+            .collect();
+
+        // the bind groups of each tile, only the group 0 changes between them
+        let mut bind_groups = Vec::new();
+        for buffer_index in dimensions_buffer_indices {
+            let mut tile_groups = Vec::new();
+            for (group, (bind_layout, group_entries)) in
+                bind_layouts.iter().zip(&operation_bind_entries).enumerate()
+            {
+                let mut entries = group_entries.clone();
+                if let (0, Some(binding), Some(index)) =
+                    (group, function.dimensions_binding, buffer_index)
+                {
+                    entries.push(wgpu::BindGroupEntry {
+                        binding,
+                        resource: self.buffers[index].as_entire_binding(),
+                    });
+                }
+                let bind_group_desriptor = wgpu::BindGroupDescriptor {
+                    label: Some(group_labels[group]),
+                    layout: bind_layout,
+                    entries: &entries,
+                };
+                tile_groups.push(self.executor.get_bind_group(&bind_group_desriptor));
+            }
+            bind_groups.push(tile_groups);
+        }
 
-        let shader_module = self.executor.get_shader_module(shader);
+        // (module, entry point) of each stage, the ones after the first are given by [`Function::fused`]
+        let stages: Vec<[usize; 2]> =
+            std::iter::once((function.shader.clone(), function.entry_point))
+                .chain(function.fused.iter().cloned())
+                .map(|(shader, entry_point)| self.find_or_add_module(shader, entry_point))
+                .collect();
 
-        let pipeline_descriptor = wgpu::ComputePipelineDescriptor {
-            label: Some(f_label),
-            layout: Some(&pipeline_layout),
-            module: &shader_module,
-            entry_point,
-        };
-        let pipeline: wgpu::ComputePipeline = self.executor.get_pipeline(&pipeline_descriptor);
+        // the pipelines are shared with the other functions on the same entry point and bindings
+        let pipelines: Vec<Arc<wgpu::ComputePipeline>> = stages
+            .iter()
+            .map(|&[module_pos, entry_point_pos]| {
+                let module = &self.modules[module_pos];
+                self.executor.get_cached_pipeline(
+                    &module.shader,
+                    module.entry_point[entry_point_pos],
+                    &operation_bind_layout_entries,
+                    function.push_constants.len() as u32,
+                )
+            })
+            .collect();
+        let query_set = self
+            .executor
+            .timestamp_query_set(Some(function.entry_point));
         {
             let mut compute_pass =
                 command_encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
                     label: Some(f_label),
-                    timestamp_writes: None,
+                    timestamp_writes: query_set.as_ref().map(|query_set| {
+                        wgpu::ComputePassTimestampWrites {
+                            query_set,
+                            beginning_of_pass_write_index: Some(0),
+                            end_of_pass_write_index: Some(1),
+                        }
+                    }),
                 });
-            // if let Some(_) = bind_group {
-            compute_pass.set_bind_group(0, &bind_group, &[]);
-            // }
-
-            compute_pass.set_pipeline(&pipeline);
-            compute_pass.dispatch_workgroups(workgroups[0], workgroups[1], workgroups[2])
+            for pipeline in &pipelines {
+                compute_pass.set_pipeline(pipeline);
+                if !function.push_constants.is_empty() {
+                    compute_pass.set_push_constants(0, &function.push_constants);
+                }
+                for (tile_groups, tile) in bind_groups.iter().zip(&tiles) {
+                    for (group, bind_group) in tile_groups.iter().enumerate() {
+                        compute_pass.set_bind_group(group as u32, bind_group, &[]);
+                    }
+                    compute_pass.dispatch_workgroups(
+                        tile.workgroups[0],
+                        tile.workgroups[1],
+                        tile.workgroups[2],
+                    )
+                }
+            }
         }
 
+        let recorded = RecordedPass {
+            pipelines,
+            push_constants: function.push_constants,
+            dispatches: bind_groups
+                .into_iter()
+                .zip(tiles.iter().map(|tile| tile.workgroups))
+                .collect(),
+        };
+
+        let dispatches = stages
+            .iter()
+            .flat_map(|&[module, entry_point]| {
+                tiles.iter().map(move |tile| (module, entry_point, tile))
+            })
+            .map(|(module, entry_point, tile)| Dispatch {
+                module,
+                entry_point,
+                workgroups: tile.workgroups,
+                bindings: bindings.clone(),
+                read_only: read_only.clone(),
+                textures: texture_indices
+                    .iter()
+                    .zip(&f_textures)
+                    .map(|(&index, bind)| (index, bind.access))
+                    .collect(),
+            })
+            .collect();
+        let handle = FunctionHandle(NEXT_HANDLE.fetch_add(1, Ordering::Relaxed));
+        if let Some(query_set) = query_set {
+            let buffer = self.executor.get_buffer(&wgpu::BufferDescriptor {
+                label: Some("timestamps"),
+                size: 2 * std::mem::size_of::<u64>() as u64,
+                usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            });
+            command_encoder.resolve_query_set(&query_set, 0..2, &buffer, 0);
+            self.buffers.push(buffer);
+            self.timestamp_queries.push(TimestampQuery {
+                handle,
+                entry_point: function.entry_point.to_owned(),
+                query_set,
+                buffer_index: self.buffers.len() - 1,
+            });
+        }
+        if let Some(buffer_index) = sentinel_buffer_index {
+            self.sentinels.push(Sentinel {
+                handle,
+                entry_point: function.entry_point.to_owned(),
+                buffer_index,
+            });
+        }
+        self.recorded.insert(handle, recorded);
+        if !outputs.is_empty() {
+            self.outputs.insert(handle, outputs);
+        }
+        self.added.push((handle, storage));
         self.solvers.push(Solver::Serial {
             command_encoder,
             variables,
+            dispatches,
+            handle: Some(handle),
         });
+        Ok(handle)
     }
 
-    /// This method executes the calculation defined in [`Algorithm`] on the GPU
-    ///
-    /// Notice this method consumes the list of operations sheduled during the [`Function`]s additions
-    /// and performs all the calculations on the GPU as defined in the shaders on the [`Variable`]s bond to
-    /// the bind groups as hey were defined in the [`Function`].
+    /// This method removes the last [`Function`] added and not yet run, giving back its handle
     ///
-    /// This method doesn't perform any ouput operation, i.e. once the calculation have been run, you need to extract the
-    /// [`Variable`] using the [`Algorithm::get_output_unmap`] method.
-    /// This is done to assure that only the needed variables are brought back to the CPU memory, not spending any more time than needed on this
-    /// operation.
+    /// The [`Function`] is unscheduled as if it was never added, and the [`Variable`]s, buffers and textures stored for
+    /// it by [`Algorithm::add_fun`] are dropped as well, unless an operation added later (e.g. a
+    /// [`Algorithm::read_variable`]) still uses them: in that case they're kept, and only the [`Function`] is removed.
+    /// Calling it again removes the [`Function`] added before, so that a mistake can be undone without rebuilding the
+    /// [`Algorithm`], see also [`Algorithm::clear`].
     ///
-    /// Takes a mutable reference to `self`
-    pub async fn run(&mut self) -> Result<(), anyhow::Error> {
-        for solver in &mut self.solvers.drain(0..) {
-            match solver {
-                Solver::Serial {
-                    command_encoder, ..
+    /// # Errors
+    /// - if there's no [`Function`] scheduled
+    pub fn pop_last_function(&mut self) -> Result<FunctionHandle, anyhow::Error> {
+        let (handle, [variables, buffers, textures]) = loop {
+            match self.added.pop() {
+                Some((handle, storage)) if self.recorded.contains_key(&handle) => {
+                    break (handle, storage)
+                }
+                Some(_) => continue,
+                None => {
+                    return Err(anyhow!(
+                        "{:?} Algorithm has no scheduled Function to remove",
+                        self.label
+                    ))
+                }
+            }
+        };
+        self.solvers.retain(
+            |solver| !matches!(solver, Solver::Serial { handle: Some(h), .. } if *h == handle),
+        );
+        self.recorded.remove(&handle);
+        self.outputs.remove(&handle);
+        self.sentinels.retain(|sentinel| sentinel.handle != handle);
+        self.timestamp_queries
+            .retain(|query| query.handle != handle);
+
+        // the storage added after the function is unwound only if nothing left refers to it
+        let mut variables_used: Vec<usize> = self.last_reads.clone();
+        let mut textures_used = Vec::new();
+        for solver in &self.solvers {
+            if let Solver::Serial { variables, .. } = solver {
+                variables_used.extend(variables.iter().filter_map(|var| self.find_variable(var)));
+            }
+            for (resource, _, _) in solver.accesses() {
+                match resource {
+                    Resource::Variable(index) => variables_used.push(index),
+                    Resource::Texture(index) => textures_used.push(index),
+                }
+            }
+        }
+        let buffers_used = self.variables[..variables.min(self.variables.len())]
+            .iter()
+            .map(|sto_var| sto_var.buffer_index)
+            .chain(self.sentinels.iter().map(|sentinel| sentinel.buffer_index))
+            .chain(
+                self.timestamp_queries
+                    .iter()
+                    .map(|query| query.buffer_index),
+            );
+        let unused = variables_used.iter().all(|&index| index < variables)
+            && textures_used.iter().all(|&index| index < textures);
+        let unused = unused && buffers_used.into_iter().all(|index| index < buffers);
+        if unused {
+            self.truncate_storage([variables, buffers, textures]);
+        }
+        Ok(handle)
+    }
+
+    // drops the variables, buffers and textures stored after the given counts of each
+    fn truncate_storage(&mut self, [variables, buffers, textures]: [usize; 3]) {
+        self.variables.truncate(variables);
+        self.buffers.truncate(buffers);
+        self.textures.truncate(textures);
+        self.unverified_uploads.retain(|resource| match *resource {
+            Resource::Variable(index) => index < variables,
+            Resource::Texture(index) => index < textures,
+        });
+    }
+
+    /// This method removes everything added to the [`Algorithm`], to start again from scratch
+    ///
+    /// All the scheduled operations are dropped together with the [`Variable`]s, buffers and textures stored, as well
+    /// as what [`Algorithm::rerun`] would run again. The [`Executor`] is kept, with its cached pipelines, and so are
+    /// the label, the settings (like [`Algorithm::set_verbose`]) and the workgroup sizes found by
+    /// [`Algorithm::autotune`]. A run still executing on the GPU isn't affected, see [`Algorithm::poll_run`].
+    pub fn clear(&mut self) {
+        let mut cleared = Algorithm::from_executor(Arc::clone(&self.executor), None);
+        cleared.label = self.label.take();
+        cleared.verbose = self.verbose;
+        cleared.fuse_serial = self.fuse_serial;
+        cleared.verify_uploads = self.verify_uploads;
+        cleared.tuned_sizes = std::mem::take(&mut self.tuned_sizes);
+        cleared.last_run = self.last_run.take();
+        cleared.last_run_stats = std::mem::take(&mut self.last_run_stats);
+        *self = cleared;
+    }
+
+    /// This method adds `iterations` times the [`Function`] given by `function` on the sides of `ping_pong`
+    ///
+    /// Before each iteration the [`Function`] is built by `function` from the current state of the [`PingPong`]
+    /// (usually with [`PingPong::binds`]), and after it the sides are swapped, so that each iteration reads what the
+    /// previous one wrote. Both the sides share their buffers among all the iterations.
+    /// Once the iterations are done the result is in [`PingPong::read_side`].
+    ///
+    /// Returns the [`FunctionHandle`]s of the iterations, in order.
+    ///
+    /// # Example
+    /// ```ignore
+    /// algorithm.add_iterations(&mut ping_pong, 100, |sides| {
+    ///     Function::new(&shader, "step", sides.binds(0, 1))
+    /// })?;
+    /// ```
+    ///
+    /// # Errors
+    /// - all the errors of [`Algorithm::add_fun`], in which case the sides are left as after the last iteration added
+    pub fn add_iterations(
+        &mut self,
+        ping_pong: &mut PingPong<V>,
+        iterations: usize,
+        function: impl Fn(&PingPong<V>) -> Function<'a, V>,
+    ) -> Result<Vec<FunctionHandle>, anyhow::Error> {
+        let mut handles = Vec::with_capacity(iterations);
+        for _ in 0..iterations {
+            handles.push(self.add_fun(function(ping_pong))?);
+            ping_pong.swap();
+        }
+        Ok(handles)
+    }
+
+    /// This method adds a [`Function`] to the [`Algorithm`] like [`Algorithm::add_fun`], dispatching it `n` times in a row
+    ///
+    /// The dispatches are recorded in the same compute pass, sharing the pipeline and the bind group, so an iterative
+    /// method (e.g. Jacobi or gradient descent) working in place costs a single [`Function`] however many iterations
+    /// it takes. Each iteration sees what the previous one wrote, and the buffers stay on the GPU in between: nothing
+    /// is read back until the [`Algorithm::read_variable`] following it. All the stages of a [`Function::fused`] are
+    /// repeated, in order.
+    ///
+    /// For the iterations reading and writing different buffers see [`Algorithm::add_iterations`].
+    ///
+    /// # Example
+    /// ```ignore
+    /// algorithm.repeat(Function::new(&shader, "jacobi_step", bindings), 100)?;
+    /// algorithm.read_variable(&solution)?;
+    /// ```
+    ///
+    /// # Errors
+    /// - if `n` is zero
+    /// - all the errors of [`Algorithm::add_fun`]
+    pub fn repeat(
+        &mut self,
+        mut function: Function<'a, V>,
+        n: usize,
+    ) -> Result<FunctionHandle, anyhow::Error> {
+        if n == 0 {
+            return Err(anyhow!(
+                "Function {} can't be repeated zero times",
+                function.entry_point
+            ));
+        }
+        let stages: Vec<(ShaderRef<'a>, &'a str)> =
+            std::iter::once((function.shader.clone(), function.entry_point))
+                .chain(function.fused.iter().cloned())
+                .collect();
+        function.fused = stages
+            .iter()
+            .cloned()
+            .cycle()
+            .skip(1)
+            .take(stages.len() * n - 1)
+            .collect();
+        self.add_fun(function)
+    }
+
+    /// This method adds a [`Function`] to the [`Algorithm`] like [`Algorithm::add_fun`], dispatching it in tiles
+    ///
+    /// The extent of the first [`Variable`] (its [`Variable::dimension_sizes`]) is covered by dispatches of at most
+    /// `tile_size` workgroups, each with its own offset (see [`Function::with_dispatch_offset`], the offsets of the
+    /// tiles are added to the one of the [`Function`]). This allows to work on grids bigger than the limit of
+    /// workgroups of a single dispatch, which is 65535 on most devices.
+    /// All the tiles are recorded in the same compute pass, sharing the pipeline and the variables buffers.
+    ///
+    /// # Errors
+    /// - if the [`Function`] has no dimensions uniform (see [`Function::with_dimensions`])
+    /// - if any element of `tile_size` is zero or over the device limit of workgroups per dimension
+    /// - all the errors of [`Algorithm::add_fun`]
+    pub fn add_function_tiled(
+        &mut self,
+        mut function: Function<'a, V>,
+        tile_size: [u32; 3],
+    ) -> Result<FunctionHandle, anyhow::Error> {
+        function.tile_size = Some(tile_size);
+        self.add_fun(function)
+    }
+
+    /// This method adds a [`Function`] to the [`Algorithm`] like [`Algorithm::add_fun`], splitting the dispatch in chunks
+    /// which fit the device limits
+    ///
+    /// It's [`Algorithm::add_function_tiled`] with the biggest tiles the device allows
+    /// ([`wgpu::Limits::max_compute_workgroups_per_dimension`]), so that a grid too big for a single dispatch runs in
+    /// as few dispatches as possible, while a grid within the limits runs in a single one. The shader gets the offset
+    /// of each chunk from the dimensions uniform, as for [`Algorithm::add_function_tiled`].
+    ///
+    /// # Errors
+    /// - if the [`Function`] has no dimensions uniform (see [`Function::with_dimensions`])
+    /// - all the errors of [`Algorithm::add_fun`]
+    pub fn add_function_chunked(
+        &mut self,
+        function: Function<'a, V>,
+    ) -> Result<FunctionHandle, anyhow::Error> {
+        let max = self
+            .executor
+            .with_device(|device, _| device.limits().max_compute_workgroups_per_dimension);
+        self.add_function_tiled(function, [max; 3])
+    }
+
+    /// This method merges the operations of `other` into `self`, to be executed after the ones already scheduled
+    ///
+    /// The [`Function`]s of `other` keep their order, and their [`FunctionHandle`]s stay valid for
+    /// [`Algorithm::run_selected`] on `self`. The [`Variable`]s (and [`TextureVariable`]s) of both are deduplicated by
+    /// identity: a variable used in both [`Algorithm`]s keeps the buffer of `self`, which is copied to the buffer
+    /// used by each [`Function`] of `other` before it runs and copied back after it, so that the data passes
+    /// between the two stages. The other variables are moved to `self` as they are, including their pending uploads.
+    ///
+    /// Both [`Algorithm`]s need to share the same [`Executor`], see [`Algorithm::with_shared_executor`].
+    ///
+    /// # Errors
+    /// - if the [`Algorithm`]s don't share the same [`Executor`]
+    /// - if a shared [`Variable`] is a constant in either [`Algorithm`] (see [`Algorithm::add_constant_mapped`]),
+    ///   or any of its buffers has no [`wgpu::BufferUsages::COPY_DST`] usage, since they can't be copied into
+    pub fn append(&mut self, other: Algorithm<'a, V>) -> Result<(), anyhow::Error> {
+        if !Arc::ptr_eq(&self.executor, &other.executor) {
+            return Err(anyhow!(
+                "{:?} Algorithm can't be appended to {:?} Algorithm, they don't share the same Executor",
+                other.label,
+                self.label
+            ));
+        }
+        for sto_var in &other.variables {
+            if let Some(index) = self.find_variable(&sto_var.variable) {
+                if sto_var.constant || self.variables[index].constant {
+                    return Err(anyhow!(
+                        "{:?} Algorithm can't be appended to {:?} Algorithm, variable {:?} is a constant shared by both",
+                        other.label,
+                        self.label,
+                        variable_name(&sto_var.variable)
+                    ));
+                }
+                // the shared buffers are copied into one another around the functions of `other`
+                let usages = [
+                    self.buffers[self.variables[index].buffer_index].usage(),
+                    other.buffers[sto_var.buffer_index].usage(),
+                ];
+                if let Some(usage) = usages
+                    .into_iter()
+                    .find(|usage| !usage.contains(wgpu::BufferUsages::COPY_DST))
+                {
+                    return Err(anyhow!(
+                        "{:?} Algorithm can't be appended to {:?} Algorithm, variable {:?} shared by both has a buffer \
+                        with usage {:?}, which can't be copied into",
+                        other.label,
+                        self.label,
+                        variable_name(&sto_var.variable),
+                        usage
+                    ));
+                }
+            }
+        }
+
+        // the buffers of the shared variables are kept, since the recorded operations of `other` use them
+        let buffer_offset = self.buffers.len();
+        self.buffers.extend(other.buffers);
+        // the index in `self` of each variable of `other`
+        let mut variable_indices = Vec::with_capacity(other.variables.len());
+        let mut shared_variables = Vec::new();
+        for sto_var in other.variables {
+            let buffer_index = sto_var.buffer_index + buffer_offset;
+            match self.find_variable(&sto_var.variable) {
+                Some(index) => {
+                    shared_variables.push(SharedVariable {
+                        variable: index,
+                        own_buffer: self.variables[index].buffer_index,
+                        their_buffer: buffer_index,
+                    });
+                    self.variables[index].binds.extend(sto_var.binds);
+                    variable_indices.push(index);
+                }
+                None => {
+                    self.variables.push(StoredVariable {
+                        buffer_index,
+                        ..sto_var
+                    });
+                    variable_indices.push(self.variables.len() - 1);
+                }
+            }
+        }
+
+        // the (own, their) indices of the shared textures, the duplicated texture is kept after the one of
+        // `self`, which is the one found by the lookups
+        let mut shared_textures = Vec::new();
+        // the index in `self` of each texture of `other`, the one of `self` for the shared ones
+        let mut texture_indices = Vec::with_capacity(other.textures.len());
+        for mut sto_texture in other.textures {
+            if let Some(index) = self.find_texture(&sto_texture.variable) {
+                shared_textures.push((index, self.textures.len()));
+                texture_indices.push(index);
+                sto_texture.pending_upload = false;
+            } else {
+                texture_indices.push(self.textures.len());
+            }
+            self.textures.push(sto_texture);
+        }
+
+        // the index in `self` of each module of `other`, with the indices of its entry points
+        let mut module_indices = Vec::with_capacity(other.modules.len());
+        for module in other.modules {
+            let module_pos = match self
+                .modules
+                .iter()
+                .position(|existing_module| existing_module.shader == module.shader)
+            {
+                Some(pos) => pos,
+                None => {
+                    self.modules.push(Module::new(module.shader));
+                    self.modules.len() - 1
+                }
+            };
+            let entry_points = module
+                .entry_point
+                .iter()
+                .map(|&e_p| {
+                    self.modules[module_pos]
+                        .find_entry_point(e_p)
+                        .unwrap_or_else(|| self.modules[module_pos].add_entry_point(e_p))
+                })
+                .collect();
+            module_indices.push((module_pos, entry_points));
+        }
+
+        for mut solver in other.solvers {
+            solver.reindex(&module_indices, &variable_indices, &texture_indices);
+            // the copies get the handle of the function, so that they're selected together with it
+            let (handle, variables, textures) = match &solver {
+                Solver::Serial {
+                    handle: Some(handle),
+                    dispatches,
+                    ..
+                } => (
+                    *handle,
+                    dispatches
+                        .iter()
+                        .flat_map(|dispatch| dispatch.bindings.iter().map(|&(_, var)| var))
+                        .collect::<HashSet<usize>>(),
+                    dispatches
+                        .iter()
+                        .flat_map(|dispatch| dispatch.textures.iter().map(|&(texture, _)| texture))
+                        .collect::<HashSet<usize>>(),
+                ),
+                _ => {
+                    self.solvers.push(solver);
+                    continue;
+                }
+            };
+            let shared: Vec<&SharedVariable> = shared_variables
+                .iter()
+                .filter(|shared| variables.contains(&shared.variable))
+                .collect();
+            let used_textures: Vec<(usize, usize)> = shared_textures
+                .iter()
+                .filter(|(own, _)| textures.contains(own))
+                .copied()
+                .collect();
+            if shared.is_empty() && used_textures.is_empty() {
+                self.solvers.push(solver);
+                continue;
+            }
+
+            let mut copy_in = self.executor.create_encoder(Some("append copy in"));
+            let mut copy_out = self.executor.create_encoder(Some("append copy out"));
+            for shared in shared {
+                let own = &self.buffers[shared.own_buffer];
+                let theirs = &self.buffers[shared.their_buffer];
+                let size = own.size().min(theirs.size());
+                copy_in.copy_buffer_to_buffer(own, 0, theirs, 0, size);
+                copy_out.copy_buffer_to_buffer(theirs, 0, own, 0, size);
+            }
+            for (own, theirs) in used_textures {
+                let own = &self.textures[own].texture;
+                let theirs = &self.textures[theirs].texture;
+                copy_in.copy_texture_to_texture(
+                    own.as_image_copy(),
+                    theirs.as_image_copy(),
+                    own.size(),
+                );
+                copy_out.copy_texture_to_texture(
+                    theirs.as_image_copy(),
+                    own.as_image_copy(),
+                    own.size(),
+                );
+            }
+            let copy_solver = |command_encoder| Solver::Serial {
+                command_encoder,
+                variables: Vec::new(),
+                dispatches: Vec::new(),
+                handle: Some(handle),
+            };
+            self.solvers.push(copy_solver(copy_in));
+            self.solvers.push(solver);
+            self.solvers.push(copy_solver(copy_out));
+        }
+
+        self.sentinels
+            .extend(other.sentinels.into_iter().map(|sentinel| Sentinel {
+                buffer_index: sentinel.buffer_index + buffer_offset,
+                ..sentinel
+            }));
+        self.timestamp_queries
+            .extend(
+                other
+                    .timestamp_queries
+                    .into_iter()
+                    .map(|query| TimestampQuery {
+                        buffer_index: query.buffer_index + buffer_offset,
+                        ..query
+                    }),
+            );
+        self.recorded.extend(other.recorded);
+        self.outputs.extend(other.outputs);
+        // the storage of `other` is interleaved with the one of `self`, so it's never unwound
+        let storage = [
+            self.variables.len(),
+            self.buffers.len(),
+            self.textures.len(),
+        ];
+        self.added
+            .extend(other.added.into_iter().map(|(handle, _)| (handle, storage)));
+        self.pending_stats.bytes_uploaded += other.pending_stats.bytes_uploaded;
+        for (key, size) in other.tuned_sizes {
+            self.tuned_sizes.entry(key).or_insert(size);
+        }
+        Ok(())
+    }
+
+    /// This method turns the [`Algorithm`] into a [`CompiledAlgorithm`], which replays all the scheduled [`Function`]s
+    /// at each [`CompiledAlgorithm::step`]
+    ///
+    /// The pipelines and bind groups created by [`Algorithm::add_fun`] are kept, so that a step doesn't create any
+    /// GPU object, and the data of the [`Variable`]s not yet uploaded is written once here (see [`Algorithm::upload_all`]).
+    /// The [`Function`]s are replayed in the order they're scheduled.
+    ///
+    /// # Example
+    /// ```ignore
+    /// algorithm.add_fun(Function::new(&shader, "add_1", vec![VariableBind::new(Arc::clone(&var), 0)]))?;
+    /// let mut compiled = algorithm.compile()?;
+    /// for _ in 0..1000 {
+    ///     compiled.step()?;
+    /// }
+    /// compiled.get_output(&var).await?;
+    /// ```
+    ///
+    /// # Errors
+    /// - if an operation which isn't a [`Function`] is scheduled, like a [`Algorithm::read_variable`], a
+    ///   [`Algorithm::reset_accumulator`] or the copies of the variables shared by an [`Algorithm::append`]
+    pub fn compile(mut self) -> Result<CompiledAlgorithm<V>, anyhow::Error> {
+        self.upload_all()?;
+        let mut passes = Vec::new();
+        for solver in std::mem::take(&mut self.solvers) {
+            solver.take_passes(&mut self.recorded, &mut passes)?;
+        }
+        Ok(CompiledAlgorithm {
+            variables: self.variables,
+            buffers: self.buffers,
+            _textures: self.textures,
+            passes,
+            label: self.label,
+            executor: self.executor,
+        })
+    }
+
+    /// This method writes the data of all the [`Variable`]s added and not yet uploaded to their GPU buffers
+    ///
+    /// The writes are batched in a single pass over the variables, each of them locked only once, and go to the
+    /// queue of the [`Executor`] to be submitted with the following operations. It's called by [`Algorithm::run`],
+    /// but it can be called earlier to upload the inputs as soon as they're all added.
+    ///
+    /// When more than one variable is pending and they add up to at least 1 MiB, the variables are split among
+    /// as many threads as [`std::thread::available_parallelism`], each locking and writing its share, so that
+    /// the copies of the host data are done concurrently. All the threads are joined before this method returns,
+    /// and since a write to the queue is applied before any command buffer submitted after it, the [`Function`]s
+    /// run afterwards always see the data uploaded here, whichever thread wrote it.
+    ///
+    /// The data uploaded is the one the [`Variable`]s hold at the time of this call.
+    ///
+    /// # Errors
+    /// - if the [`Variable::byte_data`] of a [`Variable`] doesn't have as many bytes as its [`Variable::byte_size`], or
+    ///   its lock is poisoned. All the other variables are uploaded, the failed ones are left to the next call and
+    ///   the error of the first one is returned
+    /// - if the [`TextureVariable::byte_data`] of a texture doesn't fit its texture, e.g. because its dimensions
+    ///   changed after it was added
+    pub fn upload_all(&mut self) -> Result<(), anyhow::Error> {
+        let pending: Vec<usize> = (0..self.variables.len())
+            .filter(|&index| self.variables[index].pending_upload)
+            .collect();
+        let total_bytes: u64 = pending
+            .iter()
+            .map(|&index| self.buffers[self.variables[index].buffer_index].size())
+            .sum();
+        let write = |index: usize| -> Result<u64, anyhow::Error> {
+            let sto_var = &self.variables[index];
+            let var_lock = lock_variable(&sto_var.variable)?;
+            let buffer = &self.buffers[sto_var.buffer_index];
+            let data = buffer_upload_data(&*var_lock, buffer)?;
+            self.executor.write_buffer(buffer, data);
+            Ok(data.len() as u64)
+        };
+        let threads = std::thread::available_parallelism()
+            .map_or(1, usize::from)
+            .min(pending.len());
+        let results: Vec<Result<u64, anyhow::Error>> = if threads > 1
+            && total_bytes >= PARALLEL_UPLOAD_BYTES
+        {
+            let chunk_size = pending.len().div_ceil(threads);
+            std::thread::scope(|scope| {
+                let handles: Vec<_> = pending
+                    .chunks(chunk_size)
+                    .map(|chunk| {
+                        scope.spawn(|| chunk.iter().map(|&index| write(index)).collect::<Vec<_>>())
+                    })
+                    .collect();
+                // a panicked thread fails all the uploads of its chunk, which stay pending
+                handles
+                    .into_iter()
+                    .zip(pending.chunks(chunk_size))
+                    .flat_map(|(handle, chunk)| {
+                        handle.join().unwrap_or_else(|_| {
+                            chunk
+                                .iter()
+                                .map(|&index| {
+                                    Err(anyhow!(
+                                        "The thread uploading variable {:?} of {:?} Algorithm panicked",
+                                        variable_name(&self.variables[index].variable),
+                                        self.label
+                                    ))
+                                })
+                                .collect()
+                        })
+                    })
+                    .collect()
+            })
+        } else {
+            pending.iter().map(|&index| write(index)).collect()
+        };
+        let mut first_error = None;
+        for (index, result) in pending.into_iter().zip(results) {
+            match result {
+                Ok(bytes) => {
+                    self.pending_stats.bytes_uploaded += bytes;
+                    self.variables[index].pending_upload = false;
+                    if self.verify_uploads {
+                        self.unverified_uploads.push(Resource::Variable(index));
+                    }
+                }
+                Err(error) => {
+                    first_error.get_or_insert(error);
+                }
+            }
+        }
+        if let Some(error) = first_error {
+            return Err(error);
+        }
+        for (index, sto_texture) in self
+            .textures
+            .iter_mut()
+            .enumerate()
+            .filter(|(_, sto_texture)| sto_texture.pending_upload)
+        {
+            let texture_lock = lock_texture(&sto_texture.variable)?;
+            let data = texture_lock.byte_data();
+            self.executor.write_texture(&sto_texture.texture, data)?;
+            self.pending_stats.bytes_uploaded += data.len() as u64;
+            drop(texture_lock);
+            sto_texture.pending_upload = false;
+            if self.verify_uploads {
+                self.unverified_uploads.push(Resource::Texture(index));
+            }
+        }
+        Ok(())
+    }
+
+    /// Enables the verification of the uploads, which reads back every buffer written to compare it with the host data
+    ///
+    /// When set, every [`Algorithm::run`] reads back the buffers (and textures) just written by [`Algorithm::upload_all`]
+    /// and errors if their content on the GPU differs from the [`Variable::byte_data`] they were written with.
+    /// It's a debugging aid to find out whether the data arrived corrupted on the GPU (e.g. because of a driver bug or
+    /// a wrong alignment), and it's very slow, since it reads back all the inputs: it should never be left on.
+    pub fn debug_verify_uploads(&mut self, verify: bool) {
+        self.verify_uploads = verify;
+    }
+
+    // reads back the uploads not yet verified and compares them with the host data
+    async fn check_uploads(&mut self) -> Result<(), anyhow::Error> {
+        for resource in std::mem::take(&mut self.unverified_uploads) {
+            let (on_gpu, on_host) = match resource {
+                Resource::Variable(index) => {
+                    let sto_var = &self.variables[index];
+                    let on_gpu = self
+                        .executor
+                        .read_buffer(&self.buffers[sto_var.buffer_index])
+                        .await;
+                    let on_host = lock_variable(&sto_var.variable)?.byte_data().to_vec();
+                    (on_gpu, on_host)
+                }
+                Resource::Texture(index) => {
+                    let sto_texture = &self.textures[index];
+                    let on_gpu = self.executor.read_texture(&sto_texture.texture).await?;
+                    let on_host = lock_texture(&sto_texture.variable)?.byte_data().to_vec();
+                    (on_gpu, on_host)
+                }
+            };
+            // the buffer can be longer than the data, e.g. for the alignment
+            let mismatch = if on_gpu.len() < on_host.len() {
+                Some(on_gpu.len())
+            } else {
+                on_host
+                    .iter()
+                    .zip(&on_gpu)
+                    .position(|(host, gpu)| host != gpu)
+            };
+            if let Some(offset) = mismatch {
+                return Err(anyhow!(
+                    "Upload of {} differs from its data starting at byte {}",
+                    self.resource_name(resource),
+                    offset
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// This method executes the calculation defined in [`Algorithm`] on the GPU
+    ///
+    /// Notice this method consumes the list of operations sheduled during the [`Function`]s additions
+    /// and performs all the calculations on the GPU as defined in the shaders on the [`Variable`]s bond to
+    /// the bind groups as hey were defined in the [`Function`].
+    ///
+    /// This method doesn't perform any ouput operation, i.e. once the calculation have been run, you need to extract the
+    /// [`Variable`] using the [`Algorithm::get_output_unmap`] method.
+    /// This is done to assure that only the needed variables are brought back to the CPU memory, not spending any more time than needed on this
+    /// operation.
+    ///
+    /// Takes a mutable reference to `self`
+    ///
+    /// # Errors
+    /// - if the data of a [`Variable`] to upload doesn't match its byte size (see [`Algorithm::upload_all`]).
+    ///   The scheduled operations are kept, so the run can be retried once the data is fixed
+    /// - if an upload differs from its data, when verified (see [`Algorithm::debug_verify_uploads`])
+    /// - if two operations scheduled to run in parallel access the same [`Variable`] and one of them writes it
+    /// - if the data read back into a [`Variable`] doesn't match its byte size, or fails [`Variable::read_data`]
+    /// - if the lock of a [`Variable`] to upload or read back is poisoned, i.e. a thread panicked while holding it
+    pub async fn run(&mut self) -> Result<(), anyhow::Error> {
+        self.execute_solvers(|algorithm| std::mem::take(&mut algorithm.solvers), None)
+            .await?;
+        Ok(())
+    }
+
+    /// This method executes only the selected [`Function`]s among the scheduled ones, like [`Algorithm::run`]
+    ///
+    /// The [`Function`]s of the `handles` (given by [`Algorithm::add_fun`]) are executed in the order they were added,
+    /// together with all the other scheduled operations which are not [`Function`]s (e.g. the reads of
+    /// [`Algorithm::read_variable`]), so that the results of the selected stages can be read back.
+    /// The [`Function`]s not selected stay scheduled, with their handles still valid for the following runs.
+    ///
+    /// # Errors
+    /// - if a handle doesn't belong to a scheduled [`Function`], e.g. because it was already run
+    /// - all the errors of [`Algorithm::run`]
+    pub async fn run_selected(&mut self, handles: &[FunctionHandle]) -> Result<(), anyhow::Error> {
+        let scheduled: HashSet<FunctionHandle> =
+            self.solvers.iter().filter_map(Solver::handle).collect();
+        if let Some(handle) = handles.iter().find(|handle| !scheduled.contains(handle)) {
+            return Err(anyhow!(
+                "{:?} is not scheduled in {:?} Algorithm",
+                handle,
+                self.label
+            ));
+        }
+        let select = |algorithm: &mut Self| {
+            let (kept, selected) = std::mem::take(&mut algorithm.solvers)
+                .into_iter()
+                .partition(|solver| {
+                    solver
+                        .handle()
+                        .is_some_and(|handle| !handles.contains(&handle))
+                });
+            algorithm.solvers = kept;
+            selected
+        };
+        self.execute_solvers(select, None).await?;
+        Ok(())
+    }
+
+    /// This method executes the calculation like [`Algorithm::run`], stopping once the `deadline` has passed
+    ///
+    /// The deadline is checked before submitting each operation, and to make it meaningful each operation is waited
+    /// for on the GPU before checking it again: once the deadline has passed the operations already submitted finish,
+    /// but no new one is submitted. The operations not submitted stay scheduled, in the same order, so that they can
+    /// be resumed by a following run (e.g. in the next frame).
+    ///
+    /// Returns a [`DeadlineReport`] with the [`FunctionHandle`]s of the [`Function`]s which completed and of the
+    /// ones which were skipped.
+    ///
+    /// # Errors
+    /// - all the errors of [`Algorithm::run`]
+    pub async fn run_with_deadline(
+        &mut self,
+        deadline: Instant,
+    ) -> Result<DeadlineReport, anyhow::Error> {
+        self.execute_solvers(
+            |algorithm| std::mem::take(&mut algorithm.solvers),
+            Some(deadline),
+        )
+        .await
+    }
+
+    /// This method runs again the [`Function`]s executed by the last run, and reads back the same [`Variable`]s
+    ///
+    /// The buffers, pipelines and bind groups of the last run are kept, so running the same [`Function`]s again
+    /// (e.g. each iteration of a solver) doesn't rebuild anything: the [`Variable`]s marked with
+    /// [`Algorithm::mark_dirty`] are written to the GPU, all the dispatches are recorded in a single compute pass,
+    /// and then the variables read by the last run (see [`Algorithm::read_variable`]) are read back.
+    /// A last run with other operations, like a [`Algorithm::reset_accumulator`], can't be repeated, and the
+    /// [`Function`]s scheduled since then are left for the next [`Algorithm::run`]. The [`Algorithm::last_run_stats`]
+    /// and the state given by [`Algorithm::poll_run`] are updated as for a run.
+    ///
+    /// # Example
+    /// ```ignore
+    /// algorithm.add_fun(function)?;
+    /// algorithm.read_variable(&output)?;
+    /// algorithm.run().await?;
+    /// for input_data in inputs {
+    ///     *input.lock().unwrap() = input_data;
+    ///     algorithm.mark_dirty(&input)?;
+    ///     algorithm.rerun().await?;
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// - if the last run didn't execute any [`Function`], or there was no run yet
+    /// - if the last run executed an operation which isn't a [`Function`] or a read, like a
+    ///   [`Algorithm::reset_accumulator`] or the copies of the variables shared by an [`Algorithm::append`]
+    /// - all the errors of [`Algorithm::upload_all`]
+    pub async fn rerun(&mut self) -> Result<(), anyhow::Error> {
+        let result = self.replay_last_run().await;
+        self.track_run(&result);
+        result
+    }
+
+    // submits again the recorded passes of the last run and reads back its variables
+    async fn replay_last_run(&mut self) -> Result<(), anyhow::Error> {
+        if self.last_passes.is_empty() {
+            return Err(anyhow!(
+                "The last run of {:?} Algorithm didn't execute any Function, there's nothing to run again",
+                self.label
+            ));
+        }
+        if !self.last_replayable {
+            return Err(anyhow!(
+                "The last run of {:?} Algorithm executed operations which aren't Functions, it can't be run again",
+                self.label
+            ));
+        }
+        self.upload_all()?;
+        self.check_uploads().await?;
+        let mut stats = std::mem::take(&mut self.pending_stats);
+        let mut pipelines = HashSet::new();
+        for pass in &self.last_passes {
+            for pipeline in &pass.pipelines {
+                pipelines.insert(Arc::as_ptr(pipeline));
+                for (_, workgroups) in &pass.dispatches {
+                    stats.dispatches += 1;
+                    stats.workgroups += workgroups
+                        .iter()
+                        .map(|&count| count as u64)
+                        .product::<u64>();
+                }
+            }
+        }
+        stats.pipelines = pipelines.len();
+        stats.submissions = 1;
+
+        let mut command_encoder = self.executor.create_encoder(self.label.as_deref());
+        {
+            let mut compute_pass =
+                command_encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                    label: self.label.as_deref(),
+                    timestamp_writes: None,
+                });
+            record_passes(&mut compute_pass, &self.last_passes);
+        }
+        self.executor.execute([command_encoder.finish()]);
+        for &index in &self.last_reads {
+            let sto_var = &self.variables[index];
+            let buffer = &self.buffers[sto_var.buffer_index];
+            read_into_variable(&self.executor, buffer, &sto_var.variable).await?;
+            stats.bytes_read += buffer.size();
+        }
+        self.last_run_stats = stats;
+        Ok(())
+    }
+
+    /// This method marks the [`Variable`] *`var` as changed on the CPU, so that it's written to the GPU again
+    /// by the next [`Algorithm::run`] or [`Algorithm::rerun`]
+    ///
+    /// The data uploaded is the one the [`Variable`] holds at the time of the upload.
+    ///
+    /// # Errors
+    /// - if the variable is not found in the [`Algorithm`]
+    /// - if the variable is an accumulator, whose buffer is owned by the GPU (see [`Algorithm::accumulate_into`])
+    /// - if the variable is a constant, whose buffer can't be written (see [`Algorithm::add_constant_mapped`])
+    pub fn mark_dirty(&mut self, var: &Arc<Mutex<V>>) -> Result<(), anyhow::Error> {
+        let index = self.find_variable(var).ok_or_else(|| {
+            anyhow!(
+                "Variable {:?} not found in {:?} Algorithm",
+                variable_name(var),
+                self.label
+            )
+        })?;
+        if self.variables[index].accumulator {
+            return Err(anyhow!(
+                "Variable {:?} is an accumulator of {:?} Algorithm, it can't be uploaded",
+                variable_name(var),
+                self.label
+            ));
+        }
+        if self.variables[index].constant {
+            return Err(anyhow!(
+                "Variable {:?} is a constant of {:?} Algorithm, it can't be uploaded",
+                variable_name(var),
+                self.label
+            ));
+        }
+        self.variables[index].pending_upload = true;
+        Ok(())
+    }
+
+    // submits the solvers taken by `select` in order, uploading first the pending variables
+    //
+    // The solvers are taken only once the uploads succeeded, so that a failed upload leaves them all scheduled.
+    // If a deadline is given the solvers after it are put back in front of the scheduled ones.
+    async fn execute_solvers(
+        &mut self,
+        select: impl FnOnce(&mut Self) -> Vec<Solver<V>>,
+        deadline: Option<Instant>,
+    ) -> Result<DeadlineReport, anyhow::Error> {
+        let result = self.submit_solvers(select, deadline).await;
+        self.track_run(&result);
+        result
+    }
+
+    // keeps the outcome of a run for [`Algorithm::poll_run`], flagging when the GPU is done with its work
+    fn track_run<T>(&mut self, result: &Result<T, anyhow::Error>) {
+        self.last_run = Some(match result {
+            Ok(_) => {
+                let done = Arc::new(AtomicBool::new(false));
+                let flag = Arc::clone(&done);
+                self.executor.with_device(|_, queue| {
+                    queue.on_submitted_work_done(move || flag.store(true, Ordering::Release))
+                });
+                Ok(done)
+            }
+            Err(error) => Err(error.to_string()),
+        });
+    }
+
+    // submits the solvers in order, waiting only for the reads (and for each submission if there's a deadline)
+    async fn submit_solvers(
+        &mut self,
+        select: impl FnOnce(&mut Self) -> Vec<Solver<V>>,
+        deadline: Option<Instant>,
+    ) -> Result<DeadlineReport, anyhow::Error> {
+        self.upload_all()?;
+        self.check_uploads().await?;
+        let solvers = select(self);
+        self.last_passes.clear();
+        self.last_reads.clear();
+        self.last_replayable = false;
+        let mut replayable = true;
+        let mut stats = std::mem::take(&mut self.pending_stats);
+        let mut pipelines = HashSet::new();
+        let mut report = DeadlineReport::default();
+        // the command buffers waiting to be submitted together, the deadline needs each one to be waited for
+        let fuse = self.fuse_serial && deadline.is_none();
+        let mut fused = Vec::new();
+
+        let mut solvers = solvers.into_iter();
+        while let Some(solver) = solvers.next() {
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                let skipped: Vec<Solver<V>> = std::iter::once(solver).chain(solvers).collect();
+                for solver in &skipped {
+                    solver.collect_handles(&mut report.skipped);
+                }
+                // a function can be split in more solvers, e.g. by [`Algorithm::append`]
+                let completed = &report.completed;
+                report.skipped.retain(|handle| !completed.contains(handle));
+                self.solvers.splice(0..0, skipped);
+                break;
+            }
+            replayable &= solver.is_replayable();
+            let mut handles = Vec::new();
+            solver.collect_handles(&mut handles);
+            for handle in &handles {
+                if let Some(pass) = self.recorded.remove(handle) {
+                    self.last_passes.push(pass);
+                }
+                self.outputs.remove(handle);
+            }
+
+            let submission = match solver {
+                Solver::Serial {
+                    command_encoder,
+                    dispatches,
+                    ..
                 } => {
-                    self.executor
-                        .execute([command_encoder.finish()].into_iter());
+                    stats.add_dispatches(&dispatches, &mut pipelines);
+                    if fuse {
+                        fused.push(command_encoder.finish());
+                        None
+                    } else {
+                        stats.submissions += 1;
+                        Some(self.executor.execute([command_encoder.finish()]))
+                    }
                 }
 
                 Solver::Parallel(solvers) => {
+                    if let Some((resource, first, second)) = Solver::parallel_hazard(&solvers) {
+                        submit_fused(&self.executor, &mut fused, &mut stats);
+                        return Err(anyhow!(
+                            "Operations {} and {} of a parallel batch of {:?} Algorithm both access {}, and one of them \
+                            writes it: they must be scheduled one after the other",
+                            first,
+                            second,
+                            self.label,
+                            self.resource_name(resource)
+                        ));
+                    }
                     let mut buffers = Vec::new();
                     for serial in solvers {
                         match serial {
                             Solver::Serial {
-                                command_encoder, ..
-                            } => buffers.push(command_encoder.finish()),
-                            _ => return Err(anyhow!("Cannot nest multiple parallel solvers!")),
+                                command_encoder,
+                                dispatches,
+                                ..
+                            } => {
+                                stats.add_dispatches(&dispatches, &mut pipelines);
+                                buffers.push(command_encoder.finish())
+                            }
+                            _ => {
+                                submit_fused(&self.executor, &mut fused, &mut stats);
+                                return Err(anyhow!("Cannot nest multiple parallel solvers!"));
+                            }
                         }
                     }
-                    self.executor.execute(buffers.into_iter());
+                    if fuse {
+                        fused.extend(buffers);
+                        None
+                    } else {
+                        stats.submissions += 1;
+                        Some(self.executor.execute(buffers))
+                    }
                 }
 
                 Solver::ReadBuffer(index) => {
+                    // the read back has to see the results of the fused functions
+                    submit_fused(&self.executor, &mut fused, &mut stats);
+                    self.last_reads.push(index);
                     let buffer_index = self.variables[index].buffer_index;
                     let buffer = &self.buffers[buffer_index];
-                    let mut var_write = self.variables[index].variable.lock().unwrap();
                     let result = self.executor.read_buffer(buffer).await;
-                    var_write.read_data(&result);
+                    stats.bytes_read += result.len() as u64;
+                    let mut var_write = lock_variable(&self.variables[index].variable)?;
+                    write_back(&mut *var_write, &result)?;
+                    None
+                }
+            };
+            if let (Some(_), Some(index)) = (deadline, submission) {
+                self.executor.with_device(|device, _| {
+                    device.poll(wgpu::Maintain::WaitForSubmissionIndex(index))
+                });
+            }
+            for handle in handles {
+                if !report.completed.contains(&handle) {
+                    report.completed.push(handle);
+                }
+            }
+        }
+        submit_fused(&self.executor, &mut fused, &mut stats);
+
+        stats.pipelines = pipelines.len();
+        self.last_run_stats = stats;
+        self.last_replayable = replayable;
+        self.read_timings(&report.completed).await;
+        Ok(report)
+    }
+
+    // reads the timestamps of the `completed` functions, waiting for them to finish on the GPU
+    async fn read_timings(&mut self, completed: &[FunctionHandle]) {
+        let (run, pending): (Vec<TimestampQuery>, Vec<TimestampQuery>) =
+            std::mem::take(&mut self.timestamp_queries)
+                .into_iter()
+                .partition(|query| completed.contains(&query.handle));
+        self.timestamp_queries = pending;
+        self.timings.clear();
+        let period = self.executor.timestamp_period() as f64;
+        for query in run {
+            let data = self
+                .executor
+                .read_buffer(&self.buffers[query.buffer_index])
+                .await;
+            let [start, end]: [u64; 2] = bytemuck::pod_read_unaligned(&data);
+            let ticks = end.saturating_sub(start);
+            self.timings.push((
+                query.entry_point,
+                Duration::from_nanos((ticks as f64 * period) as u64),
+            ));
+        }
+    }
+
+    /// This method gives the time spent on the GPU by each [`Function`] executed by the last run, in order
+    ///
+    /// The timings are measured with timestamps written at the beginning and at the end of the compute pass of each
+    /// [`Function`], so they need a device created with the [`wgpu::Features::TIMESTAMP_QUERY`] feature
+    /// (see [`Algorithm::with_options`]): without it nothing is measured and the timings are empty.
+    /// Each [`Function`] is named by its entry point. Reading the timestamps waits for the GPU, so a run profiled
+    /// this way returns only once all its work is done.
+    pub fn timings(&self) -> Vec<(String, Duration)> {
+        self.timings.clone()
+    }
+
+    /// This method reports whether the work submitted by the last [`Algorithm::run`] has finished on the GPU,
+    /// without blocking
+    ///
+    /// A run returns as soon as its operations are submitted (unless it reads a [`Variable`] back), while the GPU may
+    /// still be executing them. This method advances the device without waiting, so an application with its own
+    /// event loop (e.g. one frame at a time) can check the progress instead of awaiting the results.
+    /// Before the first run it reports [`RunState::Done`], since there's nothing to wait for.
+    pub fn poll_run(&self) -> RunState {
+        match &self.last_run {
+            None => RunState::Done,
+            Some(Err(error)) => RunState::Error(error.clone()),
+            Some(Ok(done)) => {
+                self.executor
+                    .with_device(|device, _| device.poll(wgpu::Maintain::Poll));
+                if done.load(Ordering::Acquire) {
+                    RunState::Done
+                } else {
+                    RunState::Pending
+                }
+            }
+        }
+    }
+
+    /// This method gives back the [`RunStats`] of the last [`Algorithm::run`] (or [`Algorithm::rerun`])
+    ///
+    /// It's useful to understand the shape of the workload submitted to the GPU, e.g. to
+    /// find out why an [`Algorithm`] is slow to construct or to execute.
+    /// Before the first run all the statistics are zero.
+    pub fn last_run_stats(&self) -> &RunStats {
+        &self.last_run_stats
+    }
+
+    /// This method overwrite the [`Variable`] *`var` with the ouptut of the calculation
+    ///
+    /// reading from a GPU buffer is in general an expensive operation. This functions calls the
+    /// correct method on the [`Executor`] to read the GPU buffer asycronously and with the least
+    /// amount of effort possible.
+    ///
+    /// The function returns an error if the variable is not found in the [`Algorithm`] or
+    pub fn read_variable(&mut self, var: &Arc<Mutex<V>>) -> Result<(), anyhow::Error> {
+        match self.find_variable(var) {
+            None => Err(anyhow!(
+                "Variable {:?} not found in {:?} Algorithm",
+                variable_name(var),
+                self.label
+            )),
+            Some(index) => {
+                self.solvers.push(Solver::ReadBuffer(index));
+                Ok(())
+            }
+        }
+    }
+
+    /// This method schedules the read back of the outputs of the [`Function`] `handle`, see [`VariableBind::as_output`]
+    ///
+    /// It's the same as calling [`Algorithm::read_variable`] on each output, in the order they're bound, so the values
+    /// read are the ones after all the [`Function`]s added so far. A [`Function`] without outputs reads nothing.
+    ///
+    /// # Errors
+    /// - if `handle` isn't a [`Function`] of this [`Algorithm`] still to be run
+    pub fn read_outputs(&mut self, handle: FunctionHandle) -> Result<(), anyhow::Error> {
+        if !self.recorded.contains_key(&handle) {
+            return Err(anyhow!(
+                "Function {:?} not found in {:?} Algorithm",
+                handle,
+                self.label
+            ));
+        }
+        for var in self.outputs.get(&handle).cloned().unwrap_or_default() {
+            self.read_variable(&var)?;
+        }
+        Ok(())
+    }
+
+    /// This method overwrites the [`Variable`] *`var` with the current content of its GPU buffer
+    ///
+    /// Differently from [`Algorithm::read_variable`] the reading is not scheduled, but takes place
+    /// immediately, after all the operations already submitted to the GPU (e.g. with [`Algorithm::run`]).
+    ///
+    /// # Errors
+    /// - if the variable is not found in the [`Algorithm`]
+    /// - if the variable lock is poisoned
+    /// - if the size of the buffer doesn't match the [`Variable::byte_size`]
+    pub async fn get_output_unmap(&self, var: &Arc<Mutex<V>>) -> Result<(), anyhow::Error> {
+        let index = self.find_variable(var).ok_or_else(|| {
+            anyhow!(
+                "Variable {:?} not found in {:?} Algorithm",
+                variable_name(var),
+                self.label
+            )
+        })?;
+        let buffer = &self.buffers[self.variables[index].buffer_index];
+        read_into_variable(&self.executor, buffer, var).await
+    }
+
+    /// This method gives a copy of the current content of the GPU buffer of the [`Variable`] *`var`, for debugging
+    ///
+    /// It's meant to look at an intermediate result (e.g. of a diverging iteration) without reading it into the
+    /// [`Variable`]: the pending uploads are written and the scheduled operations are submitted as by
+    /// [`Algorithm::run`], except for the reads (see [`Algorithm::read_variable`]), which stay scheduled for the next
+    /// run, so that no [`Variable`] is overwritten. To stop in the middle of the scheduled [`Function`]s run only the
+    /// first ones with [`Algorithm::run_selected`] and peek afterwards.
+    ///
+    /// **Warning**: this forces a synchronisation with the GPU, waiting for all the submitted work to finish before
+    /// copying the buffer back, which defeats the lazy design of the [`Algorithm`]. It shouldn't be left in
+    /// production code, use [`Algorithm::read_variable`] for the results.
+    ///
+    /// # Errors
+    /// - if the variable is not found in the [`Algorithm`]
+    /// - all the errors of [`Algorithm::run`]
+    pub async fn peek(&mut self, var: &Arc<Mutex<V>>) -> Result<Vec<u8>, anyhow::Error> {
+        let index = self.find_variable(var).ok_or_else(|| {
+            anyhow!(
+                "Variable {:?} not found in {:?} Algorithm",
+                variable_name(var),
+                self.label
+            )
+        })?;
+        self.execute_solvers(
+            |algorithm| {
+                let (reads, solvers) = std::mem::take(&mut algorithm.solvers)
+                    .into_iter()
+                    .partition(|solver| matches!(solver, Solver::ReadBuffer(_)));
+                algorithm.solvers = reads;
+                solvers
+            },
+            None,
+        )
+        .await?;
+        let buffer = &self.buffers[self.variables[index].buffer_index];
+        Ok(self.executor.read_buffer(buffer).await)
+    }
+
+    /// This method checks that all the [`Function`]s with a sentinel (see [`Function::with_sentinel`]) which have been
+    /// run did set their flag
+    ///
+    /// Only the flags are read back, 4 bytes for each [`Function`], after all the operations already submitted
+    /// to the GPU. The [`Function`]s still scheduled aren't checked, and the ones checked are forgotten, so that each
+    /// flag is checked once.
+    ///
+    /// # Errors
+    /// - if a [`Function`] didn't set its flag (e.g. because it was dispatched with zero workgroups),
+    ///   giving the entry points of all of them
+    pub async fn verify_executed(&mut self) -> Result<(), anyhow::Error> {
+        let scheduled: Vec<FunctionHandle> =
+            self.solvers.iter().filter_map(Solver::handle).collect();
+        let (pending, run): (Vec<Sentinel>, Vec<Sentinel>) = std::mem::take(&mut self.sentinels)
+            .into_iter()
+            .partition(|sentinel| scheduled.contains(&sentinel.handle));
+        self.sentinels = pending;
+
+        let mut not_executed = Vec::new();
+        for sentinel in run {
+            let flag: u32 = self
+                .executor
+                .read_scalar(&self.buffers[sentinel.buffer_index])
+                .await?;
+            if flag == 0 {
+                not_executed.push(sentinel.entry_point);
+            }
+        }
+        if !not_executed.is_empty() {
+            return Err(anyhow!(
+                "Functions {:?} of {:?} Algorithm didn't set their sentinel, they may not have run",
+                not_executed,
+                self.label
+            ));
+        }
+        Ok(())
+    }
+
+    /// This method reads the GPU texture of the [`TextureVariable`] `texture` back to it
+    ///
+    /// Like [`Algorithm::get_output_unmap`] the reading takes place immediately, after all the operations already
+    /// submitted to the GPU. The data is given to [`TextureVariable::read_data`] as rows of texels without padding.
+    ///
+    /// # Errors
+    /// - if the texture is not found in the [`Algorithm`]
+    /// - if the texture lock is poisoned
+    /// - if the data read back fails [`TextureVariable::read_data`]
+    pub async fn read_texture<T: TextureVariable + 'static>(
+        &self,
+        texture: &Arc<Mutex<T>>,
+    ) -> Result<(), anyhow::Error> {
+        let index = self.find_texture(texture).ok_or_else(|| {
+            anyhow!(
+                "Texture {:?} not found in {:?} Algorithm",
+                texture
+                    .lock()
+                    .ok()
+                    .and_then(|texture| texture.get_name().map(str::to_string)),
+                self.label
+            )
+        })?;
+        let result = self
+            .executor
+            .read_texture(&self.textures[index].texture)
+            .await?;
+        lock_texture(texture)?.read_data(&result)
+    }
+
+    /// This method reads the GPU buffer of the [`Variable`] `var` into the file at `path`
+    ///
+    /// Like [`Algorithm::get_output_unmap`] the reading takes place immediately, but the data is neither given to the
+    /// [`Variable`] nor collected in memory: the buffer is streamed to the file in chunks (see
+    /// [`Executor::read_buffer_to_writer`]), so that results bigger than the available memory can be saved.
+    /// The file is created (or truncated) and holds the raw bytes of the buffer, in the same layout as
+    /// [`Variable::byte_data`]. It can then be memory mapped to be processed out of core.
+    ///
+    /// # Errors
+    /// - if the variable is not found in the [`Algorithm`]
+    /// - if the file can't be created or written
+    pub async fn read_variable_to_file(
+        &self,
+        var: &Arc<Mutex<V>>,
+        path: impl AsRef<std::path::Path>,
+    ) -> Result<(), anyhow::Error> {
+        let index = self.find_variable(var).ok_or_else(|| {
+            anyhow!(
+                "Variable {:?} not found in {:?} Algorithm",
+                variable_name(var),
+                self.label
+            )
+        })?;
+        let mut file = std::fs::File::create(path)?;
+        self.executor
+            .read_buffer_to_writer(&self.buffers[self.variables[index].buffer_index], &mut file)
+            .await
+    }
+
+    /// This method maps the GPU buffer of the [`Variable`] *`var` to read it row by row
+    ///
+    /// Like [`Algorithm::get_output_unmap`] the reading takes place immediately, but the data is not copied to the
+    /// [`Variable`]: the rows are read in place from the mapped buffer, so that a big result can be streamed
+    /// (e.g. written to disk) without holding another copy of it in memory. Nothing is read back to the [`Variable`].
+    /// A row is made of the first of the [`Variable::dimension_sizes`] elements of type `f32`.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let mapped = algorithm.read_rows(&var).await?;
+    /// let view = mapped.view();
+    /// for row in view.rows() {
+    ///     println!("{:?}", row);
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// - if the variable is not found in the [`Algorithm`]
+    /// - if the [`Variable::element_size`] of the variable is not the one of an `f32`
+    pub async fn read_rows(&self, var: &Arc<Mutex<V>>) -> Result<MappedRows<'_>, anyhow::Error> {
+        let index = self.find_variable(var).ok_or_else(|| {
+            anyhow!(
+                "Variable {:?} not found in {:?} Algorithm",
+                variable_name(var),
+                self.label
+            )
+        })?;
+        let row_len = {
+            let var_lock = lock_variable(var)?;
+            if var_lock.element_size() != Some(std::mem::size_of::<f32>() as u64) {
+                return Err(anyhow!(
+                    "Variable {:?} has element size {:?}, only f32 rows can be read",
+                    var_lock.get_name(),
+                    var_lock.element_size()
+                ));
+            }
+            var_lock.dimension_sizes()[0].max(1) as usize
+        };
+        let buffer = &self.buffers[self.variables[index].buffer_index];
+        Ok(MappedRows {
+            mapped: self.executor.map_buffer(buffer).await,
+            row_len,
+        })
+    }
+
+    /// This method overwrites each of the [`Variable`]s in *`vars` with the content of its GPU buffer
+    ///
+    /// Like [`Algorithm::get_output_unmap`] the readings take place immediately, but a failure on one of the
+    /// [`Variable`]s doesn't stop the others to be read back.
+    ///
+    /// Returns a [`Result`] for each of the `vars`, in the same order.
+    pub async fn get_outputs_unmap(
+        &self,
+        vars: &[&Arc<Mutex<V>>],
+    ) -> Vec<Result<(), anyhow::Error>> {
+        let mut results = Vec::with_capacity(vars.len());
+        for var in vars {
+            results.push(self.get_output_unmap(var).await);
+        }
+        results
+    }
+
+    /// This method overwrites each of the [`Variable`]s in *`vars` with the content of its GPU buffer, in a single readback
+    ///
+    /// Like [`Algorithm::get_output_unmap`] the readings take place immediately, after all the operations already
+    /// submitted to the GPU, but all the buffers are copied back together (see [`Executor::read_buffers`]): reading
+    /// several results costs a single synchronisation with the GPU instead of one for each [`Variable`].
+    ///
+    /// # Errors
+    /// - if one of the variables is not found in the [`Algorithm`], in which case nothing is read
+    /// - if the lock of a variable is poisoned, or the size of its buffer doesn't match the [`Variable::byte_size`]. The
+    ///   variables before it are overwritten, and the ones after it are left untouched
+    pub async fn read_variables(&self, vars: &[&Arc<Mutex<V>>]) -> Result<(), anyhow::Error> {
+        let mut buffers = Vec::with_capacity(vars.len());
+        for var in vars {
+            let index = self.find_variable(var).ok_or_else(|| {
+                anyhow!(
+                    "Variable {:?} not found in {:?} Algorithm",
+                    variable_name(var),
+                    self.label
+                )
+            })?;
+            buffers.push(&self.buffers[self.variables[index].buffer_index]);
+        }
+        let results = self.executor.read_buffers(&buffers).await;
+        for (var, bytes) in vars.iter().zip(results) {
+            write_back(&mut *lock_variable(var)?, &bytes)?;
+        }
+        Ok(())
+    }
+
+    /// This method marks the [`Variable`] *`var` as an accumulator, whose GPU buffer persists between runs
+    ///
+    /// An accumulator is owned by the GPU: its host data is never uploaded to the buffer, so that the
+    /// values accumulated by the [`Function`]s across successive [`Algorithm::run`] calls are never
+    /// overwritten. The buffer starts zeroed, and can be zeroed again with [`Algorithm::reset_accumulator`].
+    ///
+    /// If the [`Variable`] was already added to the [`Algorithm`] its buffer is kept as it is, and the host data
+    /// not yet uploaded is dropped, otherwise a new zeroed one is created. A buffer already written by a run keeps
+    /// that data, so call this before running the [`Function`]s using it to start from zero.
+    ///
+    /// # Arguments
+    /// * - `var` - the [`Variable`] which will hold the accumulated result
+    ///
+    /// # Errors
+    /// - if the [`Variable`] is a constant of the [`Algorithm`] (see [`Algorithm::add_constant_mapped`])
+    /// - if the [`Variable`] fails [`Variable::validate_self`]
+    /// - if the byte size of the [`Variable`] is not a multiple of [`wgpu::COPY_BUFFER_ALIGNMENT`], so that its
+    ///   buffer couldn't be zeroed by [`Algorithm::reset_accumulator`]
+    /// - if the lock of the [`Variable`] is poisoned
+    pub fn accumulate_into(&mut self, var: &Arc<Mutex<V>>) -> Result<(), anyhow::Error> {
+        if let Some(index) = self.find_variable(var) {
+            if self.variables[index].constant {
+                return Err(anyhow!(
+                    "Variable {:?} is a constant of {:?} Algorithm, it can't be an accumulator",
+                    variable_name(var),
+                    self.label
+                ));
+            }
+            // the host data not yet uploaded would overwrite the accumulated values at the next run
+            self.variables[index].accumulator = true;
+            self.variables[index].pending_upload = false;
+            return Ok(());
+        }
+
+        let buffer = {
+            let var_lock = lock_variable(var)?;
+            var_lock.validate_self()?;
+            if !var_lock
+                .byte_size()
+                .is_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT)
+            {
+                return Err(anyhow!(
+                    "Variable {:?} can't be an accumulator, its size of {} bytes is not a multiple of {}",
+                    var_lock.get_name(),
+                    var_lock.byte_size(),
+                    wgpu::COPY_BUFFER_ALIGNMENT
+                ));
+            }
+            self.executor.get_buffer(&var_lock.to_buffer_descriptor())
+        };
+        self.variables.push(StoredVariable {
+            variable: Arc::clone(var),
+            binds: Vec::new(),
+            buffer_index: self.buffers.len(),
+            accumulator: true,
+            constant: false,
+            pending_upload: false,
+        });
+        self.buffers.push(buffer);
+        Ok(())
+    }
+
+    /// This method adds the [`Variable`] *`var` as a constant, seeding its GPU buffer at allocation
+    ///
+    /// The buffer is created mapped and the data of the [`Variable`] is written straight into it
+    /// (see [`Executor::get_buffer_mapped`]), without a separate write on the queue. It's the way to place
+    /// data which never changes on the GPU, like a precomputed lookup table.
+    /// The buffer is resident: the [`Function`]s using the [`Variable`] find it already in the [`Algorithm`] and
+    /// never upload it again, and it can't be the destination of GPU copies. It's up to the shaders to
+    /// only read from it.
+    ///
+    /// # Errors
+    /// - if the [`Variable`] is already in the [`Algorithm`]
+    /// - if the [`Variable`] fails [`Variable::validate_self`]
+    /// - if the byte size of the [`Variable`] is not a multiple of [`wgpu::COPY_BUFFER_ALIGNMENT`]
+    pub fn add_constant_mapped(&mut self, var: &Arc<Mutex<V>>) -> Result<(), anyhow::Error> {
+        if self.find_variable(var).is_some() {
+            return Err(anyhow!(
+                "Variable {:?} is already in {:?} Algorithm",
+                variable_name(var),
+                self.label
+            ));
+        }
+        let buffer = {
+            let var_lock = lock_variable(var)?;
+            var_lock.validate_self()?;
+            let buffer = self.executor.get_buffer_mapped(
+                &wgpu::BufferDescriptor {
+                    usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+                    ..var_lock.to_buffer_descriptor()
+                },
+                upload_data(&*var_lock)?,
+            )?;
+            self.pending_stats.bytes_uploaded += var_lock.byte_data().len() as u64;
+            buffer
+        };
+        self.variables.push(StoredVariable {
+            variable: Arc::clone(var),
+            binds: Vec::new(),
+            buffer_index: self.buffers.len(),
+            accumulator: false,
+            constant: true,
+            pending_upload: false,
+        });
+        self.buffers.push(buffer);
+        Ok(())
+    }
+
+    /// This method schedules the zeroing of an accumulator [`Variable`] buffer
+    ///
+    /// The operation is added to the list of operations (like a [`Function`]), and takes place on the GPU
+    /// in the order it's added during the following [`Algorithm::run`].
+    ///
+    /// # Errors
+    /// - if the variable is not found in the [`Algorithm`]
+    /// - if the variable is a constant (see [`Algorithm::add_constant_mapped`])
+    /// - if the variable was not set as an accumulator with [`Algorithm::accumulate_into`]
+    /// - if the buffer of the variable has no [`wgpu::BufferUsages::COPY_DST`] usage, or its size is not a multiple
+    ///   of [`wgpu::COPY_BUFFER_ALIGNMENT`], like for [`Algorithm::zero_variable`]
+    pub fn reset_accumulator(&mut self, var: &Arc<Mutex<V>>) -> Result<(), anyhow::Error> {
+        let index = match self.find_variable(var) {
+            Some(index) if self.variables[index].constant => {
+                return Err(anyhow!(
+                    "Variable {:?} is a constant of {:?} Algorithm, it can't be reset",
+                    variable_name(var),
+                    self.label
+                ))
+            }
+            Some(index) if self.variables[index].accumulator => index,
+            Some(_) => {
+                return Err(anyhow!(
+                    "Variable {:?} is not an accumulator of {:?} Algorithm",
+                    variable_name(var),
+                    self.label
+                ))
+            }
+            None => {
+                return Err(anyhow!(
+                    "Variable {:?} not found in {:?} Algorithm",
+                    variable_name(var),
+                    self.label
+                ))
+            }
+        };
+
+        self.schedule_clear(index, var, "reset accumulator")
+    }
+
+    /// This method schedules the zeroing of the GPU buffer of a [`Variable`]
+    ///
+    /// Like [`Algorithm::reset_accumulator`] the operation takes place on the GPU in the order it's added during the
+    /// following [`Algorithm::run`], but it works for any [`Variable`]: it's useful for the scratch buffers which must
+    /// start from zero at each run, without uploading (and keeping in memory) a zeroed host variable.
+    /// The host data of the [`Variable`] is left untouched.
+    ///
+    /// # Errors
+    /// - if the variable is not found in the [`Algorithm`]
+    /// - if the buffer of the variable has no [`wgpu::BufferUsages::COPY_DST`] usage
+    /// - if the size of the buffer is not a multiple of [`wgpu::COPY_BUFFER_ALIGNMENT`]
+    pub fn zero_variable(&mut self, var: &Arc<Mutex<V>>) -> Result<(), anyhow::Error> {
+        let index = self.find_variable(var).ok_or_else(|| {
+            anyhow!(
+                "Variable {:?} not found in {:?} Algorithm",
+                variable_name(var),
+                self.label
+            )
+        })?;
+        self.schedule_clear(index, var, "zero variable")
+    }
+
+    // schedules the clearing of the whole buffer of the variable at `index`, if the buffer allows it
+    fn schedule_clear(
+        &mut self,
+        index: usize,
+        var: &Arc<Mutex<V>>,
+        label: &str,
+    ) -> Result<(), anyhow::Error> {
+        let buffer = &self.buffers[self.variables[index].buffer_index];
+        if !buffer.usage().contains(wgpu::BufferUsages::COPY_DST) {
+            return Err(anyhow!(
+                "Variable {:?} can't be zeroed, its buffer has usage {:?}",
+                variable_name(var),
+                buffer.usage()
+            ));
+        }
+        if !buffer.size().is_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT) {
+            return Err(anyhow!(
+                "Variable {:?} can't be zeroed, its size of {} bytes is not a multiple of {}",
+                variable_name(var),
+                buffer.size(),
+                wgpu::COPY_BUFFER_ALIGNMENT
+            ));
+        }
+        let mut command_encoder = self.executor.create_encoder(Some(label));
+        command_encoder.clear_buffer(buffer, 0, None);
+
+        self.solvers.push(Solver::Serial {
+            command_encoder,
+            variables: vec![Arc::clone(var)],
+            dispatches: Vec::new(),
+            handle: None,
+        });
+        Ok(())
+    }
+
+    /// This method schedules a copy of the buffer of a [`Variable`] into a [`wgpu::Buffer`] owned by the caller
+    ///
+    /// The copy is done GPU to GPU in the order it's added during the following [`Algorithm::run`], so the result of
+    /// a calculation can be handed to e.g. a render pipeline on the same device without going through the CPU.
+    /// The `destination` must be created on the device of the [`Algorithm`] (see [`Algorithm::executor`]) with
+    /// the [`wgpu::BufferUsages::COPY_DST`] usage, and it's written from its start.
+    ///
+    /// # Errors
+    /// - if the variable is not found in the [`Algorithm`]
+    /// - if `destination` has no [`wgpu::BufferUsages::COPY_DST`] usage or is smaller than the variable
+    pub fn copy_variable_to_external(
+        &mut self,
+        var: &Arc<Mutex<V>>,
+        destination: &wgpu::Buffer,
+    ) -> Result<(), anyhow::Error> {
+        let index = self.find_variable(var).ok_or_else(|| {
+            anyhow!(
+                "Variable {:?} not found in {:?} Algorithm",
+                variable_name(var),
+                self.label
+            )
+        })?;
+        let buffer = &self.buffers[self.variables[index].buffer_index];
+        if !destination.usage().contains(wgpu::BufferUsages::COPY_DST) {
+            return Err(anyhow!(
+                "Destination buffer can't be copied into, it has usage {:?}",
+                destination.usage()
+            ));
+        }
+        if destination.size() < buffer.size() {
+            return Err(anyhow!(
+                "Destination buffer of {} bytes can't hold variable {:?} of {} bytes",
+                destination.size(),
+                variable_name(var),
+                buffer.size()
+            ));
+        }
+
+        let mut command_encoder = self.executor.create_encoder(Some("copy to external"));
+        command_encoder.copy_buffer_to_buffer(buffer, 0, destination, 0, buffer.size());
+
+        self.solvers.push(Solver::Serial {
+            command_encoder,
+            variables: vec![Arc::clone(var)],
+            dispatches: Vec::new(),
+            handle: None,
+        });
+        Ok(())
+    }
+
+    /// This method finds the fastest workgroup size of a [`Function`] between the `candidate_sizes`
+    ///
+    /// The [`Shader`] of the [`Function`] is a template where [`WORKGROUP_SIZE_PLACEHOLDER`] is replaced by each candidate.
+    /// Every variant is compiled and dispatched with enough workgroups to cover the dimensions of the first [`Variable`],
+    /// so the shader must skip the invocations out of bounds (see [`Function::with_dimensions`]).
+    /// Each variant runs once to warm up and then a few more times, on copies of the [`Variable`]s data: the
+    /// variables and the operations of the [`Algorithm`] are left untouched.
+    ///
+    /// The best size is kept for the shader template and entry point, so the following calls return it without
+    /// running anything. The result is meant to template the [`Shader`] used for the actual calculation.
+    ///
+    /// # Errors
+    /// - if there are no candidates, or a candidate is not valid for the device limits
+    /// - if a [`Variable`] of the [`Function`] is in a bind group other than 0 (see [`VariableBind::in_group`])
+    /// - if a [`Variable`] of the [`Function`] fails [`Variable::validate_self`]
+    /// - if a candidate needs more workgroups than the device allows
+    /// - if the [`Shader`] can't be templated, see [`Shader::render`]
+    pub fn autotune(
+        &mut self,
+        function: &Function<'a, V>,
+        candidate_sizes: &[[u32; 3]],
+    ) -> Result<[u32; 3], anyhow::Error> {
+        let key = ((*function.shader).clone(), function.entry_point.to_owned());
+        if let Some(size) = self.tuned_sizes.get(&key) {
+            return Ok(*size);
+        }
+        if candidate_sizes.is_empty() {
+            return Err(anyhow!(
+                "No workgroup size to tune {} with",
+                function.entry_point
+            ));
+        }
+        if let Some(bind) = function.variables.iter().find(|bind| bind.group != 0) {
+            return Err(anyhow!(
+                "Function {} has a variable in bind group {}, but autotune only binds the group 0",
+                function.entry_point,
+                bind.group
+            ));
+        }
+        let limits = self.executor.with_device(|device, _| device.limits());
+        for size in candidate_sizes {
+            let max = [
+                limits.max_compute_workgroup_size_x,
+                limits.max_compute_workgroup_size_y,
+                limits.max_compute_workgroup_size_z,
+            ];
+            let invocations: u64 = size.iter().map(|&dim| dim as u64).product();
+            if size
+                .iter()
+                .zip(max)
+                .any(|(&dim, max)| dim == 0 || dim > max)
+                || invocations > limits.max_compute_invocations_per_workgroup as u64
+            {
+                return Err(anyhow!(
+                    "Workgroup size {:?} is not valid for the device limits",
+                    size
+                ));
+            }
+        }
+        for bind in &function.variables {
+            lock_variable(&bind.variable)?.validate_self()?;
+        }
+
+        let dimensions = lock_variable(&function.variables[0].variable)?.dimension_sizes();
+        let buffers = function
+            .variables
+            .iter()
+            .map(|bind| {
+                let var = lock_variable(&bind.variable)?;
+                Ok(self
+                    .executor
+                    .get_buffer_init(&wgpu::util::BufferInitDescriptor {
+                        label: var.get_name(),
+                        contents: var.byte_data(),
+                        usage: if bind.uniform {
+                            wgpu::BufferUsages::UNIFORM
+                        } else {
+                            wgpu::BufferUsages::STORAGE
+                        },
+                    }))
+            })
+            .collect::<Result<Vec<wgpu::Buffer>, anyhow::Error>>()?;
+        let dimensions_buffer = function.dimensions_binding.map(|_| {
+            self.executor
+                .get_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("autotune dimensions"),
+                    contents: bytemuck::cast_slice(&dimensions_uniform(
+                        dimensions,
+                        function.dispatch_offset,
+                    )),
+                    usage: wgpu::BufferUsages::UNIFORM,
+                })
+        });
+
+        let mut layout_entries = Vec::new();
+        let mut bind_entries = Vec::new();
+        for (bind, buffer) in function.variables.iter().zip(&buffers) {
+            let ty = if bind.uniform {
+                wgpu::BufferBindingType::Uniform
+            } else {
+                wgpu::BufferBindingType::Storage {
+                    read_only: function
+                        .read_only_bindings
+                        .contains(&(bind.group, bind.bind_group)),
+                }
+            };
+            layout_entries.push(wgpu::BindGroupLayoutEntry {
+                binding: bind.bind_group,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Buffer {
+                    ty,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            });
+            let resource = match bind.view {
+                Some((offset, len)) => wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                    buffer,
+                    offset,
+                    size: NonZeroU64::new(len),
+                }),
+                None => buffer.as_entire_binding(),
+            };
+            bind_entries.push(wgpu::BindGroupEntry {
+                binding: bind.bind_group,
+                resource,
+            });
+        }
+        if let (Some(binding), Some(buffer)) = (function.dimensions_binding, &dimensions_buffer) {
+            layout_entries.push(dimensions_layout_entry(binding));
+            bind_entries.push(wgpu::BindGroupEntry {
+                binding,
+                resource: buffer.as_entire_binding(),
+            });
+        }
+
+        let bind_layout = self
+            .executor
+            .get_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("autotune"),
+                entries: &layout_entries,
+            });
+        let bind_group = self.executor.get_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("autotune"),
+            layout: &bind_layout,
+            entries: &bind_entries,
+        });
+        let pipeline_layout = self
+            .executor
+            .get_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("autotune"),
+                bind_group_layouts: &[&bind_layout],
+                push_constant_ranges: &[],
+            });
+
+        let mut best: Option<([u32; 3], Duration)> = None;
+        for size in candidate_sizes {
+            let mut workgroups = [0u32; 3];
+            for id in 0..3 {
+                workgroups[id] = dimensions[id].div_ceil(size[id]).max(1);
+            }
+            if workgroups
+                .iter()
+                .any(|&count| count > limits.max_compute_workgroups_per_dimension)
+            {
+                return Err(anyhow!(
+                    "Workgroup size {:?} needs {:?} workgroups, which exceeds the device limits",
+                    size,
+                    workgroups
+                ));
+            }
+
+            let shader = (*function.shader).clone().render(&[(
+                WORKGROUP_SIZE_PLACEHOLDER,
+                &format!("{}, {}, {}", size[0], size[1], size[2]),
+            )])?;
+            let shader_module = self.executor.get_shader_module(&shader);
+            let pipeline = self
+                .executor
+                .get_pipeline(&wgpu::ComputePipelineDescriptor {
+                    label: Some("autotune"),
+                    layout: Some(&pipeline_layout),
+                    module: &shader_module,
+                    entry_point: function.entry_point,
+                });
+
+            let mut elapsed = Duration::MAX;
+            // the first run warms up the pipeline and it's not timed
+            for run in 0..=AUTOTUNE_REPEATS {
+                let start = Instant::now();
+                let encoder = self.executor.dispatch_bind_and_pipeline(
+                    &bind_group,
+                    &pipeline,
+                    &workgroups,
+                    Some("autotune"),
+                );
+                self.executor.execute(std::iter::once(encoder.finish()));
+                self.executor
+                    .with_device(|device, _| device.poll(wgpu::Maintain::Wait));
+                if run > 0 {
+                    elapsed = elapsed.min(start.elapsed());
+                }
+            }
+            if best.is_none_or(|(_, best_elapsed)| elapsed < best_elapsed) {
+                best = Some((*size, elapsed));
+            }
+        }
+
+        let (size, _) = best.expect("there is at least a candidate");
+        self.tuned_sizes.insert(key, size);
+        Ok(size)
+    }
+
+    /// Gives a description of all the operations scheduled and not yet run, in the order they will be submitted
+    ///
+    /// See [`OperationInfo`] for the details of what's described.
+    pub fn get_operations(&self) -> Vec<OperationInfo> {
+        self.solvers
+            .iter()
+            .map(|solver| self.describe_solver(solver))
+            .collect()
+    }
+
+    /// Gives a human readable summary of the operations given by [`Algorithm::get_operations`], one per line
+    ///
+    /// Each operation is numbered in the order it will be submitted and says whether it's serial, parallel or a read
+    /// back. The serial ones list the [`Variable`]s they touch and each of their dispatches, with its entry point, its
+    /// workgroups and its bindings, while the operations of a parallel batch are indented below it. The variables are
+    /// named by [`Variable::get_name`] followed by their index, so the lock of all of them must be free.
+    ///
+    /// # Example
+    /// ```text
+    /// 0: serial on a (0), b (1)
+    ///     add_matrices with workgroups [3, 3, 1], bindings 0: a (0), 1: b (1)
+    /// 1: read back a (0)
+    /// ```
+    pub fn describe_operations(&self) -> String {
+        let mut summary = String::new();
+        for (position, operation) in self.get_operations().iter().enumerate() {
+            self.describe_operation(&mut summary, position, operation, 0);
+        }
+        summary
+    }
+
+    // writes the line of an operation of [`Algorithm::describe_operations`], and of its dispatches
+    fn describe_operation(
+        &self,
+        summary: &mut String,
+        position: usize,
+        operation: &OperationInfo,
+        depth: usize,
+    ) {
+        let indent = "    ".repeat(depth);
+        let name = |index: usize| {
+            format!(
+                "{} ({})",
+                self.resource_name(Resource::Variable(index)),
+                index
+            )
+        };
+        match operation {
+            OperationInfo::Serial {
+                variables,
+                dispatches,
+            } => {
+                let variables: Vec<String> = variables.iter().map(|&index| name(index)).collect();
+                summary.push_str(&format!(
+                    "{}{}: serial on {}\n",
+                    indent,
+                    position,
+                    variables.join(", ")
+                ));
+                if dispatches.is_empty() {
+                    summary.push_str(&format!("{}    buffer operation\n", indent));
+                }
+                for dispatch in dispatches {
+                    let bindings: Vec<String> = dispatch
+                        .bindings
+                        .iter()
+                        .map(|&(binding, index)| format!("{}: {}", binding, name(index)))
+                        .collect();
+                    summary.push_str(&format!(
+                        "{}    {} with workgroups {:?}, bindings {}\n",
+                        indent,
+                        dispatch.entry_point,
+                        dispatch.workgroups,
+                        bindings.join(", ")
+                    ));
+                }
+            }
+            OperationInfo::Parallel(operations) => {
+                summary.push_str(&format!("{}{}: parallel\n", indent, position));
+                for (position, operation) in operations.iter().enumerate() {
+                    self.describe_operation(summary, position, operation, depth + 1);
+                }
+            }
+            OperationInfo::ReadBuffer { variable } => {
+                summary.push_str(&format!(
+                    "{}{}: read back {}\n",
+                    indent,
+                    position,
+                    name(*variable)
+                ));
+            }
+        }
+    }
+
+    // builds the public description of a solver
+    fn describe_solver(&self, solver: &Solver<V>) -> OperationInfo {
+        match solver {
+            Solver::Serial {
+                variables,
+                dispatches,
+                ..
+            } => OperationInfo::Serial {
+                variables: variables
+                    .iter()
+                    .filter_map(|var| self.find_variable(var))
+                    .collect(),
+                dispatches: dispatches
+                    .iter()
+                    .map(|dispatch| DispatchInfo {
+                        entry_point: self.modules[dispatch.module].entry_point
+                            [dispatch.entry_point]
+                            .to_owned(),
+                        bindings: dispatch.bindings.clone(),
+                        workgroups: dispatch.workgroups,
+                    })
+                    .collect(),
+            },
+            Solver::Parallel(solvers) => OperationInfo::Parallel(
+                solvers
+                    .iter()
+                    .map(|solver| self.describe_solver(solver))
+                    .collect(),
+            ),
+            Solver::ReadBuffer(variable) => OperationInfo::ReadBuffer {
+                variable: *variable,
+            },
+        }
+    }
+
+    /// Gives the scheduled operations and their dependencies as a graph in the DOT language of Graphviz
+    ///
+    /// Each [`Function`] and each read back of a [`Variable`] is a node, in the order they will be submitted.
+    /// An edge goes from an operation to a following one when they access the same [`Variable`] (or
+    /// [`TextureVariable`]), labelled with its name:
+    /// - a solid edge when the second operation reads what the first one wrote
+    /// - a dashed edge when the second operation overwrites what the first one read or wrote
+    ///
+    /// The storage buffers are bound as `read_write`, so they count both as read and written, except the
+    /// [`Immutable`] and uniform ones which are only read, while the textures are read or written depending on their
+    /// [`TextureAccess`].
+    /// The operations which aren't linked by any path of edges could run in parallel.
+    ///
+    /// # Example
+    /// The graph can be rendered with `dot -Tsvg graph.dot -o graph.svg`
+    /// ```ignore
+    /// std::fs::write("graph.dot", algorithm.to_dot())?;
+    /// ```
+    pub fn to_dot(&self) -> String {
+        let mut nodes = Vec::new();
+        let mut edges = Vec::new();
+        // (last writer, readers since the last write) of each variable and texture
+        let mut resources: HashMap<Resource, (Option<usize>, Vec<usize>)> = HashMap::new();
+
+        let mut operations: Vec<&Solver<V>> = Vec::new();
+        for solver in &self.solvers {
+            match solver {
+                Solver::Parallel(solvers) => operations.extend(solvers),
+                _ => operations.push(solver),
+            }
+        }
+        for solver in operations {
+            let node = nodes.len();
+            match solver {
+                Solver::Serial { dispatches, .. } if !dispatches.is_empty() => {
+                    let dispatch = &dispatches[0];
+                    nodes.push(format!(
+                        "    op{} [label=\"{}\", shape=box];",
+                        node,
+                        escape_dot(self.modules[dispatch.module].entry_point[dispatch.entry_point])
+                    ));
+                }
+                Solver::ReadBuffer(_) => {
+                    nodes.push(format!("    op{} [label=\"read back\", shape=note];", node));
+                }
+                // the copies between the buffers have no dispatch to show
+                _ => continue,
+            };
+            let accesses = solver.accesses();
+
+            for (resource, read, write) in accesses {
+                let label = escape_dot(&self.resource_name(resource));
+                let (last_writer, readers) = resources.entry(resource).or_default();
+                if let Some(writer) = *last_writer {
+                    let style = if read { "solid" } else { "dashed" };
+                    edges.push(format!(
+                        "    op{} -> op{} [label=\"{}\", style={}];",
+                        writer, node, label, style
+                    ));
+                }
+                if write {
+                    for &reader in readers.iter().filter(|&&reader| reader != node) {
+                        edges.push(format!(
+                            "    op{} -> op{} [label=\"{}\", style=dashed];",
+                            reader, node, label
+                        ));
+                    }
+                    *last_writer = Some(node);
+                    readers.clear();
+                } else {
+                    readers.push(node);
+                }
+            }
+        }
+
+        let mut dot = format!(
+            "digraph \"{}\" {{\n",
+            escape_dot(self.label.as_deref().unwrap_or("Algorithm"))
+        );
+        for line in nodes.iter().chain(&edges) {
+            dot.push_str(line);
+            dot.push('\n');
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    // gives the name of a variable or texture, or its index if it has none
+    fn resource_name(&self, resource: Resource) -> String {
+        match resource {
+            Resource::Variable(index) => variable_name(&self.variables[index].variable)
+                .unwrap_or_else(|| format!("variable {}", index)),
+            Resource::Texture(index) => self.textures[index]
+                .variable
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .get_name()
+                .map_or_else(|| format!("texture {}", index), str::to_owned),
+        }
+    }
+
+    /// Sets the verbose mode, which logs every binding decision taken by [`Algorithm::add_fun`]
+    ///
+    /// For each variable of a [`Function`] it logs (at the `info` level of the [`log`] crate) whether it matched an
+    /// already stored [`Variable`] (by [`Arc::ptr_eq`]) or a new one was stored, the index of the buffer it got,
+    /// and the bind group and binding it was assigned. This helps to understand why two variables did or didn't
+    /// share a buffer. It has no effect on the operations.
+    pub fn set_verbose(&mut self, verbose: bool) {
+        self.verbose = verbose;
+    }
+
+    /// Sets the fusion of the serial functions, which submits together all the operations between two read backs
+    ///
+    /// By default each [`Function`] (and each parallel batch) of a run is submitted to the queue on its own, and every
+    /// submission has a cost on the CPU which dominates long chains of small kernels (e.g. many elementwise
+    /// operations). When set, the command buffers of the consecutive operations are collected and submitted at once
+    /// before the next [`Algorithm::read_variable`] and at the end of the run. The operations still execute in the
+    /// order they're added, so the results are the same, and [`RunStats::submissions`] counts the submissions made.
+    /// It has no effect on [`Algorithm::run_with_deadline`], which needs to wait for each operation.
+    ///
+    /// # Example
+    /// ```ignore
+    /// algorithm.set_fuse_serial(true);
+    /// for function in functions {
+    ///     algorithm.add_fun(function)?;
+    /// }
+    /// algorithm.read_variable(&output)?;
+    /// algorithm.run().await?;
+    /// assert_eq!(algorithm.last_run_stats().submissions, 1);
+    /// ```
+    pub fn set_fuse_serial(&mut self, fuse: bool) {
+        self.fuse_serial = fuse;
+    }
+
+    // logs the binding decisions of a function for the verbose mode
+    fn log_binds(
+        &self,
+        entry_point: &str,
+        bindings: &[(u32, usize)],
+        groups: &[u32],
+        matched: &[bool],
+    ) {
+        for ((&(binding, var_pos), &group), &matched) in bindings.iter().zip(groups).zip(matched) {
+            let sto_var = &self.variables[var_pos];
+            log::info!(
+                "{:?} Algorithm, {}: variable {:?} {} stored variable {}, buffer {}, group {} binding {}",
+                self.label,
+                entry_point,
+                variable_name(&sto_var.variable),
+                if matched { "matched" } else { "added as" },
+                var_pos,
+                sto_var.buffer_index,
+                group,
+                binding
+            );
+        }
+    }
+
+    /// Gives access to the [`Executor`] of the [`Algorithm`]
+    ///
+    /// It's useful to create resources on the same device of the [`Algorithm`], like the destination
+    /// of [`Algorithm::copy_variable_to_external`]
+    pub fn executor(&self) -> &Executor {
+        &self.executor
+    }
+
+    // gives the (module, entry point) position of the entry point of the shader, storing them if needed
+    fn find_or_add_module(&mut self, shader: ShaderRef<'a>, entry_point: &'a str) -> [usize; 2] {
+        if let Some(pos) = self
+            .modules
+            .iter()
+            .position(|existing_module| existing_module.shader == shader)
+        {
+            if let Some(index) = self.modules[pos].find_entry_point(entry_point) {
+                [pos, index]
+            } else {
+                self.modules[pos].add_entry_point(entry_point);
+                [pos, self.modules[pos].entry_point.len() - 1]
+            }
+        } else {
+            self.modules.push(Module {
+                shader,
+                entry_point: vec![entry_point],
+            });
+            [self.modules.len() - 1, 0]
+        }
+    }
+
+    // finds the position of the variable between the ones already stored in the [`Algorithm`]
+    fn find_variable(&self, var: &Arc<Mutex<V>>) -> Option<usize> {
+        self.variables
+            .iter()
+            .position(|sto_var| Arc::ptr_eq(&sto_var.variable, var))
+    }
+
+    // compares only the addresses, since the texture can be given as a concrete type or as a trait object
+    fn find_texture<T: TextureVariable + ?Sized>(&self, texture: &Arc<Mutex<T>>) -> Option<usize> {
+        let address = Arc::as_ptr(texture) as *const ();
+        self.textures
+            .iter()
+            .position(|sto_texture| Arc::as_ptr(&sto_texture.variable) as *const () == address)
+    }
+
+    // gives the index of the stored texture, creating the texture if it's new
+    fn find_or_store_texture(
+        &mut self,
+        texture: &Arc<Mutex<dyn TextureVariable>>,
+    ) -> Result<usize, anyhow::Error> {
+        if let Some(index) = self.find_texture(texture) {
+            return Ok(index);
+        }
+        let texture_lock = lock_texture(texture)?;
+        let [width, height] = texture_lock.texture_size();
+        let texel_size = texture_lock.format().block_size(None).unwrap_or(0);
+        let byte_size = texel_size as u64 * width as u64 * height as u64;
+        if byte_size == 0 || texture_lock.byte_data().len() as u64 != byte_size {
+            return Err(anyhow!(
+                "Texture {:?} of {}x{} texels of format {:?} can't hold {} bytes",
+                texture_lock.get_name(),
+                width,
+                height,
+                texture_lock.format(),
+                texture_lock.byte_data().len()
+            ));
+        }
+        let wgpu_texture = self
+            .executor
+            .get_texture(&texture_lock.to_texture_descriptor());
+        drop(texture_lock);
+        let view = wgpu_texture.create_view(&wgpu::TextureViewDescriptor::default());
+        self.textures.push(StoredTexture {
+            variable: Arc::clone(texture),
+            texture: wgpu_texture,
+            view,
+            pending_upload: true,
+        });
+        Ok(self.textures.len() - 1)
+    }
+}
+
+/// Blocking versions of the asynchronous methods of the [`Algorithm`], for the scripts without an async runtime
+///
+/// Each method runs the asynchronous one with [`pollster::block_on`] on the current thread, so it must not be called
+/// from inside an async runtime. They need the `blocking` feature.
+#[cfg(feature = "blocking")]
+impl<'a, V: Variable> Algorithm<'a, V> {
+    /// Creates a new empty [`Algorithm`] like [`Algorithm::new`], blocking until the [`Executor`] is ready
+    ///
+    /// # Example
+    /// ```ignore
+    /// let mut algorithm = Algorithm::new_blocking(Some("Test algorithm"))?;
+    /// algorithm.add_fun(function)?;
+    /// algorithm.read_variable(&var)?;
+    /// algorithm.run_blocking()?;
+    /// ```
+    ///
+    /// # Errors
+    /// - if the [`Executor`] fails to instantiate
+    pub fn new_blocking(label: Option<&str>) -> Result<Algorithm<'a, V>, anyhow::Error> {
+        pollster::block_on(Algorithm::new(label))
+    }
+
+    /// Executes the calculation like [`Algorithm::run`], blocking until it's submitted and its reads are done
+    ///
+    /// # Errors
+    /// - all the errors of [`Algorithm::run`]
+    pub fn run_blocking(&mut self) -> Result<(), anyhow::Error> {
+        pollster::block_on(self.run())
+    }
+
+    /// Overwrites the [`Variable`] *`var` with the current content of its GPU buffer like
+    /// [`Algorithm::get_output_unmap`], blocking until it's read
+    ///
+    /// # Errors
+    /// - all the errors of [`Algorithm::get_output_unmap`]
+    pub fn read_variable_blocking(&self, var: &Arc<Mutex<V>>) -> Result<(), anyhow::Error> {
+        pollster::block_on(self.get_output_unmap(var))
+    }
+}
+
+impl<V: Variable> CompiledAlgorithm<V> {
+    /// This method runs all the [`Function`]s of the [`CompiledAlgorithm`] once
+    ///
+    /// The [`Variable`]s marked with [`CompiledAlgorithm::mark_dirty`] are written to the GPU first, then the
+    /// recorded dispatches go in a single compute pass, submitted without waiting for it to complete.
+    /// The outputs can be read with [`CompiledAlgorithm::get_output`].
+    ///
+    /// # Errors
+    /// - if the [`Variable::byte_data`] of a [`Variable`] to upload doesn't have as many bytes as its
+    ///   [`Variable::byte_size`], or its lock is poisoned. Nothing is submitted, and the variable is left to upload
+    pub fn step(&mut self) -> Result<(), anyhow::Error> {
+        for sto_var in self
+            .variables
+            .iter_mut()
+            .filter(|sto_var| sto_var.pending_upload)
+        {
+            let var_lock = lock_variable(&sto_var.variable)?;
+            let buffer = &self.buffers[sto_var.buffer_index];
+            self.executor
+                .write_buffer(buffer, buffer_upload_data(&*var_lock, buffer)?);
+            drop(var_lock);
+            sto_var.pending_upload = false;
+        }
+
+        let mut command_encoder = self.executor.create_encoder(self.label.as_deref());
+        {
+            let mut compute_pass =
+                command_encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                    label: self.label.as_deref(),
+                    timestamp_writes: None,
+                });
+            record_passes(&mut compute_pass, &self.passes);
+        }
+        self.executor.execute([command_encoder.finish()]);
+        Ok(())
+    }
+
+    /// This method marks the [`Variable`] *`var` as changed on the CPU, so that it's written to the GPU
+    /// at the next [`CompiledAlgorithm::step`]
+    ///
+    /// The data uploaded is the one the [`Variable`] holds at the time of the step.
+    ///
+    /// # Errors
+    /// - if the variable is not found in the [`CompiledAlgorithm`]
+    /// - if the variable is an accumulator, whose buffer is owned by the GPU (see [`Algorithm::accumulate_into`])
+    /// - if the variable is a constant, whose buffer can't be written (see [`Algorithm::add_constant_mapped`])
+    pub fn mark_dirty(&mut self, var: &Arc<Mutex<V>>) -> Result<(), anyhow::Error> {
+        let index = self.find_variable(var).ok_or_else(|| {
+            anyhow!(
+                "Variable {:?} not found in {:?} CompiledAlgorithm",
+                variable_name(var),
+                self.label
+            )
+        })?;
+        if self.variables[index].accumulator {
+            return Err(anyhow!(
+                "Variable {:?} is an accumulator of {:?} CompiledAlgorithm, it can't be uploaded",
+                variable_name(var),
+                self.label
+            ));
+        }
+        if self.variables[index].constant {
+            return Err(anyhow!(
+                "Variable {:?} is a constant of {:?} CompiledAlgorithm, it can't be uploaded",
+                variable_name(var),
+                self.label
+            ));
+        }
+        self.variables[index].pending_upload = true;
+        Ok(())
+    }
+
+    /// This method overwrites the [`Variable`] *`var` with the current content of its GPU buffer
+    ///
+    /// The reading takes place after all the steps already submitted, like [`Algorithm::get_output_unmap`].
+    ///
+    /// # Errors
+    /// - if the variable is not found in the [`CompiledAlgorithm`]
+    /// - if the variable lock is poisoned
+    /// - if the size of the buffer doesn't match the [`Variable::byte_size`]
+    pub async fn get_output(&self, var: &Arc<Mutex<V>>) -> Result<(), anyhow::Error> {
+        let index = self.find_variable(var).ok_or_else(|| {
+            anyhow!(
+                "Variable {:?} not found in {:?} CompiledAlgorithm",
+                variable_name(var),
+                self.label
+            )
+        })?;
+        let buffer = &self.buffers[self.variables[index].buffer_index];
+        read_into_variable(&self.executor, buffer, var).await
+    }
+
+    // finds the position of the variable between the ones of the [`CompiledAlgorithm`]
+    fn find_variable(&self, var: &Arc<Mutex<V>>) -> Option<usize> {
+        self.variables
+            .iter()
+            .position(|sto_var| Arc::ptr_eq(&sto_var.variable, var))
+    }
+}
+
+// records again the dispatches of the `passes` in the compute pass, in order
+fn record_passes<'p>(compute_pass: &mut wgpu::ComputePass<'p>, passes: &'p [RecordedPass]) {
+    for pass in passes {
+        for pipeline in &pass.pipelines {
+            compute_pass.set_pipeline(pipeline);
+            if !pass.push_constants.is_empty() {
+                compute_pass.set_push_constants(0, &pass.push_constants);
+            }
+            for (bind_groups, workgroups) in &pass.dispatches {
+                for (group, bind_group) in bind_groups.iter().enumerate() {
+                    compute_pass.set_bind_group(group as u32, bind_group, &[]);
                 }
+                compute_pass.dispatch_workgroups(workgroups[0], workgroups[1], workgroups[2]);
             }
         }
+    }
+}
 
-        Ok(())
+// reads the buffer back into the variable, checking its size
+async fn read_into_variable<V: Variable>(
+    executor: &Executor,
+    buffer: &wgpu::Buffer,
+    var: &Arc<Mutex<V>>,
+) -> Result<(), anyhow::Error> {
+    let result = executor.read_buffer(buffer).await;
+
+    let mut var_write = lock_variable(var)?;
+    write_back(&mut *var_write, &result)
+}
+
+// locks the variable, giving a [`LockError`] instead of panicking if a thread panicked while holding the lock
+pub(crate) fn lock_variable<V: Variable>(
+    var: &Mutex<V>,
+) -> Result<MutexGuard<'_, V>, anyhow::Error> {
+    var.lock().map_err(|poisoned| {
+        LockError::Variable(poisoned.get_ref().get_name().map(str::to_owned)).into()
+    })
+}
+
+// locks the texture like [`lock_variable`]
+fn lock_texture<T: TextureVariable + ?Sized>(
+    texture: &Mutex<T>,
+) -> Result<MutexGuard<'_, T>, anyhow::Error> {
+    texture.lock().map_err(|poisoned| {
+        LockError::Texture(poisoned.get_ref().get_name().map(str::to_owned)).into()
+    })
+}
+
+// gives the name of the variable for the messages, reading it also when the lock is poisoned
+pub(crate) fn variable_name<V: Variable>(var: &Mutex<V>) -> Option<String> {
+    var.lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get_name()
+        .map(str::to_owned)
+}
+
+// gives the bytes of `var` to be written to its buffer, if they're as many as its byte size
+fn upload_data<V: Variable>(var: &V) -> Result<&[u8], anyhow::Error> {
+    let data = var.byte_data();
+    if data.len() as u64 != var.byte_size() {
+        return Err(VariableError::<u32>::SizeMismatch {
+            expected: var.byte_size(),
+            got: data.len() as u64,
+        }
+        .into());
     }
+    Ok(data)
+}
 
-    /// This method overwrite the [`Variable`] *`var` with the ouptut of the calculation
-    ///
-    /// reading from a GPU buffer is in general an expensive operation. This functions calls the
-    /// correct method on the [`Executor`] to read the GPU buffer asycronously and with the least
-    /// amount of effort possible.
-    ///
-    /// The function returns an error if the variable is not found in the [`Algorithm`] or
-    pub fn read_variable(&mut self, var: &Arc<Mutex<V>>) -> Result<(), anyhow::Error> {
-        match self
-            .variables
-            .iter()
-            .position(|existing_var| Arc::ptr_eq(&existing_var.variable, var))
-        {
-            None => {
-                return Err(anyhow!(
-                    "Variable {:?} not found in {:?} Algorithm",
-                    var.lock().unwrap().get_name(),
-                    self.label
-                ));
-            }
-            Some(index) => {
-                self.solvers.push(Solver::ReadBuffer(index));
-                return Ok(());
-            }
+// gives the bytes of `var` to be written to `buffer`, if they're as many as its byte size and fit the buffer
+fn buffer_upload_data<'v, V: Variable>(
+    var: &'v V,
+    buffer: &wgpu::Buffer,
+) -> Result<&'v [u8], anyhow::Error> {
+    let data = upload_data(var)?;
+    // the buffer can be longer than the data, e.g. for the alignment
+    if data.len() as u64 > buffer.size() {
+        return Err(VariableError::<u32>::SizeMismatch {
+            expected: buffer.size(),
+            got: data.len() as u64,
+        }
+        .into());
+    }
+    Ok(data)
+}
+
+// gives the bytes read from the buffer of `var` to [`Variable::read_data`], if they're as many as its byte size
+fn write_back<V: Variable>(var: &mut V, bytes: &[u8]) -> Result<(), anyhow::Error> {
+    let expected = var.byte_size();
+    if expected != bytes.len() as u64 {
+        return Err(VariableError::<u32>::SizeMismatch {
+            expected,
+            got: bytes.len() as u64,
         }
+        .into());
     }
+    var.read_data(bytes)
 }
 
-impl<V> Function<'_, V>
+impl<'a, V> Function<'a, V>
 where
     V: Variable,
 {
@@ -410,21 +3667,330 @@ where
     /// # Arguments
     /// * - `shader` - a reference to a [`Shader`] element, which contains the shader which will perform the operation
     /// * - `entry_point` - the name of the function inside the [`Shader`] which will execute the code
-    /// *- `vars` - an array reference of [`VariableBind`] which will be the variables passed to the GPU (with the relative bind number)
-    pub fn new<'a>(
+    /// * - `vars` - an array reference of [`VariableBind`] which will be the variables passed to the GPU (with the relative bind number)
+    pub fn new(
         shader: &'a Shader,
         entry_point: &'a str,
         variables: Vec<VariableBind<V>>,
+    ) -> Function<'a, V> {
+        Function::from_shader(ShaderRef::Borrowed(shader), entry_point, variables)
+    }
+
+    // creates a new function like [`Function::new`] on a shader it owns, e.g. rendered from a template
+    pub(crate) fn from_rendered(
+        shader: Shader,
+        entry_point: &'a str,
+        variables: Vec<VariableBind<V>>,
+    ) -> Function<'a, V> {
+        Function::from_shader(ShaderRef::Owned(Arc::new(shader)), entry_point, variables)
+    }
+
+    fn from_shader(
+        shader: ShaderRef<'a>,
+        entry_point: &'a str,
+        variables: Vec<VariableBind<V>>,
     ) -> Function<'a, V> {
         Function {
             shader,
             entry_point,
             variables,
+            dimensions_binding: None,
+            dispatch_offset: [0; 3],
+            tile_size: None,
+            group_labels: Vec::new(),
+            textures: Vec::new(),
+            sentinel_binding: None,
+            fused: Vec::new(),
+            read_only_bindings: Vec::new(),
+            workgroups: None,
+            push_constants: Vec::new(),
+            uniform_data: None,
+        }
+    }
+
+    /// Creates a new function made of the entry points of `stages`, dispatched one after the other on the same variables
+    ///
+    /// The stages can come from different [`Shader`]s, and they're recorded in a single compute pass sharing one
+    /// bind group, so that tightly coupled kernels (e.g. a computation followed by a normalization) don't pay for binding
+    /// the variables and starting a pass each. Each stage sees the results of the previous one.
+    /// All the stages must declare the same bindings and the same `@workgroup_size`, the dispatch is
+    /// calculated once for the whole [`Function`] as for [`Function::new`].
+    ///
+    /// # Example
+    /// ```ignore
+    /// let function = Function::fused(&[(&shader, "add_1"), (&other_shader, "double")], bindings)?;
+    /// algorithm.add_fun(function)?;
+    /// ```
+    ///
+    /// # Errors
+    /// - if there are no `stages`
+    pub fn fused(
+        stages: &[(&'a Shader, &'a str)],
+        variables: Vec<VariableBind<V>>,
+    ) -> Result<Function<'a, V>, anyhow::Error> {
+        let (&(shader, entry_point), rest) = stages
+            .split_first()
+            .ok_or_else(|| anyhow!("A fused Function needs at least one stage"))?;
+        let mut function = Function::new(shader, entry_point, variables);
+        function.fused = rest
+            .iter()
+            .map(|&(shader, entry_point)| (ShaderRef::Borrowed(shader), entry_point))
+            .collect();
+        Ok(function)
+    }
+
+    /// Creates a new function like [`Function::new`], checking the variables against the schema of the `entry_point`
+    ///
+    /// The `schema` declares the bindings the entry point expects, in the same order as the `variables`.
+    /// The check is done on the host before anything is sent to the GPU, so that a mismatch between the
+    /// shader and the Rust code gives a precise error instead of a validation panic from [`wgpu`].
+    ///
+    /// # Errors
+    /// - if the number of variables is different from the number of bindings of the schema
+    /// - if a variable is bound at a different binding than the one in the same position of the schema
+    /// - if a variable is in a different bind group than the one in the same position of the schema
+    /// - if the schema declares a binding of a different kind than the one the variable is bound as: a uniform for a
+    ///   variable bound with [`VariableBind::as_uniform`], a read_write storage otherwise
+    /// - if a variable gives a [`Variable::wgsl_type`] different from the one of its binding
+    pub fn new_checked(
+        shader: &'a Shader,
+        entry_point: &'a str,
+        schema: &[BindingSchema],
+        variables: Vec<VariableBind<V>>,
+    ) -> Result<Function<'a, V>, anyhow::Error> {
+        if schema.len() != variables.len() {
+            return Err(BindingError::CountMismatch {
+                expected: schema.len(),
+                got: variables.len(),
+            }
+            .into());
+        }
+        for (position, (expected, bind)) in schema.iter().zip(&variables).enumerate() {
+            if expected.group != bind.group {
+                return Err(BindingError::UnexpectedGroup {
+                    position,
+                    expected: expected.group,
+                    got: bind.group,
+                }
+                .into());
+            }
+            if expected.binding != bind.bind_group {
+                return Err(BindingError::BindingMismatch {
+                    position,
+                    expected: expected.binding,
+                    got: bind.bind_group,
+                }
+                .into());
+            }
+            // the variables are bound as read_write storage, unless bound with [`VariableBind::as_uniform`]
+            let bound = if bind.uniform {
+                BindingKind::Uniform
+            } else {
+                BindingKind::Storage { read_only: false }
+            };
+            if expected.kind != bound {
+                return Err(BindingError::KindMismatch {
+                    binding: expected.binding,
+                    expected: format!("{:?}", expected.kind),
+                    got: format!("{:?}", bound),
+                }
+                .into());
+            }
+            let variable = lock_variable(&bind.variable)?;
+            if let Some(wgsl_type) = variable.wgsl_type() {
+                if !same_wgsl_type(wgsl_type, &expected.wgsl_type) {
+                    return Err(BindingError::TypeMismatch {
+                        binding: expected.binding,
+                        expected: expected.wgsl_type.clone(),
+                        got: wgsl_type.to_owned(),
+                    }
+                    .into());
+                }
+            }
         }
+        Ok(Function::new(shader, entry_point, variables))
+    }
+
+    /// Passes the dimensions of the first [`Variable`] to the shader as a uniform at the `binding` number
+    ///
+    /// The uniform holds the [`Variable::dimension_sizes`] as a `vec4<u32>` (the last element is padding),
+    /// followed by the dispatch offset as another `vec4<u32>` (see [`Function::with_dispatch_offset`]), and all the storage bindings of the [`Function`] are declared as runtime sized, so that the shader can
+    /// declare them as `array<T>` instead of templating their size.
+    /// This way the same [`Shader`] source and bind group layout serve [`Variable`]s of any size,
+    /// with the size delivered as data rather than baked into the source.
+    ///
+    /// # Example
+    /// The shader declares the uniform at the chosen binding, and uses it to index the runtime sized array
+    /// ```wgsl
+    /// @group(0) @binding(0)
+    /// var<storage,read_write> a: array<f32>;
+    /// @group(0) @binding(1)
+    /// var<uniform> dims: vec4<u32>;
+    /// ```
+    pub fn with_dimensions(mut self, binding: u32) -> Self {
+        self.dimensions_binding = Some(binding);
+        self
+    }
+
+    /// Sets the offset of the dispatch of the [`Function`], delivered to the shader with the dimensions
+    ///
+    /// The offset is the second `vec4<u32>` of the uniform set by [`Function::with_dimensions`], which is needed
+    /// for the offset to reach the shader. The shader adds it to the `global_invocation_id` to find the element to
+    /// work on, so that a dispatch can cover a sub-region of the [`Variable`]s.
+    /// [`Algorithm::add_function_tiled`] uses it to dispatch the same [`Function`] over many tiles.
+    ///
+    /// # Example
+    /// ```wgsl
+    /// struct Dimensions {
+    ///     size: vec4<u32>,
+    ///     offset: vec4<u32>,
+    /// }
+    /// @group(0) @binding(1)
+    /// var<uniform> dims: Dimensions;
+    /// ```
+    pub fn with_dispatch_offset(mut self, offset: [u32; 3]) -> Self {
+        self.dispatch_offset = offset;
+        self
+    }
+
+    /// Sets the number of workgroups dispatched for the [`Function`], instead of deriving it from its first [`Variable`]
+    ///
+    /// By default the [`Algorithm`] dispatches enough workgroups of the `@workgroup_size` of the entry point to cover
+    /// the dimensions of the first [`Variable`]. Some kernels need a different dispatch, like a reduction which
+    /// runs one workgroup for each chunk of its input, or a strided kernel: the `workgroups` are then dispatched as given,
+    /// without dividing them by the `@workgroup_size`.
+    /// The dimensions uniform (see [`Function::with_dimensions`]) still holds the dimensions of the first [`Variable`].
+    ///
+    /// # Example
+    /// ```ignore
+    /// // one workgroup of 64 invocations sums 64 elements of the 4096 of the input
+    /// let function = Function::new(&shader, "partial_sums", bindings).with_workgroups([4096 / 64, 1, 1]);
+    /// algorithm.add_fun(function)?;
+    /// ```
+    pub fn with_workgroups(mut self, workgroups: [u32; 3]) -> Self {
+        self.workgroups = Some(workgroups);
+        self
+    }
+
+    /// Sets the bytes of the push constants of the [`Function`], declared with `var<push_constant>` in the WGSL shader
+    ///
+    /// Push constants are a small block of data recorded straight in the commands of the dispatch, so that passing
+    /// e.g. the index of an iteration or a scale factor to each [`Function`] doesn't need a buffer written for each of them.
+    /// The device must be created with the [`wgpu::Features::PUSH_CONSTANTS`] feature and a big enough
+    /// [`wgpu::Limits::max_push_constant_size`] (see [`Algorithm::with_options`]), and the size must be a multiple of 4.
+    /// They're the same for all the stages and tiles of the [`Function`].
+    ///
+    /// # Example
+    /// ```ignore
+    /// // var<push_constant> scale: f32;
+    /// let function = Function::new(&shader, "scale", bindings).with_push_constants(bytemuck::bytes_of(&2f32));
+    /// ```
+    pub fn with_push_constants(mut self, data: &[u8]) -> Self {
+        self.push_constants = data.to_vec();
+        self
+    }
+
+    /// Gives a label to the bind group `group` of the [`Function`]
+    ///
+    /// The label is used for the [`wgpu::BindGroup`] and its [`wgpu::BindGroupLayout`], so that each group
+    /// can be told apart in a GPU debugger capture. Setting the label of the same group twice keeps the last one.
+    /// Since bind group layouts are shared between functions with the same bindings, a layout keeps the label
+    /// of the first [`Function`] which created it. The groups without a label are labelled with the entry point.
+    pub fn with_group_label(mut self, group: u32, label: &'a str) -> Self {
+        self.group_labels.retain(|&(existing, _)| existing != group);
+        self.group_labels.push((group, label));
+        self
+    }
+
+    /// Binds a sentinel flag to the [`Function`] at the `binding` number, to check it ran with [`Algorithm::verify_executed`]
+    ///
+    /// The [`Algorithm`] creates a `u32` buffer initialized to 0, and by convention the shader sets it to any other
+    /// value when it runs. Checking the flag only reads 4 bytes, which makes it a cheap confirmation that the dispatch
+    /// ran and wrote to memory, without reading back its results (e.g. in smoke tests).
+    ///
+    /// # Example
+    /// ```wgsl
+    /// @group(0) @binding(1)
+    /// var<storage,read_write> sentinel: u32;
+    ///
+    /// @compute @workgroup_size(1)
+    /// fn add_1 (@builtin(global_invocation_id) id: vec3<u32>) {
+    ///     sentinel = 1u;
+    ///     // ...
+    /// }
+    /// ```
+    pub fn with_sentinel(mut self, binding: u32) -> Self {
+        self.sentinel_binding = Some(binding);
+        self
+    }
+
+    // binds `data` as a uniform at `binding` of the group 0, in a buffer created and written by [`Algorithm::add_fun`]
+    //
+    // It passes a few bytes (e.g. the scalar of [`ops::scale`]) without making a [`Variable`] of them, which
+    // a generic [`Algorithm`] couldn't create.
+    pub(crate) fn with_uniform_data(mut self, binding: u32, data: &[u8]) -> Self {
+        self.uniform_data = Some((binding, data.to_vec()));
+        self
+    }
+
+    /// Adds an [`Immutable`] [`VariableBind`] to the variables of the [`Function`], bound as read only
+    ///
+    /// The shader must declare the binding as `var<storage, read>`: the GPU is then told the buffer isn't written by
+    /// the [`Function`], and declaring it `read_write` fails the validation of the pipeline.
+    /// The variable is added after the ones given at creation, so it doesn't decide the workgroups unless it's the only one.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let input = VariableBind::new(Arc::clone(&input), 1).set_immutable();
+    /// let function = Function::new(&shader, "add_matrices", vec![VariableBind::new(output, 0)]).with_immutable(input);
+    /// ```
+    pub fn with_immutable(mut self, bind: VariableBind<V, Immutable>) -> Self {
+        self.read_only_bindings.push((bind.group, bind.bind_group));
+        self.variables.push(bind.set_mutable());
+        self
+    }
+
+    /// Binds a [`TextureVariable`] to the [`Function`] at the `binding` number of the bind group 0
+    ///
+    /// With [`TextureAccess::Read`] the shader declares the texture as `texture_2d<f32>` and reads it with `textureLoad`,
+    /// with [`TextureAccess::Write`] as `texture_storage_2d<r32float, write>` (with the [`TextureVariable::format`])
+    /// and writes it with `textureStore`. The same [`TextureVariable`] can be written by a [`Function`] and read by the
+    /// following ones, while being read and written by the same [`Function`] isn't supported.
+    ///
+    /// If the [`Function`] has no [`Variable`] the workgroups are given by the size of the first texture, one per texel.
+    /// The result is read back with [`Algorithm::read_texture`].
+    ///
+    /// # Example
+    /// ```wgsl
+    /// @group(0) @binding(0)
+    /// var input: texture_2d<f32>;
+    /// @group(0) @binding(1)
+    /// var output: texture_storage_2d<r32float, write>;
+    /// ```
+    pub fn with_texture<T: TextureVariable + 'static>(
+        mut self,
+        texture: Arc<Mutex<T>>,
+        binding: u32,
+        access: TextureAccess,
+    ) -> Self {
+        self.textures.push(TextureBind {
+            texture,
+            binding,
+            access,
+        });
+        self
+    }
+
+    // gives the label of the bind group `group`, if set
+    fn group_label(&self, group: u32) -> Option<&'a str> {
+        self.group_labels
+            .iter()
+            .find(|&&(existing, _)| existing == group)
+            .map(|&(_, label)| label)
     }
 }
 
-impl<'a, V> VariableBind<V, Mutable>
+impl<V> VariableBind<V, Mutable>
 where
     V: Variable,
 {
@@ -446,7 +4012,36 @@ where
         // let variable = Arc::clone(Mutex::new(*var));
         VariableBind {
             variable,
+            group: 0,
+            bind_group,
+            view: None,
+            uniform: false,
+            output: false,
+            mutable: Default::default(),
+        }
+    }
+
+    /// Creates a new [`VariableBind`] from a [`VariableView`] and the binding group number
+    ///
+    /// Only the subrange of the parent [`Variable`] buffer defined by the view will be bound to the
+    /// bind group number defined in the WGSL shader, which will see it as a whole buffer.
+    /// The parent [`Variable`] is stored in the [`Algorithm`] as any other, i.e. its
+    /// buffer is written only once and shared by all of its binds and views.
+    ///
+    /// Notice that the workgroups of a [`Function`] whose first bind is a view will still be calculated
+    /// from the parent [`Variable`].
+    ///
+    /// # Arguments
+    /// * - `view` - the [`VariableView`] of the variable to bind
+    /// * - `bind_group` - the bind group number the view will be associated with in the WGSL shader
+    pub fn from_view(view: VariableView<V>, bind_group: u32) -> VariableBind<V, Mutable> {
+        VariableBind {
+            variable: view.parent,
+            group: 0,
             bind_group,
+            view: Some((view.offset, view.len)),
+            uniform: false,
+            output: false,
             mutable: Default::default(),
         }
     }
@@ -459,22 +4054,47 @@ where
         true
     }
 
-    // /// Sets the [`VariableBind`] to be immutable, thus read only
-    // ///
-    // /// It is not unsafe per se, but set as such to warn about the possible implications of this.
-    // /// At the time of writing any variable can be set as read/write and set as immutable. This could potentially
-    // /// cause concurrency problems when queueing the pipelines on tha GPU.
-    // /// An immutable [`VariableBind`] is considered not to change during the calculation.
-    // pub unsafe fn set_immutable(self) -> VariableBind<V, Immutable> {
-    //     VariableBind {
-    //         variable: self.variable,
-    //         bind_group: self.bind_group,
-    //         mutable: std::marker::PhantomData::<Immutable>,
-    //     }
-    // }
+    /// Marks the [`VariableBind`] as an output of its [`Function`], i.e. a [`Variable`] the shader writes the result to
+    ///
+    /// The outputs are read back together with [`Algorithm::read_outputs`] and make the data flow between the
+    /// [`Function`]s explicit, instead of implicit in the shader code. The binds which aren't outputs are the inputs.
+    /// An in-place operation (like `add_1`, which reads and writes its only variable) marks as output the same bind it
+    /// reads, while an out-of-place one marks the bind it writes to and leaves the others as inputs.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let bindings = vec![
+    ///     VariableBind::new(Arc::clone(&output), 0).as_output(),
+    ///     VariableBind::new(Arc::clone(&input), 1),
+    /// ];
+    /// let handle = algorithm.add_fun(Function::new(&shader, "add_matrices", bindings))?;
+    /// algorithm.read_outputs(handle)?;
+    /// ```
+    pub fn as_output(mut self) -> Self {
+        self.output = true;
+        self
+    }
+
+    /// Sets the [`VariableBind`] to be immutable, thus read only
+    ///
+    /// An immutable [`VariableBind`] is considered not to change during the calculation: it's added to a [`Function`]
+    /// with [`Function::with_immutable`] and bound as read only, so the shader must declare it as `var<storage, read>`.
+    ///
+    /// An [`Immutable`] bind is never an output, so [`VariableBind::as_output`] is dropped.
+    pub fn set_immutable(self) -> VariableBind<V, Immutable> {
+        VariableBind {
+            variable: self.variable,
+            group: self.group,
+            bind_group: self.bind_group,
+            view: self.view,
+            uniform: self.uniform,
+            output: false,
+            mutable: std::marker::PhantomData::<Immutable>,
+        }
+    }
 }
 
-impl<'a, V> VariableBind<V, Immutable>
+impl<V> VariableBind<V, Immutable>
 where
     V: Variable,
 {
@@ -494,23 +4114,192 @@ where
     pub fn set_mutable(self) -> VariableBind<V, Mutable> {
         VariableBind {
             variable: self.variable,
+            group: self.group,
             bind_group: self.bind_group,
+            view: self.view,
+            uniform: self.uniform,
+            output: self.output,
             mutable: std::marker::PhantomData::<Mutable>,
         }
     }
+
+    /// This method returns weather the variable is mutable or not, which is never the case for an [`Immutable`] bind
+    pub fn is_mutable(&self) -> bool {
+        false
+    }
+}
+
+impl<V, Type> VariableBind<V, Type>
+where
+    V: Variable,
+{
+    /// Moves the [`VariableBind`] to the bind group `group`, declared with `@group(group)` in the WGSL shader
+    ///
+    /// By default the variables are in the group 0. The [`Algorithm`] makes a [`wgpu::BindGroup`] for each group
+    /// from 0 to the highest one used by the [`Function`], so that e.g. the constants can be kept in a group separate
+    /// from the data. The textures, the dimensions and the sentinel of the [`Function`] are always in the group 0.
+    ///
+    /// # Example
+    /// ```wgsl
+    /// @group(0) @binding(0)
+    /// var<storage, read_write> data: array<f32>;
+    /// @group(1) @binding(0)
+    /// var<storage, read_write> constants: array<f32>;
+    /// ```
+    pub fn in_group(mut self, group: u32) -> Self {
+        self.group = group;
+        self
+    }
+
+    /// Binds the [`Variable`] as a uniform buffer, declared with `var<uniform>` in the WGSL shader
+    ///
+    /// Uniform buffers are faster than storage ones for the small constants read by every invocation, like scalars
+    /// and parameter structs, but they're read only and limited in size (64 KiB on most devices) and layout: the
+    /// arrays of a uniform need an element stride multiple of 16 bytes.
+    /// The buffer of the [`Variable`] gets the uniform usage when it's first added to the [`Algorithm`], so a
+    /// [`Variable`] already added by a storage bind can't be bound as uniform later on.
+    ///
+    /// # Example
+    /// ```wgsl
+    /// struct Params {
+    ///     scale: f32,
+    ///     offset: f32,
+    /// }
+    /// @group(0) @binding(1)
+    /// var<uniform> params: Params;
+    /// ```
+    pub fn as_uniform(mut self) -> Self {
+        self.uniform = true;
+        self
+    }
+
+    /// This method returns whether the [`VariableBind`] is an output of its [`Function`], see [`VariableBind::as_output`]
+    pub fn is_output(&self) -> bool {
+        self.output
+    }
+}
+
+impl<V> VariableView<V>
+where
+    V: Variable,
+{
+    /// Creates a new [`VariableView`] over `len` bytes of the `parent` [`Variable`], starting at `offset`
+    ///
+    /// # Arguments
+    /// * - `parent` - an Arc<Mutex> of the variable whose buffer holds the view
+    /// * - `offset` - the offset in bytes of the start of the view, multiple of [`VIEW_OFFSET_ALIGNMENT`]
+    /// * - `len` - the size in bytes of the view, non zero and multiple of 4
+    ///
+    /// # Errors
+    /// - if the offset is not aligned or the size is not valid for a storage binding
+    /// - if the view exceeds the [`Variable::byte_size`] of the parent
+    pub fn new(
+        parent: Arc<Mutex<V>>,
+        offset: u64,
+        len: u64,
+    ) -> Result<VariableView<V>, anyhow::Error> {
+        if !offset.is_multiple_of(VIEW_OFFSET_ALIGNMENT) {
+            return Err(anyhow!(
+                "View offset {} is not a multiple of {} bytes",
+                offset,
+                VIEW_OFFSET_ALIGNMENT
+            ));
+        }
+        if len == 0 || !len.is_multiple_of(4) {
+            return Err(anyhow!(
+                "View size {} is not a non zero multiple of 4 bytes",
+                len
+            ));
+        }
+        let parent_size = lock_variable(&parent)?.byte_size();
+        if offset + len > parent_size {
+            return Err(anyhow!(
+                "View [{}, {}) exceeds the size {} of the variable",
+                offset,
+                offset + len,
+                parent_size
+            ));
+        }
+        Ok(VariableView {
+            parent,
+            offset,
+            len,
+        })
+    }
+}
+
+impl<V> PingPong<V>
+where
+    V: Variable,
+{
+    /// Creates a new [`PingPong`], the first iteration reads `first` and writes `second`
+    ///
+    /// # Errors
+    /// - if the two [`Variable`]s have different [`Variable::byte_size`] or [`Variable::dimension_sizes`]
+    pub fn new(first: V, second: V) -> Result<PingPong<V>, anyhow::Error> {
+        if first.byte_size() != second.byte_size()
+            || first.dimension_sizes() != second.dimension_sizes()
+        {
+            return Err(anyhow!(
+                "Variables {:?} and {:?} can't be used as ping pong, they have different sizes",
+                first.get_name(),
+                second.get_name()
+            ));
+        }
+        Ok(PingPong {
+            sides: [Arc::new(Mutex::new(first)), Arc::new(Mutex::new(second))],
+            read: 0,
+        })
+    }
+
+    /// Flips the two sides, so that the written one becomes the one read by the next iteration
+    pub fn swap(&mut self) {
+        self.read = 1 - self.read;
+    }
+
+    /// Gives the side read by the next iteration, which holds the result of the last one
+    pub fn read_side(&self) -> &Arc<Mutex<V>> {
+        &self.sides[self.read]
+    }
+
+    /// Gives the side written by the next iteration
+    pub fn write_side(&self) -> &Arc<Mutex<V>> {
+        &self.sides[1 - self.read]
+    }
+
+    /// Gives the [`VariableBind`]s of the current iteration, the read side at `read_binding` and the
+    /// write side at `write_binding`
+    pub fn binds(&self, read_binding: u32, write_binding: u32) -> Vec<VariableBind<V>> {
+        vec![
+            VariableBind::new(Arc::clone(self.read_side()), read_binding),
+            VariableBind::new(Arc::clone(self.write_side()), write_binding),
+        ]
+    }
 }
 
 impl<V: Variable> StoredVariable<V> {
     /// Creates a [`wgpu::BindGroupLayoutEntry`] from [`self`]
     ///
     /// Useful to build the bind group layout for the executor to execute.
-    pub fn get_bind_group_layout_entry(&self, bind: u32) -> wgpu::BindGroupLayoutEntry {
-        let size = self.variable.lock().unwrap().byte_size();
+    /// The `view_size` is the size of the bound [`VariableView`], if any, and `binding_type` is a read only storage
+    /// for the [`Immutable`] binds and a uniform for the ones set with [`VariableBind::as_uniform`].
+    pub fn get_bind_group_layout_entry(
+        &self,
+        bind: u32,
+        view_size: Option<u64>,
+        binding_type: wgpu::BufferBindingType,
+    ) -> wgpu::BindGroupLayoutEntry {
+        let size = view_size.unwrap_or_else(|| {
+            self.variable
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .byte_size()
+        });
         wgpu::BindGroupLayoutEntry {
             binding: bind,
             visibility: wgpu::ShaderStages::COMPUTE,
             ty: wgpu::BindingType::Buffer {
-                ty: wgpu::BufferBindingType::Storage { read_only: false },
+                ty: binding_type,
                 min_binding_size: NonZeroU64::new(size),
                 has_dynamic_offset: false,
             },
@@ -519,8 +4308,275 @@ impl<V: Variable> StoredVariable<V> {
     }
 }
 
+// escapes the quotes and backslashes of a DOT string
+fn escape_dot(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+// compares two WGSL types ignoring the whitespaces
+fn same_wgsl_type(first: &str, second: &str) -> bool {
+    first
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .eq(second.chars().filter(|c| !c.is_whitespace()))
+}
+
+// gives the dimensions of the function and the dispatch offset, each padded to a vec4<u32>, to be passed as a uniform
+fn dimensions_uniform(dimensions: [u32; 3], offset: [u32; 3]) -> [u32; 8] {
+    let [x, y, z] = dimensions;
+    [x, y, z, 0, offset[0], offset[1], offset[2], 0]
+}
+
+// gives the layout entry of a 2D texture, sampled to be read or a storage texture to be written
+fn texture_layout_entry(
+    binding: u32,
+    access: TextureAccess,
+    format: wgpu::TextureFormat,
+) -> wgpu::BindGroupLayoutEntry {
+    let ty = match access {
+        TextureAccess::Read => wgpu::BindingType::Texture {
+            sample_type: wgpu::TextureSampleType::Float { filterable: false },
+            view_dimension: wgpu::TextureViewDimension::D2,
+            multisampled: false,
+        },
+        TextureAccess::Write => wgpu::BindingType::StorageTexture {
+            access: wgpu::StorageTextureAccess::WriteOnly,
+            format,
+            view_dimension: wgpu::TextureViewDimension::D2,
+        },
+    };
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::COMPUTE,
+        ty,
+        count: None,
+    }
+}
+
+// gives the layout entry of the uniform holding the dimensions
+//
+// The size is left to be checked against the shader, which can declare only the dimensions or also the offset.
+fn dimensions_layout_entry(binding: u32) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::COMPUTE,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    }
+}
+
+// splits the extent in tiles of at most `tile_size` workgroups of `workgroup_size` invocations
+fn tiles(
+    extent: [u32; 3],
+    workgroup_size: [u32; 3],
+    tile_size: [u32; 3],
+    base_offset: [u32; 3],
+    max_workgroups: u32,
+) -> Result<Vec<Tile>, anyhow::Error> {
+    if tile_size
+        .iter()
+        .any(|&size| size == 0 || size > max_workgroups)
+    {
+        return Err(anyhow!(
+            "Tile size {:?} is not valid, each dimension must be between 1 and {}",
+            tile_size,
+            max_workgroups
+        ));
+    }
+    let extent: [u32; 3] = std::array::from_fn(|id| extent[id].div_ceil(workgroup_size[id]));
+    let starts = |id: usize| (0..extent[id].max(1)).step_by(tile_size[id] as usize);
+    let mut tiles = Vec::new();
+    for z in starts(2) {
+        for y in starts(1) {
+            for x in starts(0) {
+                let origin = [x, y, z];
+                let mut offset = [0; 3];
+                let mut workgroups = [0; 3];
+                for id in 0..3 {
+                    offset[id] = base_offset[id] + origin[id] * workgroup_size[id];
+                    workgroups[id] = tile_size[id].min(extent[id].max(1) - origin[id]);
+                }
+                tiles.push(Tile { offset, workgroups });
+            }
+        }
+    }
+    Ok(tiles)
+}
+
+impl<V: Variable> Solver<V> {
+    // moves the indices of modules, entry points and variables to the ones of the [`Algorithm`] it's appended to
+    fn reindex(
+        &mut self,
+        modules: &[(usize, Vec<usize>)],
+        variables: &[usize],
+        textures: &[usize],
+    ) {
+        match self {
+            Solver::Serial { dispatches, .. } => {
+                for dispatch in dispatches {
+                    let (module, entry_points) = &modules[dispatch.module];
+                    dispatch.module = *module;
+                    dispatch.entry_point = entry_points[dispatch.entry_point];
+                    for (_, variable) in &mut dispatch.bindings {
+                        *variable = variables[*variable];
+                    }
+                    for (texture, _) in &mut dispatch.textures {
+                        *texture = textures[*texture];
+                    }
+                }
+            }
+            Solver::Parallel(solvers) => {
+                for solver in solvers {
+                    solver.reindex(modules, variables, textures);
+                }
+            }
+            Solver::ReadBuffer(variable) => *variable = variables[*variable],
+        }
+    }
+
+    // whether the solver only dispatches functions or reads a variable, which [`Algorithm::rerun`] can replay
+    fn is_replayable(&self) -> bool {
+        match self {
+            Solver::Serial {
+                handle, dispatches, ..
+            } => handle.is_some() && !dispatches.is_empty(),
+            Solver::Parallel(solvers) => solvers.iter().all(Solver::is_replayable),
+            Solver::ReadBuffer(_) => true,
+        }
+    }
+
+    // moves the recorded passes of the functions in the solver to `passes`, in order
+    fn take_passes(
+        self,
+        recorded: &mut HashMap<FunctionHandle, RecordedPass>,
+        passes: &mut Vec<RecordedPass>,
+    ) -> Result<(), anyhow::Error> {
+        match self {
+            Solver::Serial {
+                handle: Some(handle),
+                dispatches,
+                ..
+            } if !dispatches.is_empty() => {
+                let pass = recorded
+                    .remove(&handle)
+                    .ok_or_else(|| anyhow!("Function {:?} has no recorded pass", handle))?;
+                passes.push(pass);
+                Ok(())
+            }
+            Solver::Serial { .. } => Err(anyhow!(
+                "Only Functions can be compiled, but a copy or a reset of a buffer is scheduled"
+            )),
+            Solver::Parallel(solvers) => {
+                for solver in solvers {
+                    solver.take_passes(recorded, passes)?;
+                }
+                Ok(())
+            }
+            Solver::ReadBuffer(_) => Err(anyhow!(
+                "Reads can't be compiled, use CompiledAlgorithm::get_output after a step instead"
+            )),
+        }
+    }
+
+    // gives the (resource, read, written) accessed by the dispatches or the read of the solver
+    //
+    // The copies between buffers have no dispatch, and give no access.
+    fn accesses(&self) -> Vec<(Resource, bool, bool)> {
+        match self {
+            Solver::Serial { dispatches, .. } => {
+                let mut accesses = Vec::new();
+                // the stages and tiles of a function all have the same bindings
+                if let Some(dispatch) = dispatches.first() {
+                    accesses.extend(dispatch.bindings.iter().zip(&dispatch.read_only).map(
+                        |(&(_, var), &read_only)| (Resource::Variable(var), true, !read_only),
+                    ));
+                    accesses.extend(dispatch.textures.iter().map(|&(texture, access)| {
+                        let read = access == TextureAccess::Read;
+                        (Resource::Texture(texture), read, !read)
+                    }));
+                }
+                accesses
+            }
+            Solver::Parallel(solvers) => solvers.iter().flat_map(Solver::accesses).collect(),
+            Solver::ReadBuffer(var) => vec![(Resource::Variable(*var), true, false)],
+        }
+    }
+
+    // gives the first resource written by one of the parallel `solvers` and accessed by another one, with their positions
+    //
+    // The operations of a parallel batch can run in any order on the GPU, so they must not depend on each other.
+    fn parallel_hazard(solvers: &[Solver<V>]) -> Option<(Resource, usize, usize)> {
+        let accesses: Vec<Vec<(Resource, bool, bool)>> =
+            solvers.iter().map(Solver::accesses).collect();
+        for (first, first_accesses) in accesses.iter().enumerate() {
+            for (second, second_accesses) in accesses.iter().enumerate().skip(first + 1) {
+                for &(resource, _, first_writes) in first_accesses {
+                    let hazard = second_accesses.iter().any(|&(other, _, second_writes)| {
+                        other == resource && (first_writes || second_writes)
+                    });
+                    if hazard {
+                        return Some((resource, first, second));
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    // gives the handle of the function of the solver, if any
+    fn handle(&self) -> Option<FunctionHandle> {
+        match self {
+            Solver::Serial { handle, .. } => *handle,
+            _ => None,
+        }
+    }
+
+    // adds the handles of the functions in the solver, without repeating them
+    fn collect_handles(&self, handles: &mut Vec<FunctionHandle>) {
+        match self {
+            Solver::Serial {
+                handle: Some(handle),
+                ..
+            } if !handles.contains(handle) => handles.push(*handle),
+            Solver::Parallel(solvers) => {
+                for solver in solvers {
+                    solver.collect_handles(handles);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// submits the command buffers collected by a run with [`Algorithm::set_fuse_serial`], if any
+fn submit_fused(executor: &Executor, fused: &mut Vec<wgpu::CommandBuffer>, stats: &mut RunStats) {
+    if !fused.is_empty() {
+        stats.submissions += 1;
+        executor.execute(fused.drain(..));
+    }
+}
+
+impl RunStats {
+    // accounts the dispatches of a solver, collecting the pipelines used
+    fn add_dispatches(&mut self, dispatches: &[Dispatch], pipelines: &mut HashSet<[usize; 2]>) {
+        for dispatch in dispatches {
+            self.dispatches += 1;
+            self.workgroups += dispatch
+                .workgroups
+                .iter()
+                .map(|&count| count as u64)
+                .product::<u64>();
+            pipelines.insert([dispatch.module, dispatch.entry_point]);
+        }
+    }
+}
+
 impl<'a> Module<'a> {
-    fn new(shader: &'a Shader) -> Self {
+    fn new(shader: ShaderRef<'a>) -> Self {
         Self {
             shader,
             entry_point: Vec::new(),
@@ -529,10 +4585,49 @@ impl<'a> Module<'a> {
 
     fn add_entry_point(&mut self, e_p: &'a str) -> usize {
         self.entry_point.push(e_p);
-        return self.entry_point.len() - 1;
+        self.entry_point.len() - 1
     }
 
     fn find_entry_point(&self, e_p: &'a str) -> Option<usize> {
         self.entry_point.iter().position(|&entry| entry == e_p)
     }
 }
+
+#[cfg(test)]
+mod algorithm_test {
+    use super::*;
+    use crate::translator::GpuArray2;
+    use ndarray::Array2;
+
+    #[tokio::test]
+    async fn parallel_hazard() {
+        let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
+        let var = Arc::new(Mutex::new(
+            GpuArray2::from_ndarray(Array2::zeros((3, 3)), Some("shared")).unwrap(),
+        ));
+        let shader = Shader::from_file_path("./tests/shaders/mat2calcs.wgsl").unwrap();
+        for _ in 0..2 {
+            algorithm
+                .add_fun(Function::new(
+                    &shader,
+                    "add_1",
+                    vec![VariableBind::new(Arc::clone(&var), 0)],
+                ))
+                .unwrap();
+        }
+        // the scheduling doesn't build parallel batches yet, so it's done by hand
+        let serials = std::mem::take(&mut algorithm.solvers);
+        assert_eq!(
+            Solver::parallel_hazard(&serials).map(|(_, first, second)| (first, second)),
+            Some((0, 1))
+        );
+        algorithm.solvers.push(Solver::Parallel(serials));
+
+        let error = algorithm.run().await.unwrap_err();
+        assert!(
+            error.to_string().contains("both access shared"),
+            "{}",
+            error
+        );
+    }
+}
@@ -0,0 +1,360 @@
+//! This module contains the built in operations between [`Variable`]s, added to an [`Algorithm`]
+//!
+//! They are made only of [`Function`]s added to the [`Algorithm`], with their shaders shipped
+//! with the crate, so they're executed with the others by [`Algorithm::run`] and their results must be read back
+//! with [`Algorithm::read_variable`] as usual.
+//! The elementwise operations and the ones with a scalar work on any [`Variable`] of `f32`, the matrix ones on the
+//! [`GpuArray2`] of the [`crate::translator`] module.
+//!
+//! # Example
+//! ```ignore
+//! ops::add(&mut algorithm, &a, &b, &sum)?;
+//! ops::scale(&mut algorithm, &sum, 0.5)?;
+//! algorithm.read_variable(&sum)?;
+//! algorithm.run().await?;
+//! ```
+
+use std::sync::{Arc, Mutex, OnceLock};
+
+use anyhow::anyhow;
+
+use crate::algorithm::{
+    lock_variable, variable_name, Algorithm, Function, FunctionHandle, VariableBind,
+};
+use crate::coding::Shader;
+use crate::errors::VariableError;
+use crate::translator::GpuArray2;
+use crate::variable::Variable;
+
+// the workgroup size of the elementwise and scalar shaders
+const ELEMENTWISE_WORKGROUP_SIZE: u64 = 64;
+
+// the side of the square tiles of the transpose shader
+const TRANSPOSE_TILE: usize = 8;
+
+// gives the shader with an entry point for each elementwise operation
+fn elementwise_shader() -> &'static Shader {
+    static SHADER: OnceLock<Shader> = OnceLock::new();
+    SHADER.get_or_init(|| Shader::from_content(include_str!("../shaders/elementwise.wgsl")))
+}
+
+// gives the shader with an entry point for each operation with a scalar
+fn scalar_shader() -> &'static Shader {
+    static SHADER: OnceLock<Shader> = OnceLock::new();
+    SHADER.get_or_init(|| Shader::from_content(include_str!("../shaders/scalar.wgsl")))
+}
+
+/// Adds a [`Function`] to `algorithm` writing the elementwise sum of `a` and `b` to `out`
+///
+/// The variables must hold `f32` elements and have the same dimensions, so that the element at each position of
+/// `out` is calculated from the elements at the same position of `a` and `b`. `out` can be the same variable as
+/// `a` or `b`, to calculate the result in place.
+///
+/// # Errors
+/// - if the variables don't have the same [`Variable::dimension_sizes`], with a [`VariableError::ShapeMismatch`]
+/// - if the variables don't have the same [`Variable::byte_size`], with a [`VariableError::ByteSizeMismatch`]
+/// - if the variables don't hold `f32` elements, as told by [`Variable::element_size`]
+/// - if the [`Function`] can't be added, see [`Algorithm::add_fun`]
+pub fn add<V: Variable>(
+    algorithm: &mut Algorithm<'_, V>,
+    a: &Arc<Mutex<V>>,
+    b: &Arc<Mutex<V>>,
+    out: &Arc<Mutex<V>>,
+) -> Result<FunctionHandle, anyhow::Error> {
+    elementwise(algorithm, "add", a, b, out)
+}
+
+/// Adds a [`Function`] to `algorithm` writing the elementwise difference `a - b` to `out`, see [`add`]
+///
+/// # Errors
+/// The same as [`add`]
+pub fn sub<V: Variable>(
+    algorithm: &mut Algorithm<'_, V>,
+    a: &Arc<Mutex<V>>,
+    b: &Arc<Mutex<V>>,
+    out: &Arc<Mutex<V>>,
+) -> Result<FunctionHandle, anyhow::Error> {
+    elementwise(algorithm, "sub", a, b, out)
+}
+
+/// Adds a [`Function`] to `algorithm` writing the elementwise product of `a` and `b` to `out`, see [`add`]
+///
+/// # Errors
+/// The same as [`add`]
+pub fn mul_elementwise<V: Variable>(
+    algorithm: &mut Algorithm<'_, V>,
+    a: &Arc<Mutex<V>>,
+    b: &Arc<Mutex<V>>,
+    out: &Arc<Mutex<V>>,
+) -> Result<FunctionHandle, anyhow::Error> {
+    elementwise(algorithm, "mul_elementwise", a, b, out)
+}
+
+/// Adds a [`Function`] to `algorithm` writing the elementwise quotient `a / b` to `out`, see [`add`]
+///
+/// The division follows the WGSL rules, so dividing by 0 gives an infinite or NaN element without erroring.
+///
+/// # Errors
+/// The same as [`add`]
+pub fn div<V: Variable>(
+    algorithm: &mut Algorithm<'_, V>,
+    a: &Arc<Mutex<V>>,
+    b: &Arc<Mutex<V>>,
+    out: &Arc<Mutex<V>>,
+) -> Result<FunctionHandle, anyhow::Error> {
+    elementwise(algorithm, "div", a, b, out)
+}
+
+/// Adds a [`Function`] to `algorithm` multiplying each element of `var` by `factor`, in place
+///
+/// The `factor` is bound as a small uniform buffer owned by the [`Function`], so no variable of the size of `var`
+/// is needed. `var` must hold `f32` elements.
+///
+/// # Errors
+/// - if `var` doesn't hold `f32` elements, as told by [`Variable::element_size`]
+/// - if the [`Function`] can't be added, see [`Algorithm::add_fun`]
+pub fn scale<V: Variable>(
+    algorithm: &mut Algorithm<'_, V>,
+    var: &Arc<Mutex<V>>,
+    factor: f32,
+) -> Result<FunctionHandle, anyhow::Error> {
+    with_scalar(algorithm, "scale", var, factor)
+}
+
+/// Adds a [`Function`] to `algorithm` adding `c` to each element of `var`, in place, see [`scale`]
+///
+/// # Errors
+/// The same as [`scale`]
+pub fn offset<V: Variable>(
+    algorithm: &mut Algorithm<'_, V>,
+    var: &Arc<Mutex<V>>,
+    c: f32,
+) -> Result<FunctionHandle, anyhow::Error> {
+    with_scalar(algorithm, "offset", var, c)
+}
+
+/// Adds a [`Function`] to `algorithm` writing the matrix product of `a` and `b` to `out`
+///
+/// The product is calculated by tiles of 8 x 8 elements of `out`, each by a workgroup, so the workgroups are
+/// given by the dimensions of `out`. The shader is templated with the dimensions of the matrices, and it's
+/// compiled once for each shape.
+///
+/// # Errors
+/// - if the rows of `b` aren't as many as the columns of `a`, or `out` doesn't have the rows of `a` and the columns
+///   of `b`, with a [`VariableError::ShapeMismatch`]
+/// - if `out` is the same variable as `a` or `b`, which are read while `out` is written
+/// - if the [`Function`] can't be added, see [`Algorithm::add_fun`]
+pub fn matmul(
+    algorithm: &mut Algorithm<'_, GpuArray2>,
+    a: &Arc<Mutex<GpuArray2>>,
+    b: &Arc<Mutex<GpuArray2>>,
+    out: &Arc<Mutex<GpuArray2>>,
+) -> Result<FunctionHandle, anyhow::Error> {
+    if Arc::ptr_eq(out, a) || Arc::ptr_eq(out, b) {
+        return Err(anyhow!(
+            "Variable {:?} can't be both an operand and the output of a matrix multiplication",
+            variable_name(out)
+        ));
+    }
+    let (m, k) = lock_variable(a)?.dim();
+    let (b_rows, n) = lock_variable(b)?.dim();
+    if b_rows != k {
+        return Err(shape_mismatch(b, "matmul", &[k, n], &[b_rows, n]));
+    }
+    let (out_rows, out_cols) = lock_variable(out)?.dim();
+    if (out_rows, out_cols) != (m, n) {
+        return Err(shape_mismatch(
+            out,
+            "matmul",
+            &[m, n],
+            &[out_rows, out_cols],
+        ));
+    }
+
+    // the output is the first variable, so that it gives the workgroups
+    let binds = [out, a, b]
+        .into_iter()
+        .enumerate()
+        .map(|(binding, var)| VariableBind::new(Arc::clone(var), binding as u32))
+        .collect();
+    let shader = rendered_shader(
+        include_str!("../shaders/matmul.wgsl"),
+        &[
+            ("€m", format!("{}u", m)),
+            ("€k", format!("{}u", k)),
+            ("€n", format!("{}u", n)),
+        ],
+    )?;
+    algorithm.add_fun(Function::from_rendered(shader, "matmul", binds))
+}
+
+/// Adds a [`Function`] to `algorithm` writing the transpose of `input` to `output`
+///
+/// The transposition goes by square tiles, each read by a workgroup in its memory and written transposed, so that
+/// the workgroups read and write contiguous rows. As for [`matmul`] the shader is templated with the dimensions of
+/// the matrices.
+///
+/// # Errors
+/// - if `output` doesn't have the dimensions of `input` swapped, with a [`VariableError::ShapeMismatch`]
+/// - if `output` is the same variable as `input`
+/// - if the [`Function`] can't be added, see [`Algorithm::add_fun`]
+pub fn transpose(
+    algorithm: &mut Algorithm<'_, GpuArray2>,
+    input: &Arc<Mutex<GpuArray2>>,
+    output: &Arc<Mutex<GpuArray2>>,
+) -> Result<FunctionHandle, anyhow::Error> {
+    if Arc::ptr_eq(input, output) {
+        return Err(anyhow!(
+            "Variable {:?} can't be transposed in place",
+            variable_name(input)
+        ));
+    }
+    let (rows, cols) = lock_variable(input)?.dim();
+    let (output_rows, output_cols) = lock_variable(output)?.dim();
+    if (output_rows, output_cols) != (cols, rows) {
+        return Err(shape_mismatch(
+            output,
+            "transpose",
+            &[cols, rows],
+            &[output_rows, output_cols],
+        ));
+    }
+
+    // the input is the first variable, so that the workgroups cover its tiles
+    let binds = vec![
+        VariableBind::new(Arc::clone(input), 0),
+        VariableBind::new(Arc::clone(output), 1),
+    ];
+    let shader = rendered_shader(
+        include_str!("../shaders/transpose.wgsl"),
+        &[
+            ("€rows", format!("{}u", rows)),
+            ("€cols", format!("{}u", cols)),
+            ("€tile", TRANSPOSE_TILE.to_string()),
+            ("€padded_tile", (TRANSPOSE_TILE + 1).to_string()),
+        ],
+    )?;
+    algorithm.add_fun(Function::from_rendered(shader, "transpose", binds))
+}
+
+// adds the elementwise operation `entry_point` of the elementwise shader, after checking the shapes
+fn elementwise<V: Variable>(
+    algorithm: &mut Algorithm<'_, V>,
+    entry_point: &'static str,
+    a: &Arc<Mutex<V>>,
+    b: &Arc<Mutex<V>>,
+    out: &Arc<Mutex<V>>,
+) -> Result<FunctionHandle, anyhow::Error> {
+    let (dimensions, byte_size) = {
+        let a = lock_variable(a)?;
+        (a.dimension_sizes(), a.byte_size())
+    };
+    for var in [a, b, out] {
+        let var_lock = lock_variable(var)?;
+        if var_lock.dimension_sizes() != dimensions {
+            let sizes = |dimensions: [u32; 3]| dimensions.map(|size| size as usize);
+            return Err(VariableError::<u32>::ShapeMismatch {
+                name: var_lock.get_name().map(str::to_owned),
+                operation: entry_point,
+                expected: sizes(dimensions).to_vec(),
+                got: sizes(var_lock.dimension_sizes()).to_vec(),
+            }
+            .into());
+        }
+        if var_lock.byte_size() != byte_size {
+            return Err(VariableError::<u32>::ByteSizeMismatch {
+                byte_size: var_lock.byte_size(),
+                dimensions,
+            }
+            .into());
+        }
+        check_f32(&*var_lock, entry_point)?;
+    }
+
+    let binds = [a, b, out]
+        .into_iter()
+        .enumerate()
+        .map(|(binding, var)| VariableBind::new(Arc::clone(var), binding as u32))
+        .collect();
+    let elements = byte_size / std::mem::size_of::<f32>() as u64;
+    let function = Function::new(elementwise_shader(), entry_point, binds)
+        .with_workgroups(spread_workgroups(algorithm, elements));
+    algorithm.add_fun(function)
+}
+
+// adds the operation `entry_point` of the scalar shader, with `scalar` bound as uniform
+fn with_scalar<V: Variable>(
+    algorithm: &mut Algorithm<'_, V>,
+    entry_point: &'static str,
+    var: &Arc<Mutex<V>>,
+    scalar: f32,
+) -> Result<FunctionHandle, anyhow::Error> {
+    let elements = {
+        let var_lock = lock_variable(var)?;
+        check_f32(&*var_lock, entry_point)?;
+        var_lock.byte_size() / std::mem::size_of::<f32>() as u64
+    };
+    let function = Function::new(
+        scalar_shader(),
+        entry_point,
+        vec![VariableBind::new(Arc::clone(var), 0)],
+    )
+    .with_uniform_data(1, bytemuck::bytes_of(&scalar))
+    .with_workgroups(spread_workgroups(algorithm, elements));
+    algorithm.add_fun(function)
+}
+
+// checks that `var` holds `f32` elements, which the shaders of the operations work on
+fn check_f32<V: Variable>(var: &V, operation: &str) -> Result<(), anyhow::Error> {
+    let element_size = std::mem::size_of::<f32>() as u64;
+    if var.element_size().is_some_and(|size| size != element_size)
+        || !var.byte_size().is_multiple_of(element_size)
+    {
+        return Err(anyhow!(
+            "Variable {:?} doesn't hold f32 elements, which the operation {} needs",
+            var.get_name(),
+            operation
+        ));
+    }
+    Ok(())
+}
+
+// gives the [`VariableError::ShapeMismatch`] of the matrix `var`
+fn shape_mismatch(
+    var: &Arc<Mutex<GpuArray2>>,
+    operation: &'static str,
+    expected: &[usize],
+    got: &[usize],
+) -> anyhow::Error {
+    VariableError::<u32>::ShapeMismatch {
+        name: variable_name(var),
+        operation,
+        expected: expected.to_vec(),
+        got: got.to_vec(),
+    }
+    .into()
+}
+
+// gives the workgroups of the elementwise shaders for `elements` invocations, spread on the y axis when the x
+// axis isn't enough
+fn spread_workgroups<V: Variable>(algorithm: &Algorithm<'_, V>, elements: u64) -> [u32; 3] {
+    let max_workgroups = algorithm
+        .executor()
+        .with_device(|device, _| device.limits().max_compute_workgroups_per_dimension)
+        as u64;
+    let workgroups = elements.div_ceil(ELEMENTWISE_WORKGROUP_SIZE);
+    let workgroups_x = workgroups.min(max_workgroups);
+    let workgroups_y = workgroups.div_ceil(workgroups_x);
+    [workgroups_x as u32, workgroups_y as u32, 1]
+}
+
+// gives the shader `template` with its `tokens` replaced by the values given
+//
+// The [`Function`] owns the rendered shader, which is then kept by the [`Algorithm`] together with its module
+fn rendered_shader(template: &str, tokens: &[(&str, String)]) -> Result<Shader, anyhow::Error> {
+    let map: Vec<(&str, &str)> = tokens
+        .iter()
+        .map(|(token, value)| (*token, value.as_str()))
+        .collect();
+    Shader::from_content(template).render(&map)
+}
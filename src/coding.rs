@@ -1,4 +1,14 @@
-use std::{error::Error, path::Path};
+use anyhow::anyhow;
+use std::{
+    error::Error,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+};
+
+use crate::algorithm::{BindingKind, WORKGROUP_SIZE_PLACEHOLDER};
+
+// the first character of the templating tokens checked by [`Shader::replace_all`]
+const TOKEN_PREFIX: char = '€';
 
 /// The [`Shader`] is a struct containing WGSL code
 ///
@@ -11,9 +21,56 @@ use std::{error::Error, path::Path};
 /// but at the same time it allows to write pseudo code and to manipulate it at runtime.
 /// This allows to pass veriable length [`Variable`]s to the GPU without using some still unsupported (at the time of writing)
 /// WGSL features
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone)]
 pub struct Shader {
-    content: String,
+    code: ShaderCode,
+    // the file the shader was read from, see [`Shader::reload`]
+    path: Option<PathBuf>,
+}
+
+// the shaders are compared by their code only, so that the same code read from a file or given as a string shares
+// the compiled modules and pipelines
+impl PartialEq for Shader {
+    fn eq(&self, other: &Self) -> bool {
+        self.code == other.code
+    }
+}
+
+impl Eq for Shader {}
+
+impl Hash for Shader {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.code.hash(state);
+    }
+}
+
+// the code held by a [`Shader`]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum ShaderCode {
+    Wgsl(String),
+    // the words of a SPIR-V module, see [`Shader::from_spirv`]
+    #[cfg(feature = "spirv")]
+    SpirV(Vec<u32>),
+}
+
+/// The requirements of a compute entry point of a [`Shader`], given by [`Shader::entry_point_info`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EntryPointInfo {
+    /// the size declared with `@workgroup_size`, with the missing dimensions set to 1
+    pub workgroup_size: [u32; 3],
+    /// the device features needed by the [`Shader`], e.g. [`wgpu::Features::SHADER_F64`] if it uses `f64`
+    pub required_features: wgpu::Features,
+}
+
+/// The layout of a field of a struct of a [`Shader`], given by [`Shader::struct_layout`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldLayout {
+    /// the name of the field
+    pub name: String,
+    /// the offset in bytes of the field from the start of the struct
+    pub offset: u32,
+    /// the size in bytes of the field, without the padding after it
+    pub size: u32,
 }
 
 impl Shader {
@@ -46,7 +103,8 @@ impl Shader {
     /// ```
     pub fn from_content(content: &str) -> Self {
         Shader {
-            content: content.to_string(),
+            code: ShaderCode::Wgsl(content.to_string()),
+            path: None,
         }
     }
 
@@ -57,6 +115,14 @@ impl Shader {
     ///
     /// No effort whatsoever is done at this stage to check the correctnes of the shader, which is only checked at compile time (by Naga)
     ///
+    /// A line holding only an `//!include "other.wgsl"` directive is replaced by the content of `other.wgsl`, whose path is
+    /// relative to the directory of the file including it. The included files can include other files in turn, so the
+    /// shared struct definitions and helper functions can live in a single file.
+    /// Each directive is resolved on its own, so a file included twice (even indirectly) puts its content twice in
+    /// the [`Shader`].
+    ///
+    /// The path is kept, so that the file can be read again with [`Shader::reload`].
+    ///
     /// # Arguments
     /// * - `path_to_module` - a string slice holding the path to the module
     ///
@@ -66,12 +132,128 @@ impl Shader {
     /// use wgpu_calc::coding::Shader;
     /// let shader = Shader::from_file_path("../shaders/example_shader.wgsl");
     /// ```
-
+    ///
+    /// # Errors
+    /// - if a file is not existent or not readable
+    /// - if an include directive is malformed
+    /// - if a file includes itself, directly or through other files
     pub fn from_file_path(path_to_module: &str) -> Result<Self, Box<dyn Error>> {
-        let path = Path::new(path_to_module);
-        let content = std::fs::read_to_string(path)?;
+        let content = read_with_includes(Path::new(path_to_module), &mut Vec::new())?;
+
+        Ok(Shader {
+            code: ShaderCode::Wgsl(content),
+            path: Some(PathBuf::from(path_to_module)),
+        })
+    }
 
-        Ok(Shader { content })
+    /// This method reads again the file the [`Shader`] was created from with [`Shader::from_file_path`], following its
+    /// include directives, and tells if the content changed
+    ///
+    /// It's meant for an edit loop, where the shader file is changed while the program runs: the
+    /// [`crate::algorithm::Function`]s borrow
+    /// their [`Shader`], so the reloaded code is used by the ones created afterwards (e.g. in a new
+    /// [`crate::algorithm::Algorithm`] made with [`crate::algorithm::Algorithm::with_shared_executor`], which compiles only the
+    /// shaders which changed).
+    /// The content is replaced by the one of the file, so all the changes made since it was read (e.g. with
+    /// [`Shader::replace`] or [`Shader::include`]) are lost and the templating must be done again. The comparison is made
+    /// with the content before these changes are lost, so a templated [`Shader`] is reported as changed.
+    ///
+    /// # Example
+    /// ```
+    /// use wgpu_calc::coding::Shader;
+    /// let mut shader = Shader::from_file_path("./tests/shaders/example_shader.wgsl").unwrap();
+    /// assert!(!shader.reload().unwrap());
+    /// ```
+    ///
+    /// # Errors
+    /// - if the [`Shader`] wasn't created from a file
+    /// - if the file can't be read anymore, see [`Shader::from_file_path`]. In this case the [`Shader`] is unchanged
+    pub fn reload(&mut self) -> Result<bool, anyhow::Error> {
+        let path = self
+            .path
+            .as_ref()
+            .ok_or_else(|| anyhow!("Shader wasn't read from a file, so it can't be reloaded"))?;
+        let code = ShaderCode::Wgsl(read_with_includes(path, &mut Vec::new())?);
+        let changed = code != self.code;
+        self.code = code;
+        Ok(changed)
+    }
+
+    /// This method creates a shader from the bytes of a SPIR-V module, e.g. a GLSL shader compiled offline
+    ///
+    /// The module is read with `Naga` like the WGSL code, so the methods inspecting the [`Shader`] (e.g.
+    /// [`Shader::entry_point_info`] or [`Shader::bindings`]) and the checks of [`crate::algorithm::Algorithm::add_fun`]
+    /// work the same, and `wgpu` translates it for the backend of the device.
+    /// The templating only applies to WGSL code: [`Shader::replace_all`] and [`Shader::render`] give an error on
+    /// a SPIR-V [`Shader`], while [`Shader::replace`] and [`Shader::include`] leave it unchanged.
+    ///
+    /// Needs the `spirv` feature of the crate.
+    ///
+    /// # Example
+    /// ```ignore
+    /// use wgpu_calc::coding::Shader;
+    /// let shader = Shader::from_spirv(&std::fs::read("add_1.spv").unwrap()).unwrap();
+    /// assert!(shader.entry_points().unwrap().contains(&"main".to_string()));
+    /// ```
+    ///
+    /// # Errors
+    /// - if the length of `bytes` is not a multiple of 4
+    /// - if `bytes` doesn't start with the SPIR-V magic number, in either byte order
+    #[cfg(feature = "spirv")]
+    pub fn from_spirv(bytes: &[u8]) -> Result<Self, anyhow::Error> {
+        const MAGIC: u32 = 0x0723_0203;
+        if !bytes.len().is_multiple_of(4) {
+            return Err(anyhow!(
+                "SPIR-V module has {} bytes, which is not a whole number of words",
+                bytes.len()
+            ));
+        }
+        let mut words: Vec<u32> = bytes
+            .chunks_exact(4)
+            .map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+            .collect();
+        match words.first() {
+            Some(&MAGIC) => {}
+            Some(&word) if word.swap_bytes() == MAGIC => {
+                words.iter_mut().for_each(|word| *word = word.swap_bytes());
+            }
+            _ => return Err(anyhow!("Bytes don't start with the SPIR-V magic number")),
+        }
+        Ok(Shader {
+            code: ShaderCode::SpirV(words),
+            path: None,
+        })
+    }
+
+    /// This method puts the content of the `other` [`Shader`] at the beginning of this one
+    ///
+    /// It's useful to share struct definitions and helper functions among shaders, without copying them in each one.
+    /// As in [`Shader::replace`] no check on the correctness of the final code is done, so e.g. including the same
+    /// [`Shader`] twice gives duplicated definitions, which will fail at compile time.
+    ///
+    /// # Example
+    /// ```
+    /// use wgpu_calc::coding::Shader;
+    /// let helpers = Shader::from_content("
+    ///     fn square(x: f32) -> f32 {
+    ///         return x * x;
+    ///     }");
+    /// let mut shader = Shader::from_content("
+    ///     @group(0) @binding(0)
+    ///     var<storage,read_write> a: array<f32>;
+    ///
+    ///     @compute @workgroup_size(64)
+    ///     fn square_all (@builtin(global_invocation_id) id: vec3<u32>) {
+    ///         a[id.x] = square(a[id.x]);
+    ///     }");
+    /// shader.include(&helpers);
+    /// assert!(shader.get_content().starts_with(helpers.get_content()));
+    /// assert!(shader.entry_point_info("square_all").is_ok());
+    /// ```
+    pub fn include(&mut self, other: &Shader) {
+        if let Some(content) = self.wgsl_mut() {
+            *content = format!("{}\n{}", other.get_content(), content);
+        }
     }
 
     /// This function replace the `from` sring with the `to` string inside the [`Shader`]
@@ -124,14 +306,516 @@ impl Shader {
     /// assert_eq!(shader, check_shader)
     /// ```
     pub fn replace(&mut self, from: &str, to: &str) {
-        self.content = self.content.replace(from, to);
+        if let Some(content) = self.wgsl_mut() {
+            *content = content.replace(from, to);
+        }
+    }
+
+    /// This function replaces all the tokens of `map` inside the [`Shader`] in a single pass
+    ///
+    /// Each pair of `map` is a `(from, to)` as in [`Shader::replace`], but the substitutions are done all at once, so their
+    /// order doesn't matter: the `to` strings are never searched for other tokens, and when more tokens match at the same
+    /// position the longest one is replaced (e.g. `€ncols` isn't taken for `€n` followed by `cols`).
+    /// After the substitutions the [`Shader`] can't have any token left, i.e. a word starting with `€`, apart from
+    /// [`crate::algorithm::WORKGROUP_SIZE_PLACEHOLDER`] which is replaced by [`crate::algorithm::Algorithm::autotune`].
+    ///
+    /// # Example
+    /// ```
+    /// use wgpu_calc::coding::Shader;
+    /// let mut shader = Shader::from_content("
+    ///     struct Mat2 {
+    ///         elements: array<array<f32,€rows>,€cols>,
+    ///         }");
+    /// assert!(shader.replace_all(&[("€rows", "5")]).is_err());
+    /// shader.replace_all(&[("€cols", "4"), ("€rows", "5")]).unwrap();
+    /// assert_eq!(shader.get_content(), "
+    ///     struct Mat2 {
+    ///         elements: array<array<f32,5>,4>,
+    ///         }");
+    /// ```
+    ///
+    /// # Errors
+    /// - if some tokens are left after the substitutions, giving all of them. In this case the [`Shader`] is unchanged
+    /// - if the [`Shader`] is a SPIR-V module (see [`Shader::from_spirv`])
+    pub fn replace_all(&mut self, map: &[(&str, &str)]) -> Result<(), anyhow::Error> {
+        let source = self
+            .wgsl_mut()
+            .ok_or_else(|| anyhow!("A SPIR-V shader can't be templated"))?;
+        let mut content = String::with_capacity(source.len());
+        let mut rest = source.as_str();
+        while let Some(next) = rest.chars().next() {
+            match map
+                .iter()
+                .filter(|(from, _)| !from.is_empty() && rest.starts_with(from))
+                .max_by_key(|(from, _)| from.len())
+            {
+                Some((from, to)) => {
+                    content.push_str(to);
+                    rest = &rest[from.len()..];
+                }
+                None => {
+                    content.push(next);
+                    rest = &rest[next.len_utf8()..];
+                }
+            }
+        }
+
+        let mut leftovers: Vec<&str> = Vec::new();
+        let mut tokens = content.as_str();
+        while let Some(start) = tokens.find(TOKEN_PREFIX) {
+            let token = &tokens[start..];
+            let end = token[TOKEN_PREFIX.len_utf8()..]
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .map_or(token.len(), |end| end + TOKEN_PREFIX.len_utf8());
+            let token = &token[..end];
+            if token != WORKGROUP_SIZE_PLACEHOLDER && !leftovers.contains(&token) {
+                leftovers.push(token);
+            }
+            tokens = &tokens[start + end..];
+        }
+        if !leftovers.is_empty() {
+            return Err(anyhow!(
+                "Shader has tokens which haven't been replaced: {}",
+                leftovers.join(", ")
+            ));
+        }
+
+        *source = content;
+        Ok(())
+    }
+
+    /// This function consumes the [`Shader`] and gives a new one with all the tokens of `map` replaced
+    ///
+    /// It's the same as [`Shader::replace_all`], and it's handy to build a [`Shader`] from a template in a single expression.
+    ///
+    /// # Example
+    /// ```
+    /// use wgpu_calc::coding::Shader;
+    /// let shader = Shader::from_content("var<private> a: array<f32,€len>;")
+    ///     .render(&[("€len", "8")])
+    ///     .unwrap();
+    /// assert_eq!(shader.get_content(), "var<private> a: array<f32,8>;");
+    /// ```
+    ///
+    /// # Errors
+    /// - if some tokens are left after the substitutions, giving all of them
+    /// - if the [`Shader`] is a SPIR-V module (see [`Shader::from_spirv`])
+    pub fn render(mut self, map: &[(&str, &str)]) -> Result<Shader, anyhow::Error> {
+        self.replace_all(map)?;
+        Ok(self)
     }
 
     /// This methods gets the content of the [`Shader`] as a string reference
     ///
     /// It can be used for debugging, checking or to manipulate the wgls shader before
-    /// inserting it in another [`Shader`] instance.
+    /// inserting it in another [`Shader`] instance. A SPIR-V [`Shader`] has no text, so its content is empty.
     pub fn get_content(&self) -> &str {
-        &self.content
+        match &self.code {
+            ShaderCode::Wgsl(content) => content,
+            #[cfg(feature = "spirv")]
+            ShaderCode::SpirV(_) => "",
+        }
+    }
+
+    // gives the source of the shader for `wgpu`
+    pub(crate) fn source(&self) -> wgpu::ShaderSource<'_> {
+        match &self.code {
+            ShaderCode::Wgsl(content) => {
+                wgpu::ShaderSource::Wgsl(std::borrow::Cow::Borrowed(content))
+            }
+            #[cfg(feature = "spirv")]
+            ShaderCode::SpirV(words) => {
+                wgpu::ShaderSource::SpirV(std::borrow::Cow::Borrowed(words))
+            }
+        }
+    }
+
+    // gives the WGSL code of the shader to be changed, none for a SPIR-V shader
+    fn wgsl_mut(&mut self) -> Option<&mut String> {
+        match &mut self.code {
+            ShaderCode::Wgsl(content) => Some(content),
+            #[cfg(feature = "spirv")]
+            ShaderCode::SpirV(_) => None,
+        }
+    }
+
+    /// This method parses and validates the [`Shader`] with `Naga`, the same checks `wgpu` does when the [`Shader`] is compiled
+    ///
+    /// It's useful after replacing the templating tokens (see [`Shader::replace_all`]), to get the mistakes as errors
+    /// with their line and column in the code, instead of a panic when the [`Shader`] is used by a
+    /// [`crate::algorithm::Function`]. [`crate::algorithm::Algorithm::add_fun`] validates its [`Shader`]s in the same way.
+    /// The device features needed by the [`Shader`] aren't checked here, see [`Shader::entry_point_info`].
+    ///
+    /// # Example
+    /// ```
+    /// use wgpu_calc::coding::Shader;
+    /// let shader = Shader::from_content("
+    ///     @group(0) @binding(0)
+    ///     var<storage,read_write> a: array<f32>;
+    ///
+    ///     @compute @workgroup_size(64)
+    ///     fn add_1 (@builtin(global_invocation_id) id: vec3<u32>) {
+    ///         a[id.x] = a[id.x] + 1u;
+    ///     }
+    /// ");
+    /// // adding an u32 to a f32 is parsed, but isn't valid
+    /// assert!(shader.validate().is_err());
+    /// ```
+    ///
+    /// # Errors
+    /// - if the [`Shader`] can't be parsed
+    /// - if the [`Shader`] isn't valid, e.g. because of mismatching types
+    pub fn validate(&self) -> Result<(), anyhow::Error> {
+        self.parse_validated()?;
+        Ok(())
+    }
+
+    /// This method parses the [`Shader`] with `Naga` to get its storage and uniform bindings, as `(group, binding, kind)`
+    ///
+    /// The bindings are sorted by group and binding, and the storage ones declared with the `read` access mode are
+    /// `read_only`. Textures and samplers aren't given, since they're not bound with a [`crate::algorithm::VariableBind`].
+    /// [`crate::algorithm::Algorithm::add_fun`] checks in the same way that each binding used by the entry point of the
+    /// [`crate::algorithm::Function`] is given a buffer.
+    ///
+    /// # Example
+    /// ```
+    /// use wgpu_calc::algorithm::BindingKind;
+    /// use wgpu_calc::coding::Shader;
+    /// let shader = Shader::from_content("
+    ///     @group(0) @binding(0)
+    ///     var<storage,read_write> a: array<f32>;
+    ///     @group(0) @binding(1)
+    ///     var<storage,read> b: array<f32>;
+    ///     @group(1) @binding(0)
+    ///     var<uniform> scale: f32;
+    ///
+    ///     @compute @workgroup_size(64)
+    ///     fn add (@builtin(global_invocation_id) id: vec3<u32>) {
+    ///         a[id.x] = a[id.x] + scale * b[id.x];
+    ///     }
+    /// ");
+    /// assert_eq!(
+    ///     shader.bindings().unwrap(),
+    ///     vec![
+    ///         (0, 0, BindingKind::Storage { read_only: false }),
+    ///         (0, 1, BindingKind::Storage { read_only: true }),
+    ///         (1, 0, BindingKind::Uniform),
+    ///     ]
+    /// );
+    /// ```
+    ///
+    /// # Errors
+    /// - if the [`Shader`] is not valid WGSL
+    pub fn bindings(&self) -> Result<Vec<(u32, u32, BindingKind)>, anyhow::Error> {
+        Ok(buffer_bindings(&self.parse()?, |_| true))
+    }
+
+    // gives the storage and uniform bindings used by the compute entry point `name`, validating the shader
+    pub(crate) fn entry_point_bindings(
+        &self,
+        name: &str,
+    ) -> Result<Vec<(u32, u32, BindingKind)>, anyhow::Error> {
+        let (module, info) = self.parse_validated()?;
+        let index = module
+            .entry_points
+            .iter()
+            .position(|entry_point| {
+                entry_point.name == name && entry_point.stage == naga::ShaderStage::Compute
+            })
+            .ok_or_else(|| {
+                anyhow!(
+                    "Shader has no compute entry point called {}, its compute entry points are {:?}",
+                    name,
+                    compute_entry_points(&module)
+                )
+            })?;
+        let uses = info.get_entry_point(index);
+        Ok(buffer_bindings(&module, |handle| !uses[handle].is_empty()))
+    }
+
+    /// This method parses the [`Shader`] with `Naga` to get the names of its compute entry points, in the order they're declared
+    ///
+    /// It can be used to check the entry point of a [`crate::algorithm::Function`] before adding it, e.g. when the names are
+    /// generated by a template. [`crate::algorithm::Algorithm::add_fun`] gives the same list in its error when the entry point
+    /// isn't found.
+    ///
+    /// # Example
+    /// ```
+    /// use wgpu_calc::coding::Shader;
+    /// let shader = Shader::from_content("
+    ///     @group(0) @binding(0)
+    ///     var<storage,read_write> a: array<f32>;
+    ///
+    ///     fn one() -> f32 {
+    ///         return 1.0;
+    ///     }
+    ///
+    ///     @compute @workgroup_size(64)
+    ///     fn add_1 (@builtin(global_invocation_id) id: vec3<u32>) {
+    ///         a[id.x] = a[id.x] + one();
+    ///     }
+    ///
+    ///     @compute @workgroup_size(64)
+    ///     fn sub_1 (@builtin(global_invocation_id) id: vec3<u32>) {
+    ///         a[id.x] = a[id.x] - one();
+    ///     }
+    /// ");
+    /// assert_eq!(shader.entry_points().unwrap(), vec!["add_1", "sub_1"]);
+    /// ```
+    ///
+    /// # Errors
+    /// - if the [`Shader`] is not valid WGSL
+    pub fn entry_points(&self) -> Result<Vec<String>, anyhow::Error> {
+        Ok(compute_entry_points(&self.parse()?))
+    }
+
+    /// This method parses the [`Shader`] with `Naga` to get the requirements of the compute entry point `name`
+    ///
+    /// Differently from the other methods of the [`Shader`] this one checks the code, which must be valid WGSL
+    /// (e.g. with all the templating tokens already replaced).
+    /// The required features are the ones of the whole [`Shader`], since `wgpu` compiles it as a single module:
+    /// - [`wgpu::Features::SHADER_F64`], [`wgpu::Features::SHADER_F16`] and [`wgpu::Features::SHADER_I16`] for the scalar types
+    /// - [`wgpu::Features::PUSH_CONSTANTS`] for the `push_constant` variables
+    /// - [`wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES`] for the storage textures which are read
+    ///
+    /// # Example
+    /// ```
+    /// use wgpu_calc::coding::Shader;
+    /// let shader = Shader::from_content("
+    ///     @group(0) @binding(0)
+    ///     var<storage,read_write> a: array<f32>;
+    ///
+    ///     @compute @workgroup_size(8, 8)
+    ///     fn add_1 (@builtin(global_invocation_id) id: vec3<u32>) {
+    ///         a[id.x] = a[id.x] + 1.0;
+    ///     }
+    /// ");
+    /// let info = shader.entry_point_info("add_1").unwrap();
+    /// assert_eq!(info.workgroup_size, [8, 8, 1]);
+    /// assert!(info.required_features.is_empty());
+    /// ```
+    ///
+    /// # Errors
+    /// - if the [`Shader`] is not valid WGSL
+    /// - if the [`Shader`] has no compute entry point called `name`
+    pub fn entry_point_info(&self, name: &str) -> Result<EntryPointInfo, anyhow::Error> {
+        let module = self.parse()?;
+        let entry_point = module
+            .entry_points
+            .iter()
+            .find(|entry_point| {
+                entry_point.name == name && entry_point.stage == naga::ShaderStage::Compute
+            })
+            .ok_or_else(|| {
+                anyhow!(
+                    "Shader has no compute entry point called {}, its compute entry points are {:?}",
+                    name,
+                    compute_entry_points(&module)
+                )
+            })?;
+
+        let mut required_features = wgpu::Features::empty();
+        for (_, ty) in module.types.iter() {
+            let scalar = match ty.inner {
+                naga::TypeInner::Scalar { kind, width }
+                | naga::TypeInner::Vector { kind, width, .. } => Some((kind, width)),
+                naga::TypeInner::Matrix { width, .. } => Some((naga::ScalarKind::Float, width)),
+                naga::TypeInner::Image {
+                    class: naga::ImageClass::Storage { access, .. },
+                    ..
+                } if access.contains(naga::StorageAccess::LOAD) => {
+                    required_features |= wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES;
+                    None
+                }
+                _ => None,
+            };
+            required_features |= match scalar {
+                Some((naga::ScalarKind::Float, 8)) => wgpu::Features::SHADER_F64,
+                Some((naga::ScalarKind::Float, 2)) => wgpu::Features::SHADER_F16,
+                Some((naga::ScalarKind::Sint | naga::ScalarKind::Uint, 2)) => {
+                    wgpu::Features::SHADER_I16
+                }
+                _ => wgpu::Features::empty(),
+            };
+        }
+        if module
+            .global_variables
+            .iter()
+            .any(|(_, variable)| variable.space == naga::AddressSpace::PushConstant)
+        {
+            required_features |= wgpu::Features::PUSH_CONSTANTS;
+        }
+
+        Ok(EntryPointInfo {
+            workgroup_size: entry_point.workgroup_size,
+            required_features,
+        })
+    }
+
+    /// This method gives the layout of the fields of the struct called `name`, as the shader sees them in memory
+    ///
+    /// The offsets follow the WGSL layout rules (including any `@align` and `@size` attribute), so they can be used to
+    /// read the fields back from the bytes of a buffer of the struct, instead of writing them by hand.
+    /// The fields are given in the order they're declared; the size of a runtime sized array
+    /// is the one of its element.
+    ///
+    /// # Example
+    /// ```
+    /// use wgpu_calc::coding::{FieldLayout, Shader};
+    /// let shader = Shader::from_content("
+    ///     struct Particle {
+    ///         position: vec3<f32>,
+    ///         mass: f32,
+    ///         velocity: vec2<f32>,
+    ///     }
+    ///     @group(0) @binding(0)
+    ///     var<storage,read_write> particles: array<Particle>;
+    ///
+    ///     @compute @workgroup_size(64)
+    ///     fn step (@builtin(global_invocation_id) id: vec3<u32>) {
+    ///         particles[id.x].mass = particles[id.x].mass;
+    ///     }
+    /// ");
+    /// let layout = shader.struct_layout("Particle").unwrap();
+    /// assert_eq!(layout[1], FieldLayout { name: "mass".to_string(), offset: 12, size: 4 });
+    /// assert_eq!(layout[2].offset, 16);
+    /// ```
+    ///
+    /// # Errors
+    /// - if the [`Shader`] is not valid WGSL
+    /// - if the [`Shader`] has no struct called `name`
+    pub fn struct_layout(&self, name: &str) -> Result<Vec<FieldLayout>, anyhow::Error> {
+        let module = self.parse()?;
+        let members = module
+            .types
+            .iter()
+            .find_map(|(_, ty)| match &ty.inner {
+                naga::TypeInner::Struct { members, .. } if ty.name.as_deref() == Some(name) => {
+                    Some(members)
+                }
+                _ => None,
+            })
+            .ok_or_else(|| anyhow!("Shader has no struct called {}", name))?;
+
+        let mut layouter = naga::proc::Layouter::default();
+        layouter
+            .update(module.to_ctx())
+            .map_err(|error| anyhow!("Layout of struct {} can't be calculated: {}", name, error))?;
+        Ok(members
+            .iter()
+            .map(|member| FieldLayout {
+                name: member.name.clone().unwrap_or_default(),
+                offset: member.offset,
+                size: layouter[member.ty].size,
+            })
+            .collect())
+    }
+
+    // parses and validates the WGSL of the shader, giving the errors with their position in the source
+    fn parse_validated(&self) -> Result<(naga::Module, naga::valid::ModuleInfo), anyhow::Error> {
+        let module = self.parse()?;
+        let info = naga::valid::Validator::new(
+            naga::valid::ValidationFlags::all(),
+            naga::valid::Capabilities::all(),
+        )
+        .validate(&module)
+        .map_err(|error| match &self.code {
+            ShaderCode::Wgsl(content) => {
+                anyhow!("Shader isn't valid: {}", error.emit_to_string(content))
+            }
+            // a SPIR-V module has no source to point the errors to
+            #[cfg(feature = "spirv")]
+            ShaderCode::SpirV(_) => anyhow!("Shader isn't valid: {}", error),
+        })?;
+        Ok((module, info))
+    }
+
+    // parses the code of the shader, giving the errors with their position in the WGSL source
+    fn parse(&self) -> Result<naga::Module, anyhow::Error> {
+        match &self.code {
+            ShaderCode::Wgsl(content) => naga::front::wgsl::parse_str(content).map_err(|error| {
+                anyhow!("Shader can't be parsed: {}", error.emit_to_string(content))
+            }),
+            #[cfg(feature = "spirv")]
+            ShaderCode::SpirV(words) => naga::front::spv::parse_u8_slice(
+                bytemuck::cast_slice(words),
+                &naga::front::spv::Options::default(),
+            )
+            .map_err(|error| anyhow!("Shader can't be parsed: {}", error)),
+        }
+    }
+}
+
+// gives the storage and uniform bindings of the global variables of `module` selected by `filter`, sorted
+fn buffer_bindings(
+    module: &naga::Module,
+    filter: impl Fn(naga::Handle<naga::GlobalVariable>) -> bool,
+) -> Vec<(u32, u32, BindingKind)> {
+    let mut bindings: Vec<(u32, u32, BindingKind)> = module
+        .global_variables
+        .iter()
+        .filter(|&(handle, _)| filter(handle))
+        .filter_map(|(_, variable)| {
+            let binding = variable.binding.as_ref()?;
+            let kind = match variable.space {
+                naga::AddressSpace::Storage { access } => BindingKind::Storage {
+                    read_only: !access.contains(naga::StorageAccess::STORE),
+                },
+                naga::AddressSpace::Uniform => BindingKind::Uniform,
+                _ => return None,
+            };
+            Some((binding.group, binding.binding, kind))
+        })
+        .collect();
+    bindings.sort_by_key(|&(group, binding, _)| (group, binding));
+    bindings
+}
+
+// gives the names of the compute entry points of `module`
+fn compute_entry_points(module: &naga::Module) -> Vec<String> {
+    module
+        .entry_points
+        .iter()
+        .filter(|entry_point| entry_point.stage == naga::ShaderStage::Compute)
+        .map(|entry_point| entry_point.name.clone())
+        .collect()
+}
+
+// reads the file at `path` replacing the `//!include "other.wgsl"` lines with the content of the included files,
+// whose paths are relative to the directory of `path`. `stack` holds the files being read, to find include cycles
+fn read_with_includes(path: &Path, stack: &mut Vec<PathBuf>) -> Result<String, anyhow::Error> {
+    let canonical = path
+        .canonicalize()
+        .map_err(|error| anyhow!("Shader file {} can't be read: {}", path.display(), error))?;
+    if stack.contains(&canonical) {
+        return Err(anyhow!("Shader file {} includes itself", path.display()));
+    }
+    let content = std::fs::read_to_string(path)
+        .map_err(|error| anyhow!("Shader file {} can't be read: {}", path.display(), error))?;
+
+    stack.push(canonical);
+    let mut resolved = String::with_capacity(content.len());
+    for line in content.lines() {
+        match line.trim().strip_prefix("//!include") {
+            Some(directive) => {
+                let included = directive
+                    .trim()
+                    .strip_prefix('"')
+                    .and_then(|included| included.strip_suffix('"'))
+                    .ok_or_else(|| {
+                        anyhow!(
+                            "Malformed include directive in shader file {}: {}",
+                            path.display(),
+                            line.trim()
+                        )
+                    })?;
+                let included_path = path.parent().unwrap_or(Path::new("")).join(included);
+                resolved.push_str(&read_with_includes(&included_path, stack)?);
+            }
+            None => resolved.push_str(line),
+        }
+        resolved.push('\n');
     }
+    stack.pop();
+    Ok(resolved)
 }
@@ -5,31 +5,111 @@
 use std::fmt::Debug;
 use thiserror::Error;
 
+// label shown in the errors of the solvers without one
+const UNLABELED_SOLVER: &str = "<unlabeled solver>";
+
 // type GpuResult<T> = Result<T, SizeError>;
 
-/// These errors deals with the size of the operands of a function
-///
-/// They can be raised if the dimen
+// These errors deals with the size of the operands of a function
+//
+// They can be raised if the dimen
 // #[derive(Debug, Error)]
 // pub enum SizeError {
 //     #[error("First operand has dimensions {:?}, second operand has dimension {:?}. The expected dimensions of the second operand are {:?}", [0], [1],[2])]
 //     DimensionError((usize, usize), (usize, usize), (usize, usize)),
 // }
 
+/// These errors are raised when the operations scheduled in an [`crate::algorithm::Algorithm`] can't be executed
 #[derive(Debug, Error)]
 pub enum OperationError {
-    #[error("Operation field of {:?} is empty, please start with an operation which bind groups, create a pipeline and dispatch a worgroup before calling this method",[0])]
-    BindingNotPresent(String),
+    #[error("Operation {operation} of {} has no binding, please start with an operation which bind groups, create a pipeline and dispatch a worgroup before calling this method", .label.as_deref().unwrap_or(UNLABELED_SOLVER))]
+    BindingNotPresent {
+        label: Option<String>,
+        operation: usize,
+    },
     #[error("Can't add a parallel operation to a compute pipeline. A parallel operation needs to create a wgpu::CommandBuffer for every parallel operation to submits")]
     ComputePassOnParallel,
     #[error("Can't add a buffer write to a compute pipeline. the buffer writing needs to be called on the [`wgpu::Queue`] directly")]
     ComputePassOnBuffer,
 }
 
+/// These errors deal with the dimensions and the data of a [`crate::variable::Variable`]
+///
+/// [`VariableError::SizeMismatch`] is the error to return from [`crate::variable::Variable::read_data`] when the
+/// bytes given don't match the size of the variable, [`VariableError::ShapeMismatch`] the one of the operations of
+/// [`crate::algorithm::ops`] when their operands don't fit together.
 #[derive(Debug, Error)]
 pub enum VariableError<T: Debug> {
-    #[error("Dimensions of the object {:?} is higher than 3, which is the max worksize group number",[0])]
+    #[error(
+        "Dimensions of the object {0:?} is higher than 3, which is the max worksize group number"
+    )]
     DimensionError(T),
-    #[error("Variable has size in {:?} dimension which exceeds the max workgroup size. Please make sure you have more than one workgroup defined for this id",[0])]
+    #[error("Variable has size in dimension {0} which exceeds the max workgroup size. Please make sure you have more than one workgroup defined for this id")]
     WorkgroupDimensionError(u32),
+    #[error("Variable has byte size {byte_size} which is not consistent with its dimensions {dimensions:?}")]
+    ByteSizeMismatch {
+        byte_size: u64,
+        dimensions: [u32; 3],
+    },
+    #[error("Variable has byte size {expected}, but {got} bytes were given for it")]
+    SizeMismatch { expected: u64, got: u64 },
+    #[error("Variable {name:?} has dimensions {got:?}, but the operation {operation} needs {expected:?}")]
+    ShapeMismatch {
+        name: Option<String>,
+        operation: &'static str,
+        expected: Vec<usize>,
+        got: Vec<usize>,
+    },
+}
+
+/// These errors are raised when the bindings of a [`crate::algorithm::Function`] don't match its shader or its schema
+#[derive(Debug, Error)]
+pub enum BindingError {
+    #[error("The schema declares {expected} bindings, but {got} variables were given")]
+    CountMismatch { expected: usize, got: usize },
+    #[error("Variable in position {position} is bound at {got}, but the schema expects binding {expected}")]
+    BindingMismatch {
+        position: usize,
+        expected: u32,
+        got: u32,
+    },
+    #[error("Variable in position {position} is in bind group {got}, but the schema expects group {expected}")]
+    UnexpectedGroup {
+        position: usize,
+        expected: u32,
+        got: u32,
+    },
+    #[error("Entry point {entry_point} uses the binding {binding} of group {group}, but the function binds nothing to it")]
+    MissingBinding {
+        entry_point: String,
+        group: u32,
+        binding: u32,
+    },
+    #[error("The output bound at binding {binding} of group {group} is a uniform, which the shader can't write")]
+    UniformOutput { group: u32, binding: u32 },
+    #[error(
+        "The schema declares binding {binding} as {expected}, but the variable is bound as {got}"
+    )]
+    KindMismatch {
+        binding: u32,
+        expected: String,
+        got: String,
+    },
+    #[error("The schema declares binding {binding} with type {expected}, but the variable has type {got}")]
+    TypeMismatch {
+        binding: u32,
+        expected: String,
+        got: String,
+    },
+}
+
+/// These errors are raised when the lock of a variable is poisoned, i.e. a thread panicked while holding it
+///
+/// They carry the name of the variable, if it has one.
+#[derive(Debug, Error)]
+pub enum LockError {
+    #[error("The lock of variable {0:?} is poisoned, a thread panicked while holding it")]
+    Variable(Option<String>),
+    #[error("The lock of texture {0:?} is poisoned, a thread panicked while holding it")]
+    Texture(Option<String>),
 }
@@ -4,7 +4,8 @@
 //! using the [`wgpu`] crate and its functions.
 
 #![allow(dead_code)]
-use std::sync::{Arc, Mutex};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
 
 use crate::coding::Shader;
 use anyhow::anyhow;
@@ -16,14 +17,147 @@ use wgpu::{util::DeviceExt, InstanceFlags};
 /// loading the shaders, filling the queue with the correct pipeline of commands and launching the calculations
 /// in the device.
 #[derive(Debug)]
-pub struct Executor<'a> {
+pub struct Executor {
     adapter: wgpu::Adapter,
     device: wgpu::Device,
     queue: wgpu::Queue,
-    label: Option<&'a str>,
+    label: Option<String>,
+    bind_group_layouts: Mutex<HashMap<Vec<wgpu::BindGroupLayoutEntry>, Arc<wgpu::BindGroupLayout>>>,
+    // compiled shader modules, by the source of the shader
+    shader_modules: Mutex<HashMap<Shader, Arc<wgpu::ShaderModule>>>,
+    // compute pipelines, by the source of the shader, the entry point and the entries of the bind group layout
+    pipelines: Mutex<HashMap<PipelineKey, Arc<wgpu::ComputePipeline>>>,
+    // unmapped staging buffers ready to be reused for readbacks, by size
+    staging_buffers: Mutex<HashMap<u64, Vec<wgpu::Buffer>>>,
 }
 
-impl Executor<'_> {
+/// A capability of the GPU which an application may want to use, checked by [`Executor::probe`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DesiredFeature {
+    /// the `f64` type in the shaders
+    ShaderF64,
+    /// the `f16` type in the shaders
+    ShaderF16,
+    /// the timestamp queries, to time the operations on the GPU
+    TimestampQuery,
+    /// the push constants, with a non zero size limit
+    PushConstants,
+}
+
+/// The options used to create an [`Executor`], see [`Executor::with_options`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecutorOptions {
+    /// the backends the adapter can be chosen from, all of them by default
+    ///
+    /// Excluding a backend avoids known bad drivers, e.g. `wgpu::Backends::all() - wgpu::Backends::GL`
+    /// on a machine with a flaky OpenGL implementation.
+    pub backends: wgpu::Backends,
+    /// which adapter is preferred between the ones found, [`wgpu::PowerPreference::HighPerformance`] by default
+    ///
+    /// [`wgpu::PowerPreference::LowPower`] picks the integrated GPU when there's one, which is quicker to start and
+    /// uses less power for small workloads.
+    pub power_preference: wgpu::PowerPreference,
+    /// forces the software adapter (if the platform has one), `false` by default
+    pub force_fallback_adapter: bool,
+    /// the features the device is required to have, none by default
+    ///
+    /// They're on top of the ones a [`Shader`] may need (like [`wgpu::Features::SHADER_F64`]), which are
+    /// requested whenever the adapter supports them. E.g. [`wgpu::Features::TIMESTAMP_QUERY`] to time the operations.
+    pub features: wgpu::Features,
+    /// the limits the device is required to have, [`wgpu::Limits::default`] by default
+    /// ([`wgpu::Limits::downlevel_webgl2_defaults`] on wasm)
+    ///
+    /// E.g. a bigger [`wgpu::Limits::max_storage_buffer_binding_size`] allows to bind bigger matrices.
+    pub limits: wgpu::Limits,
+}
+
+impl Default for ExecutorOptions {
+    fn default() -> Self {
+        ExecutorOptions {
+            backends: wgpu::Backends::all(),
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            force_fallback_adapter: false,
+            features: wgpu::Features::empty(),
+            limits: if cfg!(target_arch = "wasm32") {
+                wgpu::Limits::downlevel_webgl2_defaults()
+            } else {
+                wgpu::Limits::default()
+            },
+        }
+    }
+}
+
+// identifies a pipeline cached by [`Executor::get_cached_pipeline`]
+type PipelineKey = (Shader, String, Vec<Vec<wgpu::BindGroupLayoutEntry>>, u32);
+
+/// The biggest number of bytes copied at once by [`Executor::read_buffer_to_writer`]
+pub const READ_CHUNK_SIZE: u64 = 64 << 20;
+
+// the features which can be required by a shader (see [`Shader::entry_point_info`]), requested by the
+// [`Executor`] when the adapter supports them
+const SHADER_FEATURES: wgpu::Features = wgpu::Features::SHADER_F16
+    .union(wgpu::Features::SHADER_F64)
+    .union(wgpu::Features::SHADER_I16)
+    .union(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES);
+
+// holds a staging buffer during a readback, giving it back to the pool of the [`Executor`] when dropped
+//
+// The buffer is unmapped on drop, also when the mapping is still pending: a readback future dropped
+// before completion aborts the mapping and the buffer can be reused straight away.
+#[derive(Debug)]
+struct StagingBuffer<'e> {
+    executor: &'e Executor,
+    buffer: Option<wgpu::Buffer>,
+}
+
+/// A [`wgpu::Buffer`] copied back from the GPU and mapped to be read, given by [`Executor::map_buffer`]
+///
+/// The data is read in place through [`MappedBuffer::view`], without copying it to the CPU memory.
+/// The staging buffer is unmapped and given back to the [`Executor`] when this is dropped.
+#[derive(Debug)]
+pub struct MappedBuffer<'e> {
+    staging_buffer: StagingBuffer<'e>,
+}
+
+impl MappedBuffer<'_> {
+    /// Gives a view over the mapped data, which derefs to `&[u8]`
+    ///
+    /// Only one view at a time can exist, asking for a second one while the first is alive panics.
+    pub fn view(&self) -> wgpu::BufferView<'_> {
+        self.staging_buffer.slice(..).get_mapped_range()
+    }
+}
+
+impl std::ops::Deref for StagingBuffer<'_> {
+    type Target = wgpu::Buffer;
+
+    fn deref(&self) -> &wgpu::Buffer {
+        self.buffer
+            .as_ref()
+            .expect("staging buffer is only taken on drop")
+    }
+}
+
+// locks a cache of the [`Executor`], recovering it if a thread panicked while holding the lock
+//
+// The caches are only inserted into and taken from, so they're consistent also after a panic.
+fn lock_cache<T>(cache: &Mutex<T>) -> MutexGuard<'_, T> {
+    cache.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+impl Drop for StagingBuffer<'_> {
+    fn drop(&mut self) {
+        if let Some(buffer) = self.buffer.take() {
+            buffer.unmap();
+            lock_cache(&self.executor.staging_buffers)
+                .entry(buffer.size())
+                .or_default()
+                .push(buffer);
+        }
+    }
+}
+
+impl Executor {
     /// This function creates sets up the connection with the GPU
     ///
     /// The struct is responsible than of the comunication with the GPU itself, both in termns of
@@ -45,38 +179,145 @@ impl Executor<'_> {
     /// # Panics
     /// - if no adapter is found (default settings, should be rare). Limits are furtherly restricted in case this is compiled for wasm32
     /// - if device don't match features and limits (default settings, should be very rare)
-    pub async fn new(label: Option<&str>) -> Result<Executor<'_>, anyhow::Error> {
-        if let Some(adapter) = Executor::find_adapter().await {
-            let (device, queue) = adapter
-                .request_device(
-                    &wgpu::DeviceDescriptor {
-                        features: wgpu::Features::empty(), // this can be set to various values https://docs.rs/wgpu/latest/wgpu/struct.Features.html
-                        limits: if cfg!(target_arch = "wasm32") {
-                            wgpu::Limits::downlevel_webgl2_defaults()
-                        } else {
-                            wgpu::Limits::default()
-                        },
-                        label,
-                    },
-                    None, // Trace path 'used for API call tracing', probably a sort of log
-                )
-                .await?;
-
-            Ok(Executor {
-                adapter,
-                device,
-                queue,
-                label,
-            })
-        } else {
-            return Err(anyhow!("No adapter found for this phisical device"));
+    pub async fn new(label: Option<&str>) -> Result<Executor, anyhow::Error> {
+        Executor::with_options(label, ExecutorOptions::default()).await
+    }
+
+    /// This function sets up the connection with the GPU like [`Executor::new`], with the given [`ExecutorOptions`]
+    ///
+    /// # Example
+    /// ```
+    /// use wgpu_calc::interface::{Executor, ExecutorOptions};
+    /// use pollster;
+    ///
+    /// let options = ExecutorOptions {
+    ///     backends: wgpu::Backends::all() - wgpu::Backends::DX12,
+    ///     power_preference: wgpu::PowerPreference::LowPower,
+    ///     ..Default::default()
+    /// };
+    /// let interface = pollster::block_on(Executor::with_options(Some("Label"), options)).unwrap();
+    /// ```
+    ///
+    /// # Errors
+    /// - if no adapter matching the options is found
+    /// - if the adapter doesn't support the requested features or limits, giving the missing ones
+    /// - if the device can't be requested from the adapter
+    pub async fn with_options(
+        label: Option<&str>,
+        options: ExecutorOptions,
+    ) -> Result<Executor, anyhow::Error> {
+        let adapter = Executor::find_adapter(&options).await.ok_or_else(|| {
+            anyhow!(
+                "No adapter found for this phisical device with backends {:?}",
+                options.backends
+            )
+        })?;
+        let missing = options.features - adapter.features();
+        if !missing.is_empty() {
+            return Err(anyhow!(
+                "Adapter {} doesn't support the requested features {:?}",
+                adapter.get_info().name,
+                missing
+            ));
         }
+        let mut exceeded = Vec::new();
+        options.limits.check_limits_with_fail_fn(
+            &adapter.limits(),
+            false,
+            |name, requested, allowed| {
+                exceeded.push(format!("{} ({} over {})", name, requested, allowed))
+            },
+        );
+        if !exceeded.is_empty() {
+            return Err(anyhow!(
+                "Adapter {} doesn't support the requested limits {}",
+                adapter.get_info().name,
+                exceeded.join(", ")
+            ));
+        }
+
+        let (device, queue) = adapter
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    // the shader features are requested when available, see [`Shader::entry_point_info`]
+                    features: options.features | (adapter.features() & SHADER_FEATURES),
+                    limits: options.limits,
+                    label,
+                },
+                None, // Trace path 'used for API call tracing', probably a sort of log
+            )
+            .await?;
+
+        Ok(Executor {
+            adapter,
+            device,
+            queue,
+            label: label.map(str::to_owned),
+            bind_group_layouts: Mutex::new(HashMap::new()),
+            shader_modules: Mutex::new(HashMap::new()),
+            pipelines: Mutex::new(HashMap::new()),
+            staging_buffers: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Checks which of the `desired` capabilities are supported by the adapter an [`Executor`] would run on
+    ///
+    /// Only the adapter is requested, without creating a device, so that an application can choose its code path
+    /// (e.g. `f32` instead of `f64`) before creating the [`Executor`], instead of failing at its creation.
+    /// The adapter is chosen with the backends and the power preference of the `options`, as by
+    /// [`Executor::with_options`], and the capabilities are checked against its features and limits.
+    /// Returns each desired capability with whether it's supported, in the same order.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use wgpu_calc::interface::{DesiredFeature, Executor, ExecutorOptions};
+    /// use pollster;
+    ///
+    /// let options = ExecutorOptions::default();
+    /// let support =
+    ///     pollster::block_on(Executor::probe(&[DesiredFeature::ShaderF64], &options)).unwrap();
+    /// if !support[0].1 {
+    ///     println!("f64 is not supported, falling back to f32");
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// - if no adapter matching the options is found
+    pub async fn probe(
+        desired: &[DesiredFeature],
+        options: &ExecutorOptions,
+    ) -> Result<Vec<(DesiredFeature, bool)>, anyhow::Error> {
+        let adapter = Executor::find_adapter(options).await.ok_or_else(|| {
+            anyhow!(
+                "No adapter found for this phisical device with backends {:?}",
+                options.backends
+            )
+        })?;
+        let features = adapter.features();
+        let limits = adapter.limits();
+        Ok(desired
+            .iter()
+            .map(|&feature| {
+                let supported = match feature {
+                    DesiredFeature::ShaderF64 => features.contains(wgpu::Features::SHADER_F64),
+                    DesiredFeature::ShaderF16 => features.contains(wgpu::Features::SHADER_F16),
+                    DesiredFeature::TimestampQuery => {
+                        features.contains(wgpu::Features::TIMESTAMP_QUERY)
+                    }
+                    DesiredFeature::PushConstants => {
+                        features.contains(wgpu::Features::PUSH_CONSTANTS)
+                            && limits.max_push_constant_size > 0
+                    }
+                };
+                (feature, supported)
+            })
+            .collect())
     }
 
     // This function finds the adapters and gives back an Option value. It's primary purpose is the use with [`GpuInterface::new`] function
-    async fn find_adapter() -> Option<wgpu::Adapter> {
+    async fn find_adapter(options: &ExecutorOptions) -> Option<wgpu::Adapter> {
         let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
-            backends: wgpu::Backends::all(), // this is to get all the possible backends
+            backends: options.backends, // by default all the possible backends, see [`ExecutorOptions`]
             dx12_shader_compiler: wgpu::Dx12Compiler::default(),
             flags: InstanceFlags::VALIDATION,
             gles_minor_version: wgpu::Gles3MinorVersion::Automatic,
@@ -86,19 +327,23 @@ impl Executor<'_> {
             .request_adapter(
                 // this asks between all the backends of the instance which is the one satisfying the requisites here under
                 &wgpu::RequestAdapterOptions {
-                    power_preference: wgpu::PowerPreference::HighPerformance, // this can be set to HighPerformance
+                    power_preference: options.power_preference,
                     compatible_surface: None, //this is to check the possibility of using the surface, not used as we want a compute shader
-                    force_fallback_adapter: false, // this is incase we want to use a software back end instead of an hardware one
+                    force_fallback_adapter: options.force_fallback_adapter, // this is incase we want to use a software back end instead of an hardware one
                 },
             )
             .await?;
-        return Some(adapter);
+        Some(adapter)
     }
 
     /// This function gets the bind gropu layout associated with the [`Executor`] device from a descriptor
     ///
     /// The bind layout will be associated with the device created with a new [`Executor`].
     ///
+    /// Layouts are cached by their entries, so that a descriptor with the same entries of a previous one
+    /// gives back the same layout (with the label it was first created with) instead of creating a new one.
+    /// This reduces the amount of objects created on the GPU, since compatible layouts can be shared between pipelines.
+    ///
     /// # Example
     /// ```
     /// use wgpu_calc::interface::Executor;
@@ -129,14 +374,24 @@ impl Executor<'_> {
     ///         }
     ///         ]
     ///     };
-
+    ///
     /// let input_bind_layout = executor.get_bind_group_layout(input_bind_group_layout_descriptor);
     /// ```
     pub fn get_bind_group_layout(
         &self,
         layout_descriptor: &wgpu::BindGroupLayoutDescriptor,
-    ) -> wgpu::BindGroupLayout {
-        self.device.create_bind_group_layout(layout_descriptor)
+    ) -> Arc<wgpu::BindGroupLayout> {
+        let key = layout_descriptor.entries.to_vec();
+        if let Some(layout) = lock_cache(&self.bind_group_layouts).get(&key) {
+            return Arc::clone(layout);
+        }
+        // the layout is created without holding the lock, and the first one inserted wins a race
+        let layout = Arc::new(self.device.create_bind_group_layout(layout_descriptor));
+        Arc::clone(
+            lock_cache(&self.bind_group_layouts)
+                .entry(key)
+                .or_insert(layout),
+        )
     }
 
     /// This method gives back a bind group associated with the [`Executor`]
@@ -156,7 +411,7 @@ impl Executor<'_> {
         &self,
         buffer_init_descriptor: &wgpu::util::BufferInitDescriptor,
     ) -> wgpu::Buffer {
-        self.device.create_buffer_init(&buffer_init_descriptor)
+        self.device.create_buffer_init(buffer_init_descriptor)
     }
 
     /// This method gives a [`wgpu::Buffer`] from a [`wgpu::BufferDescriptor`]
@@ -164,19 +419,106 @@ impl Executor<'_> {
     /// The buffer is not instantiated, nor written, which is useful if the buffer writing
     /// wants to be managd separately
     pub fn get_buffer(&self, buffer_descriptor: &wgpu::BufferDescriptor) -> wgpu::Buffer {
-        self.device.create_buffer(&buffer_descriptor)
+        self.device.create_buffer(buffer_descriptor)
+    }
+
+    /// This method gives a [`wgpu::Buffer`] created mapped, with `data` already written in it
+    ///
+    /// The buffer is created with `mapped_at_creation`, the data is copied into the mapped range and the buffer is
+    /// unmapped, so it's ready to be used by the GPU without any write on the queue.
+    /// The `size` of the `buffer_descriptor` is ignored and replaced by the length of `data`.
+    ///
+    /// # Errors
+    /// - if the length of `data` is zero or not a multiple of [`wgpu::COPY_BUFFER_ALIGNMENT`]
+    pub fn get_buffer_mapped(
+        &self,
+        buffer_descriptor: &wgpu::BufferDescriptor,
+        data: &[u8],
+    ) -> Result<wgpu::Buffer, anyhow::Error> {
+        let size = data.len() as u64;
+        if size == 0 || !size.is_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT) {
+            return Err(anyhow!(
+                "A mapped buffer can't hold {} bytes, the size must be a non zero multiple of {}",
+                size,
+                wgpu::COPY_BUFFER_ALIGNMENT
+            ));
+        }
+        let buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            size,
+            mapped_at_creation: true,
+            ..buffer_descriptor.clone()
+        });
+        buffer
+            .slice(..)
+            .get_mapped_range_mut()
+            .copy_from_slice(data);
+        buffer.unmap();
+        Ok(buffer)
+    }
+
+    /// This method gives a [`wgpu::Texture`] from a [`wgpu::TextureDescriptor`]
+    ///
+    /// Like [`Executor::get_buffer`] the texture is not written, see [`Executor::write_texture`].
+    pub fn get_texture(&self, texture_descriptor: &wgpu::TextureDescriptor) -> wgpu::Texture {
+        self.device.create_texture(texture_descriptor)
+    }
+
+    /// Uses the queue associated to the [`Executor`] to write the whole first mip level of a 2D [`wgpu::Texture`]
+    ///
+    /// The `data` holds the rows of texels one after the other, without any padding between them.
+    ///
+    /// # Errors
+    /// - if the format of the texture has no fixed texel size (e.g. a depth format)
+    /// - if the length of `data` is different from the size of the texture
+    pub fn write_texture(&self, texture: &wgpu::Texture, data: &[u8]) -> Result<(), anyhow::Error> {
+        let row_size = texture_row_size(texture)?;
+        if data.len() as u64 != row_size * texture.height() as u64 {
+            return Err(anyhow!(
+                "Data of {} bytes can't fill a texture of {}x{} texels of {} bytes per row",
+                data.len(),
+                texture.width(),
+                texture.height(),
+                row_size
+            ));
+        }
+        self.queue.write_texture(
+            texture.as_image_copy(),
+            data,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(row_size as u32),
+                rows_per_image: None,
+            },
+            texture.size(),
+        );
+        Ok(())
     }
 
     /// This method associates the [`Shader`] object to the executor, creating a module.
     ///
-    /// At this stage the [`Shader`] must be valid WGSL code, otherwise it will cause the
-    /// program to # panic
-    pub fn get_shader_module(&self, shader: &Shader) -> wgpu::ShaderModule {
-        self.device
-            .create_shader_module(wgpu::ShaderModuleDescriptor {
-                label: self.label,
-                source: wgpu::ShaderSource::Wgsl(std::borrow::Cow::Borrowed(shader.get_content())),
-            })
+    /// At this stage the [`Shader`] must be valid WGSL code (or a valid SPIR-V module, see [`Shader::from_spirv`]),
+    /// otherwise it will cause the program to # panic
+    ///
+    /// Modules are cached by the content of the [`Shader`], so that a [`Shader`] used by many functions is
+    /// compiled only once: the following calls with the same source give back the same module.
+    pub fn get_shader_module(&self, shader: &Shader) -> Arc<wgpu::ShaderModule> {
+        if let Some(module) = lock_cache(&self.shader_modules).get(shader) {
+            return Arc::clone(module);
+        }
+        // the compilation is the slow part, so it's done without holding the lock, and the first module inserted
+        // wins a race
+        let module = Arc::new(
+            self.device
+                .create_shader_module(wgpu::ShaderModuleDescriptor {
+                    label: self.label.as_deref(),
+                    source: shader.source(),
+                }),
+        );
+        Arc::clone(
+            lock_cache(&self.shader_modules)
+                .entry(shader.clone())
+                .or_insert(module),
+        )
     }
 
     /// This method creates a pipeline layout associated with the [`Executor`] from a pipeline layout descriptor
@@ -206,6 +548,66 @@ impl Executor<'_> {
         self.device.create_compute_pipeline(pipeline_descriptor)
     }
 
+    /// This method gives a [`wgpu::ComputePipeline`] for the `entry_point` of the [`Shader`], with the bind groups
+    /// of the given layout entries
+    ///
+    /// The `group_layouts` hold the layout entries of each bind group, in the order of the `@group` index of the shader,
+    /// and `push_constant_size` is the number of bytes of push constants the entry point declares, 0 if none.
+    /// Creating a pipeline is one of the most expensive operations, so the pipelines are cached by the source of the
+    /// [`Shader`], the entry point and the layout entries: the functions running the same entry point on
+    /// compatible bindings share the same pipeline. The bind group layouts come from
+    /// [`Executor::get_bind_group_layout`], so they're compatible with the bind groups made with the same entries.
+    pub fn get_cached_pipeline(
+        &self,
+        shader: &Shader,
+        entry_point: &str,
+        group_layouts: &[Vec<wgpu::BindGroupLayoutEntry>],
+        push_constant_size: u32,
+    ) -> Arc<wgpu::ComputePipeline> {
+        let key = (
+            shader.clone(),
+            entry_point.to_owned(),
+            group_layouts.to_vec(),
+            push_constant_size,
+        );
+        if let Some(pipeline) = lock_cache(&self.pipelines).get(&key) {
+            return Arc::clone(pipeline);
+        }
+
+        let bind_layouts: Vec<Arc<wgpu::BindGroupLayout>> = group_layouts
+            .iter()
+            .map(|entries| {
+                self.get_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    label: Some(entry_point),
+                    entries,
+                })
+            })
+            .collect();
+        let bind_layouts: Vec<&wgpu::BindGroupLayout> =
+            bind_layouts.iter().map(Arc::as_ref).collect();
+        let push_constant_ranges: &[wgpu::PushConstantRange] = match push_constant_size {
+            0 => &[],
+            size => &[wgpu::PushConstantRange {
+                stages: wgpu::ShaderStages::COMPUTE,
+                range: 0..size,
+            }],
+        };
+        let pipeline_layout = self.get_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some(entry_point),
+            bind_group_layouts: &bind_layouts,
+            push_constant_ranges,
+        });
+        let shader_module = self.get_shader_module(shader);
+        let pipeline = Arc::new(self.get_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some(entry_point),
+            layout: Some(&pipeline_layout),
+            module: &shader_module,
+            entry_point,
+        }));
+        // another thread may have created the same pipeline meanwhile, the first one inserted is kept
+        Arc::clone(lock_cache(&self.pipelines).entry(key).or_insert(pipeline))
+    }
+
     /// Gets a [`wgpu::CommandEncoder`] from the device associated with the [`Executor`]
     ///
     /// Takes an optional *`label` string for debugging purposes
@@ -214,6 +616,101 @@ impl Executor<'_> {
             .create_command_encoder(&wgpu::CommandEncoderDescriptor { label })
     }
 
+    /// This method runs a closure with the [`wgpu::Device`] and the [`wgpu::Queue`] of the [`Executor`]
+    ///
+    /// It's an escape hatch to perform the operations this crate doesn't wrap (e.g. creating a query set,
+    /// a sampler or a special buffer) without giving up the [`Executor`].
+    /// Keep in mind that misusing the device or the queue (e.g. destroying a buffer used by an [`crate::algorithm::Algorithm`],
+    /// or submitting work in between its operations) can break the invariants the crate relies on.
+    ///
+    /// # Example
+    /// ```
+    /// use wgpu_calc::interface::Executor;
+    /// use pollster;
+    ///
+    /// let executor = pollster::block_on(Executor::new(Some("Debug Label"))).unwrap();
+    /// let limits = executor.with_device(|device, _queue| device.limits());
+    /// ```
+    pub fn with_device<R>(&self, f: impl FnOnce(&wgpu::Device, &wgpu::Queue) -> R) -> R {
+        f(&self.device, &self.queue)
+    }
+
+    /// Creates a [`wgpu::QuerySet`] of two timestamps, to be written at the beginning and at the end of a compute pass
+    ///
+    /// Returns `None` if the device wasn't created with the [`wgpu::Features::TIMESTAMP_QUERY`] feature
+    /// (see [`ExecutorOptions::features`]), so that the profiling can be skipped where it isn't supported.
+    /// The timestamps are in ticks of [`Executor::timestamp_period`] nanoseconds.
+    pub fn timestamp_query_set(&self, label: Option<&str>) -> Option<wgpu::QuerySet> {
+        if !self
+            .device
+            .features()
+            .contains(wgpu::Features::TIMESTAMP_QUERY)
+        {
+            return None;
+        }
+        Some(self.device.create_query_set(&wgpu::QuerySetDescriptor {
+            label,
+            ty: wgpu::QueryType::Timestamp,
+            count: 2,
+        }))
+    }
+
+    /// Gives the number of nanoseconds of each tick of the timestamps written by the GPU
+    pub fn timestamp_period(&self) -> f32 {
+        self.queue.get_timestamp_period()
+    }
+
+    /// Gives the information about the adapter the [`Executor`] runs on, like its name, backend and device type
+    pub fn adapter_info(&self) -> wgpu::AdapterInfo {
+        self.adapter.get_info()
+    }
+
+    /// Gives the limits of the device of the [`Executor`]
+    ///
+    /// These are the limits the device was created with (see [`ExecutorOptions::limits`]), e.g.
+    /// [`wgpu::Limits::max_compute_workgroups_per_dimension`] to check that the dimensions of a [`crate::variable::Variable`]
+    /// can be dispatched before building an [`crate::algorithm::Algorithm`].
+    ///
+    /// # Example
+    /// ```
+    /// use wgpu_calc::interface::Executor;
+    /// use pollster;
+    ///
+    /// let executor = pollster::block_on(Executor::new(Some("Debug Label"))).unwrap();
+    /// let fits = 100_000 <= executor.limits().max_compute_workgroups_per_dimension;
+    /// ```
+    pub fn limits(&self) -> wgpu::Limits {
+        self.device.limits()
+    }
+
+    /// Gives the backend the [`Executor`] runs on, e.g. [`wgpu::Backend::Vulkan`]
+    pub fn backend(&self) -> wgpu::Backend {
+        self.adapter.get_info().backend
+    }
+
+    /// Tells whether the [`Executor`] runs on a hardware GPU
+    ///
+    /// `wgpu` can silently select a software adapter (e.g. a CPU rasterizer) when no GPU is available, which makes
+    /// the calculations way slower than expected. This returns `false` for the [`wgpu::DeviceType::Cpu`] and
+    /// [`wgpu::DeviceType::Other`] adapters, so that a warning can be given in such case.
+    ///
+    /// # Example
+    /// ```
+    /// use wgpu_calc::interface::Executor;
+    /// use pollster;
+    ///
+    /// let executor = pollster::block_on(Executor::new(Some("Debug Label"))).unwrap();
+    /// if !executor.is_hardware_accelerated() {
+    ///     println!("Running on {}, a software adapter", executor.adapter_info().name);
+    /// }
+    /// ```
+    pub fn is_hardware_accelerated(&self) -> bool {
+        !matches!(
+            self.adapter.get_info().device_type,
+            wgpu::DeviceType::Cpu | wgpu::DeviceType::Other
+        )
+    }
+
     /// This method adds a bind group and a pipeline to the [`Executor`] and calls the dispatch for the pipeline
     ///
     /// Note this is still not executing any opration, this only creates a command encoder, binds the [`wgpu::BindGroup`]
@@ -227,7 +724,7 @@ impl Executor<'_> {
     /// *-`worgroups` - the number of worgroups to use for dispatching the computation pipeline
     /// *-`label` - an optional string for debugging purposes
     pub fn dispatch_bind_and_pipeline(
-        &mut self,
+        &self,
         bind_group: &wgpu::BindGroup,
         pipeline: &wgpu::ComputePipeline,
         workgroups: &[u32; 3],
@@ -238,14 +735,14 @@ impl Executor<'_> {
             .create_command_encoder(&wgpu::CommandEncoderDescriptor { label });
         {
             let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
-                label: self.label,
+                label: self.label.as_deref(),
                 timestamp_writes: None,
             });
             compute_pass.set_bind_group(0, bind_group, &[]);
             compute_pass.set_pipeline(pipeline);
             compute_pass.dispatch_workgroups(workgroups[0], workgroups[1], workgroups[2]);
         }
-        return encoder;
+        encoder
     }
 
     /// Dispatches a [`wgpu::ComputePipeline`] without setting the bindings.
@@ -257,7 +754,7 @@ impl Executor<'_> {
     /// *-`worgroups` - the number of worgroups to use for dispatching the computation pipeline
     /// *-`label` - an optional string for debugging purposes
     pub fn dispatch_pipeline(
-        &mut self,
+        &self,
         pipeline: &wgpu::ComputePipeline,
         workgroups: &[u32; 3],
         label: Option<&str>,
@@ -273,7 +770,7 @@ impl Executor<'_> {
             compute_pass.set_pipeline(pipeline);
             compute_pass.dispatch_workgroups(workgroups[0], workgroups[1], workgroups[2]);
         }
-        return encoder;
+        encoder
     }
 
     /// Uses the queue associated to the [`Executor`] to write a [`wgpu::Buffer`] to the GPU
@@ -287,7 +784,7 @@ impl Executor<'_> {
     /// Note that all the [`wgpu::CommandBuffer`] in the [`Iterator`] will be executed in parallel
     /// in the GPU
     pub fn execute<I: IntoIterator<Item = wgpu::CommandBuffer>>(
-        &mut self,
+        &self,
         command_buffers: I,
     ) -> wgpu::SubmissionIndex {
         self.queue.submit(command_buffers)
@@ -298,63 +795,310 @@ impl Executor<'_> {
     /// To do such it creates a staging buffer before writing back to the CPU.
     /// This allows the comunication to the CPU to happen in parallel with other GPU operations,
     /// but still need to copy the buffer from GPU to GPU before, blocking any other operation during the porcess.
+    /// Staging buffers are kept by the [`Executor`] and reused by the following reads of the same size, also when
+    /// the returned future is dropped before completing.
     pub async fn read_buffer(&self, buffer: &wgpu::Buffer) -> Vec<u8> {
+        let staging_buffer = self.map_to_staging(buffer, 0, buffer.size()).await;
+        let slice: &[u8] = &staging_buffer.slice(..).get_mapped_range();
+        slice.to_owned()
+    }
+
+    /// Reads several [`wgpu::Buffer`]s back from the GPU to the CPU at once
+    ///
+    /// Works like [`Executor::read_buffer`], but the copies to the staging buffers are recorded in a single command encoder
+    /// and submitted together, and all the staging buffers are mapped after a single wait on the device, instead of
+    /// a submission and a synchronisation for each buffer.
+    ///
+    /// Returns the bytes of each of the `buffers`, in the same order.
+    pub async fn read_buffers(&self, buffers: &[&wgpu::Buffer]) -> Vec<Vec<u8>> {
         let mut command_encoder =
             self.device
                 .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                    label: Some("copying command encoder"),
+                    label: Some("batched copying command encoder"),
                 });
-
-        let staging_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Staging Buffer"),
-            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-            size: buffer.size(),
-        });
-
-        command_encoder.copy_buffer_to_buffer(buffer, 0, &staging_buffer, 0, staging_buffer.size());
-
+        let staging_buffers: Vec<StagingBuffer<'_>> = buffers
+            .iter()
+            .map(|buffer| {
+                let staging_buffer = self.staging_buffer(buffer.size());
+                command_encoder.copy_buffer_to_buffer(buffer, 0, &staging_buffer, 0, buffer.size());
+                staging_buffer
+            })
+            .collect();
         self.queue.submit(std::iter::once(command_encoder.finish()));
 
-        let (sender, receiver) = futures_channel::oneshot::channel();
+        let receivers: Vec<_> = staging_buffers
+            .iter()
+            .map(|staging_buffer| {
+                let (sender, receiver) = futures_channel::oneshot::channel();
+                staging_buffer
+                    .slice(..)
+                    .map_async(wgpu::MapMode::Read, |result| {
+                        let _ = sender.send(result);
+                    });
+                receiver
+            })
+            .collect();
+        self.device.poll(wgpu::Maintain::Wait);
+        let mut results = Vec::with_capacity(buffers.len());
+        for (staging_buffer, receiver) in staging_buffers.iter().zip(receivers) {
+            receiver
+                .await
+                .expect("communication failed")
+                .expect("buffer reading failed");
+            results.push(staging_buffer.slice(..).get_mapped_range().to_vec());
+        }
+        results
+    }
+
+    /// Reads a [`wgpu::Buffer`] back from the GPU to the CPU without blocking the thread
+    ///
+    /// Works like [`Executor::read_buffer`], but while the copy is pending the device is polled without waiting and the
+    /// task yields to the async runtime between the polls, instead of parking the thread until the GPU is done.
+    /// This suits async applications sharing the thread with other tasks, at the cost of some busy polling.
+    pub async fn read_buffer_async(&self, buffer: &wgpu::Buffer) -> Vec<u8> {
+        let staging_buffer = self.copy_to_staging(buffer, 0, buffer.size());
+        let (sender, mut receiver) = futures_channel::oneshot::channel();
         staging_buffer
             .slice(..)
             .map_async(wgpu::MapMode::Read, |result| {
                 let _ = sender.send(result);
             });
-        self.device.poll(wgpu::Maintain::Wait); // TODO: poll in the background instead of blocking
-        receiver
-            .await
-            .expect("communication failed")
-            .expect("buffer reading failed");
+        let result = loop {
+            self.device.poll(wgpu::Maintain::Poll);
+            match receiver.try_recv().expect("communication failed") {
+                Some(result) => break result,
+                None => tokio::task::yield_now().await,
+            }
+        };
+        result.expect("buffer reading failed");
         let slice: &[u8] = &staging_buffer.slice(..).get_mapped_range();
-        return slice.to_owned();
+        slice.to_owned()
     }
 
-    pub async fn read_buffer_thread_safe(&self, buffer: Arc<Mutex<wgpu::Buffer>>) -> Vec<u8> {
+    /// Reads a [`wgpu::Buffer`] back from the GPU into the caller provided `destination`
+    ///
+    /// Works like [`Executor::read_buffer`], but copies the data into an existing slice instead of allocating a new
+    /// [`Vec`], which is useful to read buffers in a loop reusing the same memory.
+    ///
+    /// # Errors
+    /// - if the length of `destination` is different from the size of the buffer
+    pub async fn read_buffer_into(
+        &self,
+        buffer: &wgpu::Buffer,
+        destination: &mut [u8],
+    ) -> Result<(), anyhow::Error> {
+        if destination.len() as u64 != buffer.size() {
+            return Err(anyhow!(
+                "Destination of {} bytes can't hold a buffer of {} bytes",
+                destination.len(),
+                buffer.size()
+            ));
+        }
+        let staging_buffer = self.map_to_staging(buffer, 0, buffer.size()).await;
+        destination.copy_from_slice(&staging_buffer.slice(..).get_mapped_range());
+        Ok(())
+    }
+
+    /// Reads a [`wgpu::Buffer`] back from the GPU, writing its bytes to `writer`
+    ///
+    /// The buffer is copied to the CPU in chunks of at most [`READ_CHUNK_SIZE`] bytes, each mapped and written
+    /// straight from the staging buffer, so that neither the CPU nor the GPU need to hold another copy of the whole buffer.
+    /// This allows to stream results bigger than the available memory, e.g. to a file.
+    ///
+    /// # Errors
+    /// - if writing to `writer` fails
+    pub async fn read_buffer_to_writer(
+        &self,
+        buffer: &wgpu::Buffer,
+        writer: &mut impl std::io::Write,
+    ) -> Result<(), anyhow::Error> {
+        let size = buffer.size();
+        let mut offset = 0;
+        while offset < size {
+            let chunk = READ_CHUNK_SIZE.min(size - offset);
+            let staging_buffer = self.map_to_staging(buffer, offset, chunk).await;
+            writer.write_all(&staging_buffer.slice(..).get_mapped_range())?;
+            offset += chunk;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Copies a [`wgpu::Buffer`] back from the GPU and keeps it mapped, to read it in place
+    ///
+    /// Like [`Executor::read_buffer`] the buffer is copied to a staging buffer, but the data is not copied
+    /// again to a [`Vec`]: the returned [`MappedBuffer`] gives access to the mapped memory until it's dropped,
+    /// which is useful to stream big results without holding a second copy of them.
+    pub async fn map_buffer(&self, buffer: &wgpu::Buffer) -> MappedBuffer<'_> {
+        MappedBuffer {
+            staging_buffer: self.map_to_staging(buffer, 0, buffer.size()).await,
+        }
+    }
+
+    /// Reads the bytes `[offset, offset + len)` of a [`wgpu::Buffer`] back from the GPU to the CPU
+    ///
+    /// Works like [`Executor::read_buffer`], but only the requested range is copied to the staging buffer
+    /// and mapped, which is way cheaper when only a small part of a big buffer is needed.
+    ///
+    /// # Errors
+    /// - if `offset` is not a multiple of [`wgpu::COPY_BUFFER_ALIGNMENT`]
+    /// - if the range is empty or exceeds the size of the buffer, also when `offset + len` overflows
+    pub async fn read_buffer_range(
+        &self,
+        buffer: &wgpu::Buffer,
+        offset: u64,
+        len: u64,
+    ) -> Result<Vec<u8>, anyhow::Error> {
+        if !offset.is_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT) {
+            return Err(anyhow!(
+                "Offset {} is not a multiple of {}",
+                offset,
+                wgpu::COPY_BUFFER_ALIGNMENT
+            ));
+        }
+        // the copy must cover a multiple of the alignment, the exceeding bytes are dropped after the read
+        let copy_size = len
+            .checked_next_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT)
+            .filter(|&copy_size| {
+                len != 0
+                    && offset
+                        .checked_add(copy_size)
+                        .is_some_and(|end| end <= buffer.size())
+            });
+        let Some(copy_size) = copy_size else {
+            return Err(anyhow!(
+                "Range of {} bytes at offset {} is not valid for a buffer of {} bytes",
+                len,
+                offset,
+                buffer.size()
+            ));
+        };
+        let staging_buffer = self.map_to_staging(buffer, offset, copy_size).await;
+        let slice: &[u8] = &staging_buffer.slice(..).get_mapped_range();
+        Ok(slice[..len as usize].to_owned())
+    }
+
+    /// Reads a single value of type `T` from the start of a [`wgpu::Buffer`]
+    ///
+    /// This is meant for the result of reductions, which is usually written at offset 0 of the buffer:
+    /// only `size_of::<T>()` bytes are copied and mapped (see [`Executor::read_buffer_range`]), so checking
+    /// e.g. the convergence of an iterative algorithm doesn't need to read back the whole buffer.
+    ///
+    /// # Errors
+    /// - if the buffer is smaller than the value to read
+    pub async fn read_scalar<T: bytemuck::Pod>(
+        &self,
+        buffer: &wgpu::Buffer,
+    ) -> Result<T, anyhow::Error> {
+        let bytes = self
+            .read_buffer_range(buffer, 0, std::mem::size_of::<T>() as u64)
+            .await?;
+        Ok(bytemuck::pod_read_unaligned(&bytes))
+    }
+
+    // copies `size` bytes of the buffer from `offset` to a staging buffer, which is given back mapped and ready to be read
+    //
+    // The staging buffer is taken from the pool of the [`Executor`] and given back to it when the guard is dropped,
+    // so dropping the future before it completes doesn't leave a mapped buffer behind.
+    async fn map_to_staging(
+        &self,
+        buffer: &wgpu::Buffer,
+        offset: u64,
+        size: u64,
+    ) -> StagingBuffer<'_> {
+        let staging_buffer = self.copy_to_staging(buffer, offset, size);
+        self.map_staging(&staging_buffer).await;
+        staging_buffer
+    }
+
+    // records and submits the copy of `size` bytes of the buffer from `offset` to a staging buffer
+    fn copy_to_staging(&self, buffer: &wgpu::Buffer, offset: u64, size: u64) -> StagingBuffer<'_> {
         let mut command_encoder =
             self.device
                 .create_command_encoder(&wgpu::CommandEncoderDescriptor {
                     label: Some("copying command encoder"),
                 });
 
-        let staging_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Staging Buffer"),
-            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-            size: buffer.lock().unwrap().size(),
-        });
+        let staging_buffer = self.staging_buffer(size);
 
-        command_encoder.copy_buffer_to_buffer(
-            &buffer.lock().unwrap(),
-            0,
-            &staging_buffer,
-            0,
-            staging_buffer.size(),
-        );
+        command_encoder.copy_buffer_to_buffer(buffer, offset, &staging_buffer, 0, size);
+
+        self.queue.submit(std::iter::once(command_encoder.finish()));
+        staging_buffer
+    }
 
+    /// Frees the staging buffers kept for reuse by the readbacks
+    ///
+    /// Each readback (e.g. [`Executor::read_buffer`]) gives its staging buffer back to a pool, keyed by size, so that the
+    /// following reads of the same size don't allocate a new one. The pool is never shrunk otherwise, so this
+    /// gives back the GPU memory once the reads of a given size are over.
+    pub fn clear_staging_pool(&self) {
+        lock_cache(&self.staging_buffers).clear();
+    }
+
+    // gives a staging buffer of `size` bytes, reusing one from the pool if available
+    fn staging_buffer(&self, size: u64) -> StagingBuffer<'_> {
+        let reused = lock_cache(&self.staging_buffers)
+            .get_mut(&size)
+            .and_then(Vec::pop);
+        StagingBuffer {
+            executor: self,
+            buffer: Some(reused.unwrap_or_else(|| {
+                self.device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("Staging Buffer"),
+                    usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                    size,
+                })
+            })),
+        }
+    }
+
+    /// Reads the first mip level of a 2D [`wgpu::Texture`] back from the GPU to the CPU
+    ///
+    /// The texture is copied to a staging buffer with `copy_texture_to_buffer`, which needs the rows to be
+    /// aligned to [`wgpu::COPY_BYTES_PER_ROW_ALIGNMENT`]: the padding is removed, so the returned rows are one after
+    /// the other, in the same layout taken by [`Executor::write_texture`].
+    /// The texture needs the [`wgpu::TextureUsages::COPY_SRC`] usage.
+    ///
+    /// # Errors
+    /// - if the format of the texture has no fixed texel size (e.g. a depth format)
+    pub async fn read_texture(&self, texture: &wgpu::Texture) -> Result<Vec<u8>, anyhow::Error> {
+        let row_size = texture_row_size(texture)?;
+        let padded_row_size = row_size.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT as u64);
+        let height = texture.height() as u64;
+
+        let staging_buffer = self.staging_buffer(padded_row_size * height);
+        let mut command_encoder =
+            self.device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("texture copying command encoder"),
+                });
+        command_encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &staging_buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_row_size as u32),
+                    rows_per_image: None,
+                },
+            },
+            texture.size(),
+        );
         self.queue.submit(std::iter::once(command_encoder.finish()));
+        self.map_staging(&staging_buffer).await;
 
+        let mapped = staging_buffer.slice(..).get_mapped_range();
+        Ok(mapped
+            .chunks(padded_row_size as usize)
+            .flat_map(|row| &row[..row_size as usize])
+            .copied()
+            .collect())
+    }
+
+    // maps the staging buffer to be read, waiting for the copy to complete
+    async fn map_staging(&self, staging_buffer: &wgpu::Buffer) {
         let (sender, receiver) = futures_channel::oneshot::channel();
         staging_buffer
             .slice(..)
@@ -364,13 +1108,32 @@ impl Executor<'_> {
         self.device.poll(wgpu::Maintain::Wait); // TODO: poll in the background instead of blocking
         receiver
             .await
-            .expect("communication to GPU buffer failed")
+            .expect("communication failed")
             .expect("buffer reading failed");
+    }
+
+    pub async fn read_buffer_thread_safe(&self, buffer: Arc<Mutex<wgpu::Buffer>>) -> Vec<u8> {
+        let staging_buffer = {
+            let buffer = buffer.lock().unwrap_or_else(PoisonError::into_inner);
+            self.copy_to_staging(&buffer, 0, buffer.size())
+        };
+        self.map_staging(&staging_buffer).await;
         let slice: &[u8] = &staging_buffer.slice(..).get_mapped_range();
-        return slice.to_owned();
+        slice.to_owned()
     }
 }
 
+// gives the size in bytes of a row of texels of the texture, without any padding
+fn texture_row_size(texture: &wgpu::Texture) -> Result<u64, anyhow::Error> {
+    let texel_size = texture.format().block_size(None).ok_or_else(|| {
+        anyhow!(
+            "Texture format {:?} has no fixed texel size",
+            texture.format()
+        )
+    })?;
+    Ok(texel_size as u64 * texture.width() as u64)
+}
+
 #[cfg(test)]
 mod interface_test {
     use super::*;
@@ -378,7 +1141,7 @@ mod interface_test {
     async fn base_calc() {
         let label = Some("Test executor");
 
-        let mut executor = Executor::new(label).await.unwrap();
+        let executor = Executor::new(label).await.unwrap();
 
         let array: [f32; 10000] = [1.0; 10000];
 
@@ -483,6 +1246,352 @@ mod interface_test {
 
         let output = executor.read_buffer(&array1_buffer).await;
 
-        assert_eq!(bytemuck::cast_slice::<u8, f32>(&output), &[2.0; 10000])
+        assert_eq!(bytemuck::cast_slice::<u8, f32>(&output), &[2.0; 10000]);
+
+        let mut scratch = vec![0u8; output.len()];
+        executor
+            .read_buffer_into(&array1_buffer, &mut scratch)
+            .await
+            .unwrap();
+        assert_eq!(scratch, output);
+
+        let mut short = vec![0u8; 4];
+        assert!(executor
+            .read_buffer_into(&array1_buffer, &mut short)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn read_reduced_scalar() {
+        let executor = Executor::new(Some("Test executor")).await.unwrap();
+
+        let shader = Shader::from_content(
+            "
+            @group(0) @binding(0)
+            var<storage,read_write> a: array<atomic<u32>>;
+
+            @compute @workgroup_size(64)
+            fn sum (@builtin(global_invocation_id) id: vec3<u32>) {
+                if (id.x > 0u) {
+                    atomicAdd(&a[0], atomicLoad(&a[id.x]));
+                }
+            }",
+        );
+        let data = vec![1u32; 1 << 16];
+        let buffer = executor.get_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: None,
+            contents: bytemuck::cast_slice(&data),
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+        });
+        let layout = executor.get_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: None,
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Storage { read_only: false },
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let bind_group = executor.get_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+        });
+        let pipeline_layout = executor.get_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: None,
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        let module = executor.get_shader_module(&shader);
+        let pipeline = executor.get_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: None,
+            layout: Some(&pipeline_layout),
+            module: &module,
+            entry_point: "sum",
+        });
+
+        let encoder = executor.dispatch_bind_and_pipeline(
+            &bind_group,
+            &pipeline,
+            &[data.len() as u32 / 64, 1, 1],
+            None,
+        );
+        executor.execute(std::iter::once(encoder.finish()));
+
+        let sum: u32 = executor.read_scalar(&buffer).await.unwrap();
+        assert_eq!(sum, data.len() as u32);
+
+        let range = executor.read_buffer_range(&buffer, 4, 8).await.unwrap();
+        assert_eq!(bytemuck::cast_slice::<u8, u32>(&range), &[1, 1]);
+        assert!(executor.read_buffer_range(&buffer, 2, 4).await.is_err());
+        assert!(executor
+            .read_buffer_range(&buffer, buffer.size(), 4)
+            .await
+            .is_err());
+        // the end of the range overflows, which is an error and not a panic
+        assert!(executor
+            .read_buffer_range(&buffer, u64::MAX - 3, 8)
+            .await
+            .is_err());
+        assert!(executor
+            .read_buffer_range(&buffer, 0, u64::MAX)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn non_blocking_readback() {
+        let executor = Executor::new(Some("Test executor")).await.unwrap();
+        let data: Vec<u32> = (0..1024).collect();
+        let buffer = executor.get_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: None,
+            contents: bytemuck::cast_slice(&data),
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+        });
+        let output = executor.read_buffer_async(&buffer).await;
+        assert_eq!(output, executor.read_buffer(&buffer).await);
+        assert_eq!(bytemuck::cast_slice::<u8, u32>(&output), &data[..]);
+    }
+
+    #[tokio::test]
+    async fn staging_buffer_reuse() {
+        let executor = Executor::new(Some("Test executor")).await.unwrap();
+        let data = [7u32; 16];
+        let buffer = executor.get_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: None,
+            contents: bytemuck::cast_slice(&data),
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+        });
+        let pooled = |size| {
+            executor
+                .staging_buffers
+                .lock()
+                .unwrap()
+                .get(&size)
+                .map_or(0, Vec::len)
+        };
+
+        let output = executor.read_buffer(&buffer).await;
+        assert_eq!(bytemuck::cast_slice::<u8, u32>(&output), &data);
+        assert_eq!(pooled(64), 1);
+        executor.read_buffer(&buffer).await;
+        assert_eq!(pooled(64), 1);
+
+        // a readback abandoned while the mapping is pending gives the buffer back unmapped
+        let staging_buffer = executor.copy_to_staging(&buffer, 0, 64);
+        staging_buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, |_| {});
+        drop(staging_buffer);
+        assert_eq!(pooled(64), 1);
+        let output = executor.read_buffer(&buffer).await;
+        assert_eq!(bytemuck::cast_slice::<u8, u32>(&output), &data);
+        assert_eq!(pooled(64), 1);
+
+        executor.clear_staging_pool();
+        assert_eq!(pooled(64), 0);
+    }
+
+    #[tokio::test]
+    async fn probe_features() {
+        let desired = [DesiredFeature::ShaderF64, DesiredFeature::TimestampQuery];
+        let support = Executor::probe(&desired, &ExecutorOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(support.len(), 2);
+        assert_eq!(support[0].0, DesiredFeature::ShaderF64);
+        assert_eq!(support[1].0, DesiredFeature::TimestampQuery);
+
+        // no adapter is found without backends
+        let options = ExecutorOptions {
+            backends: wgpu::Backends::empty(),
+            ..Default::default()
+        };
+        assert!(Executor::probe(&desired, &options).await.is_err());
+
+        let executor = Executor::new(Some("Test executor")).await.unwrap();
+        let f64_supported = executor
+            .adapter
+            .features()
+            .contains(wgpu::Features::SHADER_F64);
+        assert_eq!(support[0].1, f64_supported);
+    }
+
+    #[tokio::test]
+    async fn hardware_acceleration() {
+        let executor = Executor::new(Some("Test executor")).await.unwrap();
+        let software = matches!(
+            executor.adapter_info().device_type,
+            wgpu::DeviceType::Cpu | wgpu::DeviceType::Other
+        );
+        assert_eq!(executor.is_hardware_accelerated(), !software);
+    }
+
+    #[tokio::test]
+    async fn excluded_backends() {
+        assert_eq!(ExecutorOptions::default().backends, wgpu::Backends::all());
+        let options = ExecutorOptions {
+            backends: wgpu::Backends::empty(),
+            ..Default::default()
+        };
+        let error = Executor::with_options(Some("Test executor"), options.clone())
+            .await
+            .unwrap_err();
+        // the requested mask is given, to debug which backends were excluded
+        let mask = format!("{:?}", options.backends);
+        assert!(error.to_string().contains(&mask), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn low_power_adapter() {
+        let options = ExecutorOptions {
+            power_preference: wgpu::PowerPreference::LowPower,
+            ..Default::default()
+        };
+        let executor = Executor::with_options(Some("Test executor"), options.clone())
+            .await
+            .unwrap();
+        assert_eq!(executor.backend(), executor.adapter_info().backend);
+        assert_eq!(executor.limits(), options.limits);
+        assert!(options.backends.contains(executor.backend().into()));
+    }
+
+    #[tokio::test]
+    async fn unsupported_limits() {
+        let options = ExecutorOptions {
+            limits: wgpu::Limits {
+                max_compute_workgroups_per_dimension: u32::MAX,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let error = Executor::with_options(Some("Test executor"), options)
+            .await
+            .unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("max_compute_workgroups_per_dimension"),
+            "{}",
+            error
+        );
+    }
+
+    #[tokio::test]
+    async fn shader_module_cache() {
+        let executor = Executor::new(Some("Test executor")).await.unwrap();
+        let shader = Shader::from_file_path("./tests/shaders/example_shader.wgsl").unwrap();
+        let same_shader = Shader::from_content(shader.get_content());
+        let other_shader = Shader::from_file_path("./tests/shaders/mat2calcs.wgsl").unwrap();
+
+        let module = executor.get_shader_module(&shader);
+        assert!(Arc::ptr_eq(
+            &module,
+            &executor.get_shader_module(&same_shader)
+        ));
+        assert!(!Arc::ptr_eq(
+            &module,
+            &executor.get_shader_module(&other_shader)
+        ));
+    }
+
+    #[tokio::test]
+    async fn pipeline_cache() {
+        let executor = Executor::new(Some("Test executor")).await.unwrap();
+        let shader = Shader::from_file_path("./tests/shaders/mat2calcs.wgsl").unwrap();
+        let entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only: false },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+
+        let pipeline = executor.get_cached_pipeline(&shader, "add_1", &[vec![entry(0)]], 0);
+        let same = executor.get_cached_pipeline(&shader, "add_1", &[vec![entry(0)]], 0);
+        assert!(Arc::ptr_eq(&pipeline, &same));
+        let other_entry_point =
+            executor.get_cached_pipeline(&shader, "add_matrices", &[vec![entry(0), entry(1)]], 0);
+        assert!(!Arc::ptr_eq(&pipeline, &other_entry_point));
+        let other_layout =
+            executor.get_cached_pipeline(&shader, "add_1", &[vec![entry(0), entry(1)]], 0);
+        assert!(!Arc::ptr_eq(&pipeline, &other_layout));
+        let other_groups =
+            executor.get_cached_pipeline(&shader, "add_1", &[vec![entry(0)], Vec::new()], 0);
+        assert!(!Arc::ptr_eq(&pipeline, &other_groups));
+    }
+
+    #[tokio::test]
+    async fn concurrent_caches() {
+        let executor = Executor::new(Some("Test executor")).await.unwrap();
+        let shader = Shader::from_file_path("./tests/shaders/mat2calcs.wgsl").unwrap();
+        let entry = wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only: false },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+
+        // the threads racing on the same shader all get the one cached
+        let pipelines: Vec<Arc<wgpu::ComputePipeline>> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..4)
+                .map(|_| {
+                    scope
+                        .spawn(|| executor.get_cached_pipeline(&shader, "add_1", &[vec![entry]], 0))
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect()
+        });
+        let cached = executor.get_cached_pipeline(&shader, "add_1", &[vec![entry]], 0);
+        assert!(pipelines
+            .iter()
+            .all(|pipeline| Arc::ptr_eq(pipeline, &cached)));
+    }
+
+    #[tokio::test]
+    async fn bind_group_layout_cache() {
+        let executor = Executor::new(Some("Test executor")).await.unwrap();
+
+        let entry = |read_only| wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let read_write = [entry(false)];
+        let read_only = [entry(true)];
+        let layout = |entries| wgpu::BindGroupLayoutDescriptor {
+            label: None,
+            entries,
+        };
+
+        let first = executor.get_bind_group_layout(&layout(&read_write));
+        let same = executor.get_bind_group_layout(&layout(&read_write));
+        let other = executor.get_bind_group_layout(&layout(&read_only));
+
+        assert!(Arc::ptr_eq(&first, &same));
+        assert!(!Arc::ptr_eq(&first, &other));
     }
 }
@@ -29,7 +29,7 @@ struct GpuArray2<'a> {
 impl<'a> GpuArray2<'a> {
     fn new(array: Array2<f32>, name: &'a str) -> GpuArray2<'a> {
         let (n_cols, n_rows) = array.dim();
-        let data = array.as_slice().unwrap().to_owned();
+        let data = array.as_standard_layout().iter().copied().collect();
         Self {
             data,
             n_rows: n_rows as u64,
@@ -79,10 +79,13 @@ impl Variable for GpuArray2<'_> {
     }
 
     // This is the opposite of the [`byte_data`] method, used to read variabled back from
-    // an array of u8
-    fn read_data(&mut self, slice: &[u8]) {
-        let vec: Vec<f32> = bytemuck::cast_slice(slice).to_owned();
-        self.data = vec;
+    // an array of u8. It fails if the GPU gives back a number of bytes different than the size of the array
+    fn read_data(&mut self, slice: &[u8]) -> Result<(), anyhow::Error> {
+        if slice.len() != self.data.len() * std::mem::size_of::<f32>() {
+            return Err(anyhow::anyhow!("{} bytes can't be read into {}", slice.len(), self.name));
+        }
+        self.data = bytemuck::cast_slice(slice).to_owned();
+        Ok(())
     }
 }
 
@@ -124,9 +127,9 @@ async fn main() {
     let function = Function::new(&shader, "add_1", bindings);
 
     // we add the function to the algorithm. Notice this will not execute anything, and
-    // we could add more of them to be executed sequentially. In this step the variable is
-    // written in the GPU buffer
-    algorithm.add_fun(function);
+    // we could add more of them to be executed sequentially. In this step the GPU buffer of
+    // the variable is created, while its data is written when the algorithm is run
+    algorithm.add_fun(function).unwrap();
 
 
     // we need to use this method to extract a variable. This
@@ -150,6 +153,14 @@ async fn main() {
 
 ```
 
+The public lifecycle of an [`algorithm::Algorithm`] is always the same:
+[`algorithm::Algorithm::add_fun`] queues the functions (and creates the buffers of their variables),
+[`algorithm::Algorithm::read_variable`] marks the variables to be copied back,
+[`algorithm::Algorithm::run`] uploads the variables (see [`algorithm::Algorithm::upload_all`]), submits the work and
+reads the marked variables back into their [`std::sync::Mutex`].
+[`algorithm::Algorithm::get_output_unmap`] reads a single variable after the run without marking it beforehand, and
+[`algorithm::Algorithm::get_operations`] lists what has been queued.
+
 Although the crate works for this simple situations (which is not a lot, but still enough to implement and execute any
 kind of linear algebra with 1D to 3D matrices) more work is still needed to make some internal features of wgpu accessible
 to the API user, like automatic padding for structs or more default implementations of the Variable trait
@@ -158,7 +169,8 @@ To notice also that the [`algorithm::Algorithm`] currently executes the [`algori
 in parallel in the GPU in the order it's added to the [`algorithm::Algorithm`].
 The infrastructure of the crate is already in place to optimize and run in parallel functions which don't act on the same [`variable::Variable`].
 
-Another improvement to be done is the parallelisation of the buffers write, which is always the worst bottleneck of the CPU-GPU interface.
+The buffers write, which is always the worst bottleneck of the CPU-GPU interface, is done from several threads when
+there is enough data to upload (see [`algorithm::Algorithm::upload_all`]).
 */
 
 #![allow(dead_code)]
@@ -167,6 +179,16 @@ use wasm_bindgen::prelude::*;
 
 pub mod algorithm;
 pub mod coding;
-pub(crate) mod errors;
+pub mod errors;
 pub mod interface;
+mod linalg;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod translator;
 pub mod variable;
+
+// used by the [`impl_variable`] macro, so that the crates calling it don't need to depend on anyhow and bytemuck
+#[doc(hidden)]
+pub use anyhow;
+#[doc(hidden)]
+pub use bytemuck;
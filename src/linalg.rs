@@ -0,0 +1,96 @@
+//! This module contains the linear algebra operations built in the [`Algorithm`] which run it, like the inversion
+//!
+//! They are made only of [`Function`]s added to the [`Algorithm`], with their shaders shipped
+//! with the crate, and they work on the [`GpuArray2`] of the [`crate::translator`] module. The operations which are
+//! only added to the [`Algorithm`] are in [`crate::algorithm::ops`].
+
+use std::sync::{Arc, Mutex, OnceLock};
+
+use anyhow::anyhow;
+use ndarray::{concatenate, s, Array2, Axis};
+
+use crate::algorithm::{lock_variable, Algorithm, Function, VariableBind};
+use crate::coding::Shader;
+use crate::translator::GpuArray2;
+use crate::variable::Variable;
+
+// gives the shader with the passes of the Gauss-Jordan elimination
+fn gauss_jordan_shader() -> &'static Shader {
+    static SHADER: OnceLock<Shader> = OnceLock::new();
+    SHADER.get_or_init(|| Shader::from_content(include_str!("shaders/gauss_jordan.wgsl")))
+}
+
+impl Algorithm<'_, GpuArray2> {
+    /// Inverts the square matrix `a` on the GPU, replacing it with its inverse
+    ///
+    /// The inverse is computed by Gauss-Jordan elimination with partial pivoting on the augmented matrix
+    /// `[a | I]`: for each column the row with the largest value is swapped in place and normalised, and then
+    /// the column is eliminated from all the other rows, in 4 dispatches per column.
+    /// The matrix is considered singular when the largest pivot of a column is below `n * f32::EPSILON` times
+    /// the largest absolute value of `a`.
+    ///
+    /// The method runs the [`Algorithm`] (see [`Algorithm::run`]), so any operation already added is executed
+    /// before the inversion. The variables used for the calculation stay in the [`Algorithm`].
+    ///
+    /// # Errors
+    /// - if `a` is not square
+    /// - if `a` is singular, in which case it's left untouched
+    pub async fn invert(&mut self, a: &mut GpuArray2) -> Result<(), anyhow::Error> {
+        let (n_rows, n_cols) = a.dim();
+        if n_rows != n_cols {
+            return Err(anyhow!(
+                "Matrix {:?} has dimensions {:?}, only square matrices can be inverted",
+                a.get_name(),
+                a.dim()
+            ));
+        }
+        let n = n_rows;
+        let matrix = a.to_ndarray()?;
+        let max_value = matrix.iter().fold(0f32, |max, value| max.max(value.abs()));
+        let tolerance = n as f32 * f32::EPSILON * max_value;
+
+        let augmented = concatenate(Axis(1), &[matrix.view(), Array2::eye(n).view()])?;
+        let aug = Arc::new(Mutex::new(GpuArray2::from_ndarray(
+            augmented,
+            Some("gauss-jordan augmented"),
+        )?));
+        let state = Arc::new(Mutex::new(GpuArray2::from_ndarray(
+            ndarray::arr2(&[[0., 0., n as f32, tolerance]]),
+            Some("gauss-jordan state"),
+        )?));
+        let factors = Arc::new(Mutex::new(GpuArray2::from_ndarray(
+            Array2::zeros((n, 1)),
+            Some("gauss-jordan factors"),
+        )?));
+
+        let shader = gauss_jordan_shader();
+        // bound in this order to the shader
+        let variables = [&aug, &state, &factors];
+        for _ in 0..n {
+            // the first variable of each pass gives its workgroups
+            for (entry_point, first) in [
+                ("pivot", 1),
+                ("row_factors", 2),
+                ("eliminate", 0),
+                ("advance", 1),
+            ] {
+                let binds = std::iter::once(first)
+                    .chain((0..variables.len()).filter(|&bind| bind != first))
+                    .map(|bind| VariableBind::new(Arc::clone(variables[bind]), bind as u32))
+                    .collect();
+                self.add_fun(Function::new(shader, entry_point, binds))?;
+            }
+        }
+        self.read_variable(&aug)?;
+        self.read_variable(&state)?;
+        self.run().await?;
+
+        if lock_variable(&state)?.to_ndarray()?[[0, 1]] != 0.0 {
+            return Err(anyhow!("Matrix {:?} is singular", a.get_name()));
+        }
+        let inverse = lock_variable(&aug)?.to_ndarray()?;
+        let name = a.get_name().map(str::to_owned);
+        *a = GpuArray2::from_ndarray(inverse.slice(s![.., n..]), name.as_deref())?;
+        Ok(())
+    }
+}
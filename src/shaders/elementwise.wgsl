@@ -0,0 +1,49 @@
+// elementwise binary operations between the f32 arrays `a` and `b`, written to `out`
+//
+// `out` can be the same buffer as `a` or `b`, so all of them are read_write.
+// The elements are spread on the x and y axes of the workgroups, so that the
+// arrays can be longer than the workgroups of a single axis can cover.
+
+@group(0) @binding(0)
+var<storage,read_write> a: array<f32>;
+@group(0) @binding(1)
+var<storage,read_write> b: array<f32>;
+@group(0) @binding(2)
+var<storage,read_write> out: array<f32>;
+
+// gives the index of the element of the invocation, out of bounds for the ones past the end
+fn element(id: vec3<u32>, workgroups: vec3<u32>) -> u32 {
+    return id.x + id.y * workgroups.x * 64u;
+}
+
+@compute @workgroup_size(64)
+fn add(@builtin(global_invocation_id) id: vec3<u32>, @builtin(num_workgroups) workgroups: vec3<u32>) {
+    let i = element(id, workgroups);
+    if (i < arrayLength(&out)) {
+        out[i] = a[i] + b[i];
+    }
+}
+
+@compute @workgroup_size(64)
+fn sub(@builtin(global_invocation_id) id: vec3<u32>, @builtin(num_workgroups) workgroups: vec3<u32>) {
+    let i = element(id, workgroups);
+    if (i < arrayLength(&out)) {
+        out[i] = a[i] - b[i];
+    }
+}
+
+@compute @workgroup_size(64)
+fn mul_elementwise(@builtin(global_invocation_id) id: vec3<u32>, @builtin(num_workgroups) workgroups: vec3<u32>) {
+    let i = element(id, workgroups);
+    if (i < arrayLength(&out)) {
+        out[i] = a[i] * b[i];
+    }
+}
+
+@compute @workgroup_size(64)
+fn div(@builtin(global_invocation_id) id: vec3<u32>, @builtin(num_workgroups) workgroups: vec3<u32>) {
+    let i = element(id, workgroups);
+    if (i < arrayLength(&out)) {
+        out[i] = a[i] / b[i];
+    }
+}
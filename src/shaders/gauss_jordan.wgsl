@@ -0,0 +1,83 @@
+// Gauss-Jordan elimination with partial pivoting on the augmented matrix [A | I]
+//
+// `aug` is the n x 2n augmented matrix in row major order, `state` holds
+// [current column, singular flag, n, pivot tolerance] and `factors` the
+// elimination factor of each row for the current column.
+
+@group(0) @binding(0)
+var<storage,read_write> aug: array<f32>;
+@group(0) @binding(1)
+var<storage,read_write> state: array<f32>;
+@group(0) @binding(2)
+var<storage,read_write> factors: array<f32>;
+
+// selects the pivot of the current column, swaps its row in place and normalises it
+@compute @workgroup_size(1)
+fn pivot(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (id.x != 0u || id.y != 0u || state[1] != 0.0) {
+        return;
+    }
+    let k = u32(state[0]);
+    let n = u32(state[2]);
+    let w = 2u * n;
+
+    var best = k;
+    var best_value = abs(aug[k * w + k]);
+    for (var i = k + 1u; i < n; i = i + 1u) {
+        let value = abs(aug[i * w + k]);
+        if (value > best_value) {
+            best = i;
+            best_value = value;
+        }
+    }
+    if (best_value <= state[3]) {
+        state[1] = 1.0;
+        return;
+    }
+
+    if (best != k) {
+        for (var j = 0u; j < w; j = j + 1u) {
+            let swap = aug[k * w + j];
+            aug[k * w + j] = aug[best * w + j];
+            aug[best * w + j] = swap;
+        }
+    }
+    let pivot_value = aug[k * w + k];
+    for (var j = 0u; j < w; j = j + 1u) {
+        aug[k * w + j] = aug[k * w + j] / pivot_value;
+    }
+}
+
+// stores the factor of each row for the current column, zero for the pivot row
+@compute @workgroup_size(1)
+fn row_factors(@builtin(global_invocation_id) id: vec3<u32>) {
+    let k = u32(state[0]);
+    let n = u32(state[2]);
+    let i = id.y;
+    if (id.x != 0u || i >= n) {
+        return;
+    }
+    factors[i] = select(aug[i * 2u * n + k], 0.0, i == k);
+}
+
+// removes the current column from all the rows but the pivot one
+@compute @workgroup_size(1)
+fn eliminate(@builtin(global_invocation_id) id: vec3<u32>) {
+    let k = u32(state[0]);
+    let n = u32(state[2]);
+    let w = 2u * n;
+    let i = id.y;
+    let j = id.x;
+    if (i >= n || j >= w || state[1] != 0.0) {
+        return;
+    }
+    aug[i * w + j] = aug[i * w + j] - factors[i] * aug[k * w + j];
+}
+
+// moves to the next column
+@compute @workgroup_size(1)
+fn advance(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (id.x == 0u && id.y == 0u) {
+        state[0] = state[0] + 1.0;
+    }
+}
@@ -0,0 +1,48 @@
+// tiled matrix multiplication `out = a * b` of row major matrices
+//
+// `a` is M x K, `b` is K x N and `out` is M x N. Each workgroup calculates
+// an 8 x 8 tile of `out`, loading the tiles of `a` and `b` it needs in the
+// workgroup memory, so that each element is read once per workgroup.
+
+const M: u32 = €m;
+const K: u32 = €k;
+const N: u32 = €n;
+const TILE: u32 = 8u;
+
+@group(0) @binding(0)
+var<storage,read_write> out: array<f32>;
+@group(0) @binding(1)
+var<storage,read_write> a: array<f32>;
+@group(0) @binding(2)
+var<storage,read_write> b: array<f32>;
+
+var<workgroup> tile_a: array<array<f32, 8>, 8>;
+var<workgroup> tile_b: array<array<f32, 8>, 8>;
+
+@compute @workgroup_size(8, 8)
+fn matmul(@builtin(global_invocation_id) id: vec3<u32>, @builtin(local_invocation_id) local: vec3<u32>) {
+    let row = id.y;
+    let col = id.x;
+    var sum = 0.0;
+    for (var t = 0u; t < (K + TILE - 1u) / TILE; t = t + 1u) {
+        // the elements past the edges of the matrices are loaded as 0
+        let a_col = t * TILE + local.x;
+        let b_row = t * TILE + local.y;
+        tile_a[local.y][local.x] = 0.0;
+        if (row < M && a_col < K) {
+            tile_a[local.y][local.x] = a[row * K + a_col];
+        }
+        tile_b[local.y][local.x] = 0.0;
+        if (b_row < K && col < N) {
+            tile_b[local.y][local.x] = b[b_row * N + col];
+        }
+        workgroupBarrier();
+        for (var i = 0u; i < TILE; i = i + 1u) {
+            sum = sum + tile_a[local.y][i] * tile_b[i][local.x];
+        }
+        workgroupBarrier();
+    }
+    if (row < M && col < N) {
+        out[row * N + col] = sum;
+    }
+}
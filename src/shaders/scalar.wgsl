@@ -0,0 +1,30 @@
+// elementwise operations between the f32 array `a` and the scalar `scalar`, in place
+//
+// The elements are spread on the x and y axes of the workgroups as in the
+// elementwise shader.
+
+@group(0) @binding(0)
+var<storage,read_write> a: array<f32>;
+@group(0) @binding(1)
+var<uniform> scalar: f32;
+
+// gives the index of the element of the invocation, out of bounds for the ones past the end
+fn element(id: vec3<u32>, workgroups: vec3<u32>) -> u32 {
+    return id.x + id.y * workgroups.x * 64u;
+}
+
+@compute @workgroup_size(64)
+fn scale(@builtin(global_invocation_id) id: vec3<u32>, @builtin(num_workgroups) workgroups: vec3<u32>) {
+    let i = element(id, workgroups);
+    if (i < arrayLength(&a)) {
+        a[i] = a[i] * scalar;
+    }
+}
+
+@compute @workgroup_size(64)
+fn offset(@builtin(global_invocation_id) id: vec3<u32>, @builtin(num_workgroups) workgroups: vec3<u32>) {
+    let i = element(id, workgroups);
+    if (i < arrayLength(&a)) {
+        a[i] = a[i] + scalar;
+    }
+}
@@ -0,0 +1,33 @@
+// tiled transposition of the row major matrix `input`, ROWS x COLS, to `output`, COLS x ROWS
+//
+// Each workgroup reads a tile of `input` along its rows to the workgroup memory,
+// and writes it transposed along the rows of `output`, so that both the reads
+// and the writes of a workgroup are contiguous.
+
+const ROWS: u32 = €rows;
+const COLS: u32 = €cols;
+const TILE: u32 = u32(€tile);
+
+@group(0) @binding(0)
+var<storage,read_write> input: array<f32>;
+@group(0) @binding(1)
+var<storage,read_write> output: array<f32>;
+
+// padded by a column, so that the columns of the tile aren't read from the same memory bank
+var<workgroup> tile: array<array<f32, €padded_tile>, €tile>;
+
+@compute @workgroup_size(€tile, €tile)
+fn transpose(@builtin(workgroup_id) group: vec3<u32>, @builtin(local_invocation_id) local: vec3<u32>) {
+    let in_row = group.y * TILE + local.y;
+    let in_col = group.x * TILE + local.x;
+    if (in_row < ROWS && in_col < COLS) {
+        tile[local.y][local.x] = input[in_row * COLS + in_col];
+    }
+    workgroupBarrier();
+
+    let out_row = group.x * TILE + local.y;
+    let out_col = group.y * TILE + local.x;
+    if (out_row < COLS && out_col < ROWS) {
+        output[out_row * ROWS + out_col] = tile[local.x][local.y];
+    }
+}
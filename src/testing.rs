@@ -0,0 +1,126 @@
+//! This module contains helpers to check the results of the calculations in tests
+//!
+//! The GPUs don't give bit exact results, which can change between drivers and devices (e.g. because of fused
+//! multiply-add or a different order of reductions), so the results need to be compared with a tolerance.
+//! The module is available with the `testing` feature.
+
+use crate::translator::GpuArray2;
+use crate::variable::Variable;
+
+/// The tolerance used by [`Tolerance::tolerance`] if not given by the type
+pub const DEFAULT_TOLERANCE: f32 = 1e-5;
+
+/// This trait declares the tolerance to use when comparing a [`Variable`] with [`assert_variable_close`]
+///
+/// Different data needs different tolerances, e.g. normalized values can be compared way more strictly than raw
+/// magnitudes, so each type can declare its own. The default is [`DEFAULT_TOLERANCE`].
+pub trait Tolerance
+where
+    Self: Variable,
+{
+    /// The tolerance relative to the magnitude of the expected values
+    ///
+    /// Two elements `got` and `expected` are close if `|got - expected| <= tolerance * max(1, |expected|)`, so the
+    /// tolerance is absolute for the values smaller than 1 and relative for the bigger ones.
+    fn tolerance(&self) -> f32 {
+        DEFAULT_TOLERANCE
+    }
+}
+
+impl Tolerance for GpuArray2 {}
+
+/// Asserts that all the elements of `got` are close to the ones of `expected`, within [`Tolerance::tolerance`]
+///
+/// The tolerance is the one declared by `expected`, see [`assert_variable_close_with`] for the details.
+///
+/// # Example
+/// ```
+/// use ndarray::array;
+/// use wgpu_calc::testing::assert_variable_close;
+/// use wgpu_calc::translator::GpuArray2;
+///
+/// let got = GpuArray2::from_ndarray(array![[1.000_001, 2.]], None).unwrap();
+/// let expected = GpuArray2::from_ndarray(array![[1., 2.]], None).unwrap();
+/// assert_variable_close(&got, &expected);
+/// ```
+///
+/// # Panics
+/// - if the two variables have different dimensions or an element which isn't an `f32`
+/// - if an element of `got` is not close to the same one of `expected`
+pub fn assert_variable_close<V: Tolerance>(got: &V, expected: &V) {
+    assert_variable_close_with(got, expected, expected.tolerance());
+}
+
+/// Asserts that all the elements of `got` are close to the ones of `expected`, within `tolerance`
+///
+/// The data of the variables is compared as `f32`, and two elements are close if
+/// `|got - expected| <= tolerance * max(1, |expected|)`. The `NaN`s are never close.
+///
+/// # Panics
+/// - if the two variables have different dimensions or an element which isn't an `f32`
+/// - if an element of `got` is not close to the same one of `expected`, giving its index
+pub fn assert_variable_close_with<V: Variable>(got: &V, expected: &V, tolerance: f32) {
+    assert_eq!(
+        got.dimension_sizes(),
+        expected.dimension_sizes(),
+        "variables {:?} and {:?} have different dimensions",
+        got.get_name(),
+        expected.get_name()
+    );
+    let element_size = std::mem::size_of::<f32>() as u64;
+    assert!(
+        got.element_size().is_none_or(|size| size == element_size)
+            && expected
+                .element_size()
+                .is_none_or(|size| size == element_size),
+        "variables {:?} and {:?} can only be compared as f32",
+        got.get_name(),
+        expected.get_name()
+    );
+    let got_data = to_f32(got.byte_data());
+    let expected_data = to_f32(expected.byte_data());
+    assert_eq!(got_data.len(), expected_data.len());
+
+    for (index, (got_value, expected_value)) in got_data.iter().zip(&expected_data).enumerate() {
+        let bound = tolerance * expected_value.abs().max(1.);
+        assert!(
+            (got_value - expected_value).abs() <= bound,
+            "element {} of {:?} is {}, but {} was expected (tolerance {})",
+            index,
+            got.get_name(),
+            got_value,
+            expected_value,
+            bound
+        );
+    }
+}
+
+// reads the data as f32, the bytes of a variable aren't necessarily aligned
+fn to_f32(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(std::mem::size_of::<f32>())
+        .map(bytemuck::pod_read_unaligned)
+        .collect()
+}
+
+#[cfg(test)]
+mod testing_test {
+    use super::*;
+    use ndarray::array;
+
+    #[test]
+    fn close_variables() {
+        let got = GpuArray2::from_ndarray(array![[1000.001, 0.000_001]], None).unwrap();
+        let expected = GpuArray2::from_ndarray(array![[1000., 0.]], None).unwrap();
+        assert_variable_close(&got, &expected);
+        assert_variable_close_with(&got, &expected, 1e-3);
+    }
+
+    #[test]
+    #[should_panic(expected = "element 1")]
+    fn distant_variables() {
+        let got = GpuArray2::from_ndarray(array![[1., 2.1]], None).unwrap();
+        let expected = GpuArray2::from_ndarray(array![[1., 2.]], None).unwrap();
+        assert_variable_close_with(&got, &expected, 1e-2);
+    }
+}
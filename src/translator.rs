@@ -0,0 +1,539 @@
+//! This module contains ready made implementations of the [`Variable`] trait for common data types
+//!
+//! These are meant to spare the user from writing the byte conversions for the most used formats,
+//! like the [`ndarray`] arrays, with [`GpuArray1`] for vectors, [`GpuArray2`] for matrices and [`GpuArray3`]
+//! for volumes.
+//! It also contains [`GpuImage`], an implementation of the [`TextureVariable`] trait for grayscale images.
+
+use anyhow::anyhow;
+use core::fmt::Debug;
+use ndarray::{Array1, Array2, Array3, ArrayBase, ArrayD, Data, Ix1, Ix2, Ix3, IxDyn};
+
+use crate::errors::VariableError;
+use crate::variable::{TextureVariable, Variable};
+
+/// A 1 dimensional array of `f32` which can be used as a [`Variable`]
+///
+/// The elements run along the x dimension of the workgroups, so that elementwise vector kernels index it
+/// with the `global_invocation_id.x`.
+#[derive(Debug, PartialEq)]
+pub struct GpuArray1 {
+    data: Vec<f32>,
+    name: Option<String>,
+}
+
+impl GpuArray1 {
+    /// Creates a new [`GpuArray1`] copying the data of a 1 dimensional [`ndarray`] array
+    ///
+    /// Any kind of array can be given, owned arrays as well as views, also with a step.
+    ///
+    /// # Errors
+    /// The method errors if the array has no elements, which wouldn't be possible to bind on the GPU
+    pub fn from_ndarray<S: Data<Elem = f32>>(
+        array: ArrayBase<S, Ix1>,
+        name: Option<&str>,
+    ) -> Result<Self, anyhow::Error> {
+        if array.is_empty() {
+            return Err(anyhow!(
+                "Array {:?} has no elements, but empty arrays can't be used on the GPU",
+                name
+            ));
+        }
+        Ok(Self {
+            data: array.to_vec(),
+            name: name.map(str::to_owned),
+        })
+    }
+
+    /// Gives the number of elements of the array
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Returns whether the array has no elements, which is never the case for a [`GpuArray1`]
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Converts the array back to an [`ndarray`] array
+    pub fn to_array1(&self) -> Array1<f32> {
+        Array1::from_vec(self.data.clone())
+    }
+}
+
+impl Variable for GpuArray1 {
+    fn byte_size(&self) -> u64 {
+        (std::mem::size_of::<f32>() * self.data.len()) as u64
+    }
+
+    fn byte_data(&self) -> &[u8] {
+        bytemuck::cast_slice(&self.data)
+    }
+
+    fn dimension_sizes(&self) -> [u32; 3] {
+        [self.data.len() as u32, 1, 1]
+    }
+
+    fn get_name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    fn element_size(&self) -> Option<u64> {
+        Some(std::mem::size_of::<f32>() as u64)
+    }
+
+    fn wgsl_type(&self) -> Option<&str> {
+        Some("array<f32>")
+    }
+
+    fn read_data(&mut self, slice: &[u8]) -> Result<(), anyhow::Error> {
+        self.data = read_elements(slice, self.data.len())?;
+        Ok(())
+    }
+}
+
+/// A scalar type which can be the element of a [`GpuMatrix`]
+///
+/// It's implemented for the 32 bits scalars of WGSL: `f32`, `i32` and `u32`. The shader has to declare the
+/// buffer with the matching element type, [`GpuScalar::WGSL_ARRAY_TYPE`], since the bytes are sent as they are.
+pub trait GpuScalar: bytemuck::Pod + Send + Debug + PartialEq {
+    /// the name of the scalar in WGSL, e.g. `f32`
+    const WGSL_TYPE: &'static str;
+    /// the runtime sized array of the scalar in WGSL, e.g. `array<f32>`
+    const WGSL_ARRAY_TYPE: &'static str;
+}
+
+impl GpuScalar for f32 {
+    const WGSL_TYPE: &'static str = "f32";
+    const WGSL_ARRAY_TYPE: &'static str = "array<f32>";
+}
+
+impl GpuScalar for i32 {
+    const WGSL_TYPE: &'static str = "i32";
+    const WGSL_ARRAY_TYPE: &'static str = "array<i32>";
+}
+
+impl GpuScalar for u32 {
+    const WGSL_TYPE: &'static str = "u32";
+    const WGSL_ARRAY_TYPE: &'static str = "array<u32>";
+}
+
+/// A 2 dimensional array of [`GpuScalar`]s which can be used as a [`Variable`]
+///
+/// The data is stored in row major order (the standard layout of [`ndarray`]), so that the
+/// x dimension of the workgroups runs along the columns and the y dimension along the rows.
+/// The matrices of `f32` are the most common, and they have their own alias, [`GpuArray2`].
+#[derive(Debug, PartialEq)]
+pub struct GpuMatrix<T: GpuScalar> {
+    data: Vec<T>,
+    n_rows: usize,
+    n_cols: usize,
+    name: Option<String>,
+}
+
+/// A 2 dimensional array of `f32` which can be used as a [`Variable`], see [`GpuMatrix`]
+pub type GpuArray2 = GpuMatrix<f32>;
+
+impl<T: GpuScalar> GpuMatrix<T> {
+    /// Creates a new [`GpuMatrix`] copying the data of a 2 dimensional [`ndarray`] array
+    ///
+    /// Any kind of array can be given, owned arrays as well as views. If the array is not contiguous
+    /// in standard layout (like a slice with a step or a transposed array) its elements are copied in the
+    /// standard layout, so that the data on the GPU is always in row major order.
+    ///
+    /// # Errors
+    /// The method errors if the array has no elements, which wouldn't be possible to bind on the GPU
+    pub fn from_ndarray<S: Data<Elem = T>>(
+        array: ArrayBase<S, Ix2>,
+        name: Option<&str>,
+    ) -> Result<Self, anyhow::Error> {
+        if array.is_empty() {
+            return Err(anyhow!(
+                "Array {:?} has dimensions {:?}, but empty arrays can't be used on the GPU",
+                name,
+                array.dim()
+            ));
+        }
+        let (n_rows, n_cols) = array.dim();
+        let data = match array.as_slice() {
+            Some(slice) => slice.to_vec(),
+            None => array.as_standard_layout().iter().copied().collect(),
+        };
+        Ok(Self {
+            data,
+            n_rows,
+            n_cols,
+            name: name.map(str::to_owned),
+        })
+    }
+
+    /// Creates a new [`GpuMatrix`] from the rows given by an iterator, each of `n_cols` elements
+    ///
+    /// The data is assembled row by row in the buffer which goes to the GPU, so that big inputs (e.g. read line by line
+    /// from a file) don't need to be collected in an intermediate array first.
+    ///
+    /// # Example
+    /// ```
+    /// use wgpu_calc::translator::GpuArray2;
+    /// let rows = (0..3).map(|row| vec![row as f32; 2]);
+    /// let array = GpuArray2::from_rows(rows, 2, Some("array")).unwrap();
+    /// assert_eq!(array.dim(), (3, 2));
+    /// ```
+    ///
+    /// # Errors
+    /// The method errors if a row doesn't have `n_cols` elements, giving the index of the row, or if there are no elements
+    pub fn from_rows(
+        rows: impl Iterator<Item = Vec<T>>,
+        n_cols: usize,
+        name: Option<&str>,
+    ) -> Result<Self, anyhow::Error> {
+        let mut data = Vec::with_capacity(rows.size_hint().0 * n_cols);
+        let mut n_rows = 0;
+        for (index, row) in rows.enumerate() {
+            if row.len() != n_cols {
+                return Err(anyhow!(
+                    "Row {} of array {:?} has {} elements, but {} were expected",
+                    index,
+                    name,
+                    row.len(),
+                    n_cols
+                ));
+            }
+            data.extend_from_slice(&row);
+            n_rows += 1;
+        }
+        if data.is_empty() {
+            return Err(anyhow!(
+                "Array {:?} has dimensions {:?}, but empty arrays can't be used on the GPU",
+                name,
+                (n_rows, n_cols)
+            ));
+        }
+        Ok(Self {
+            data,
+            n_rows,
+            n_cols,
+            name: name.map(str::to_owned),
+        })
+    }
+
+    /// Gives the dimensions of the array as (rows, columns), the same as [`ArrayBase::dim`]
+    pub fn dim(&self) -> (usize, usize) {
+        (self.n_rows, self.n_cols)
+    }
+
+    /// Converts the array back to an [`ndarray`] array
+    ///
+    /// # Errors
+    /// The method errors if the data read back from the GPU doesn't match the dimensions of the array
+    pub fn to_ndarray(&self) -> Result<Array2<T>, anyhow::Error> {
+        Ok(Array2::from_shape_vec(
+            (self.n_rows, self.n_cols),
+            self.data.clone(),
+        )?)
+    }
+}
+
+impl<T: GpuScalar> Variable for GpuMatrix<T> {
+    fn byte_size(&self) -> u64 {
+        (std::mem::size_of::<T>() * self.n_rows * self.n_cols) as u64
+    }
+
+    fn byte_data(&self) -> &[u8] {
+        bytemuck::cast_slice(&self.data)
+    }
+
+    fn dimension_sizes(&self) -> [u32; 3] {
+        [self.n_cols as u32, self.n_rows as u32, 1]
+    }
+
+    fn get_name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    fn element_size(&self) -> Option<u64> {
+        Some(std::mem::size_of::<T>() as u64)
+    }
+
+    fn wgsl_type(&self) -> Option<&str> {
+        Some(T::WGSL_ARRAY_TYPE)
+    }
+
+    fn read_data(&mut self, slice: &[u8]) -> Result<(), anyhow::Error> {
+        self.data = read_elements(slice, self.data.len())?;
+        Ok(())
+    }
+}
+
+/// A 3 dimensional array of `f32` which can be used as a [`Variable`]
+///
+/// The data is stored in standard layout, so that for an array of dimensions `(d0, d1, d2)` the x dimension of the
+/// workgroups runs along the last axis, the y dimension along the second one and the z dimension along the first one.
+/// The element at `(i, j, k)` is then at the index `k + d2 * (j + d1 * i)` of the buffer.
+#[derive(Debug, PartialEq)]
+pub struct GpuArray3 {
+    data: Vec<f32>,
+    dim: (usize, usize, usize),
+    name: Option<String>,
+}
+
+impl GpuArray3 {
+    /// Creates a new [`GpuArray3`] copying the data of a 3 dimensional [`ndarray`] array
+    ///
+    /// As for [`GpuArray2::from_ndarray`], an array not in standard layout is copied in the standard layout.
+    ///
+    /// # Errors
+    /// The method errors if the array has no elements, which wouldn't be possible to bind on the GPU
+    pub fn from_ndarray<S: Data<Elem = f32>>(
+        array: ArrayBase<S, Ix3>,
+        name: Option<&str>,
+    ) -> Result<Self, anyhow::Error> {
+        if array.is_empty() {
+            return Err(anyhow!(
+                "Array {:?} has dimensions {:?}, but empty arrays can't be used on the GPU",
+                name,
+                array.dim()
+            ));
+        }
+        let data = match array.as_slice() {
+            Some(slice) => slice.to_vec(),
+            None => array.as_standard_layout().iter().copied().collect(),
+        };
+        Ok(Self {
+            data,
+            dim: array.dim(),
+            name: name.map(str::to_owned),
+        })
+    }
+
+    /// Gives the dimensions of the array, the same as [`ArrayBase::dim`]
+    pub fn dim(&self) -> (usize, usize, usize) {
+        self.dim
+    }
+
+    /// Converts the array back to an [`ndarray`] array, with its original dimensions
+    ///
+    /// # Errors
+    /// The method errors if the data read back from the GPU doesn't match the dimensions of the array
+    pub fn to_ndarray(&self) -> Result<Array3<f32>, anyhow::Error> {
+        Ok(Array3::from_shape_vec(self.dim, self.data.clone())?)
+    }
+}
+
+impl Variable for GpuArray3 {
+    fn byte_size(&self) -> u64 {
+        let (d0, d1, d2) = self.dim;
+        (std::mem::size_of::<f32>() * d0 * d1 * d2) as u64
+    }
+
+    fn byte_data(&self) -> &[u8] {
+        bytemuck::cast_slice(&self.data)
+    }
+
+    fn dimension_sizes(&self) -> [u32; 3] {
+        let (d0, d1, d2) = self.dim;
+        [d2 as u32, d1 as u32, d0 as u32]
+    }
+
+    fn get_name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    fn element_size(&self) -> Option<u64> {
+        Some(std::mem::size_of::<f32>() as u64)
+    }
+
+    fn wgsl_type(&self) -> Option<&str> {
+        Some("array<f32>")
+    }
+
+    fn read_data(&mut self, slice: &[u8]) -> Result<(), anyhow::Error> {
+        self.data = read_elements(slice, self.data.len())?;
+        Ok(())
+    }
+}
+
+/// An array of `f32` with any number of dimensions which can be used as a [`Variable`]
+///
+/// The data is stored in standard layout, and as for [`GpuArray3`] the x dimension of the workgroups runs along the last
+/// axis, the y dimension along the one before and so on. The workgroups have only 3 axes, so all the axes before the
+/// last 3 must have length 1: e.g. an array of shape `(1, 4, 3, 2)` is dispatched as a [`GpuArray3`] of shape `(4, 3, 2)`,
+/// while one of shape `(2, 4, 3, 2)` can't be used.
+#[derive(Debug, PartialEq)]
+pub struct GpuArrayD {
+    data: Vec<f32>,
+    shape: Vec<usize>,
+    name: Option<String>,
+}
+
+impl GpuArrayD {
+    /// Creates a new [`GpuArrayD`] copying the data of a [`ndarray`] array with dynamic dimensions
+    ///
+    /// As for [`GpuArray2::from_ndarray`], an array not in standard layout is copied in the standard layout.
+    ///
+    /// # Errors
+    /// - if the array has no elements, which wouldn't be possible to bind on the GPU
+    /// - if an axis before the last 3 has length other than 1, so the array can't be mapped on the workgroups
+    pub fn from_ndarray<S: Data<Elem = f32>>(
+        array: ArrayBase<S, IxDyn>,
+        name: Option<&str>,
+    ) -> Result<Self, anyhow::Error> {
+        if array.is_empty() {
+            return Err(anyhow!(
+                "Array {:?} has dimensions {:?}, but empty arrays can't be used on the GPU",
+                name,
+                array.shape()
+            ));
+        }
+        let outer_axes = array.ndim().saturating_sub(3);
+        if array.shape()[..outer_axes].iter().any(|&len| len != 1) {
+            return Err(VariableError::DimensionError(array.shape().to_vec()).into());
+        }
+        let data = match array.as_slice() {
+            Some(slice) => slice.to_vec(),
+            None => array.as_standard_layout().iter().copied().collect(),
+        };
+        Ok(Self {
+            data,
+            shape: array.shape().to_vec(),
+            name: name.map(str::to_owned),
+        })
+    }
+
+    /// Gives the shape of the array, the same as [`ArrayBase::shape`]
+    pub fn shape(&self) -> &[usize] {
+        &self.shape
+    }
+
+    /// Converts the array back to an [`ndarray`] array, with its original shape
+    ///
+    /// # Errors
+    /// The method errors if the data read back from the GPU doesn't match the shape of the array
+    pub fn to_ndarray(&self) -> Result<ArrayD<f32>, anyhow::Error> {
+        Ok(ArrayD::from_shape_vec(
+            self.shape.clone(),
+            self.data.clone(),
+        )?)
+    }
+}
+
+impl Variable for GpuArrayD {
+    fn byte_size(&self) -> u64 {
+        (std::mem::size_of::<f32>() * self.data.len()) as u64
+    }
+
+    fn byte_data(&self) -> &[u8] {
+        bytemuck::cast_slice(&self.data)
+    }
+
+    fn dimension_sizes(&self) -> [u32; 3] {
+        let mut sizes = [1; 3];
+        for (size, &len) in sizes.iter_mut().zip(self.shape.iter().rev()) {
+            *size = len as u32;
+        }
+        sizes
+    }
+
+    fn dimensions(&self) -> Vec<u32> {
+        let mut dimensions: Vec<u32> = self.shape.iter().rev().map(|&len| len as u32).collect();
+        dimensions.resize(dimensions.len().max(3), 1);
+        dimensions
+    }
+
+    fn get_name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    fn element_size(&self) -> Option<u64> {
+        Some(std::mem::size_of::<f32>() as u64)
+    }
+
+    fn wgsl_type(&self) -> Option<&str> {
+        Some("array<f32>")
+    }
+
+    fn read_data(&mut self, slice: &[u8]) -> Result<(), anyhow::Error> {
+        self.data = read_elements(slice, self.data.len())?;
+        Ok(())
+    }
+}
+
+/// A grayscale image of `f32` texels which can be used as a [`TextureVariable`]
+///
+/// The texels are stored in row major order, and on the GPU the image is a `r32float` texture.
+#[derive(Debug, PartialEq)]
+pub struct GpuImage {
+    data: Vec<f32>,
+    width: u32,
+    height: u32,
+    name: Option<String>,
+}
+
+impl GpuImage {
+    /// Creates a new [`GpuImage`] of `width` x `height` texels from the `data` in row major order
+    ///
+    /// # Errors
+    /// The method errors if the image is empty or the length of `data` is different from `width * height`
+    pub fn new(
+        data: Vec<f32>,
+        width: u32,
+        height: u32,
+        name: Option<&str>,
+    ) -> Result<Self, anyhow::Error> {
+        if width == 0 || height == 0 || data.len() != width as usize * height as usize {
+            return Err(anyhow!(
+                "Image {:?} can't be made of {} texels with size {}x{}",
+                name,
+                data.len(),
+                width,
+                height
+            ));
+        }
+        Ok(Self {
+            data,
+            width,
+            height,
+            name: name.map(str::to_owned),
+        })
+    }
+
+    /// Gives the texels of the image in row major order
+    pub fn data(&self) -> &[f32] {
+        &self.data
+    }
+}
+
+impl TextureVariable for GpuImage {
+    fn get_name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    fn texture_size(&self) -> [u32; 2] {
+        [self.width, self.height]
+    }
+
+    fn byte_data(&self) -> &[u8] {
+        bytemuck::cast_slice(&self.data)
+    }
+
+    fn read_data(&mut self, slice: &[u8]) -> Result<(), anyhow::Error> {
+        self.data = read_elements(slice, self.data.len())?;
+        Ok(())
+    }
+}
+
+// reads the elements of a variable back from the bytes of its buffer, which must hold exactly `len` of them
+fn read_elements<T: bytemuck::Pod>(slice: &[u8], len: usize) -> Result<Vec<T>, anyhow::Error> {
+    let expected = (len * std::mem::size_of::<T>()) as u64;
+    if slice.len() as u64 != expected {
+        return Err(VariableError::<u32>::SizeMismatch {
+            expected,
+            got: slice.len() as u64,
+        }
+        .into());
+    }
+    Ok(slice
+        .chunks_exact(std::mem::size_of::<T>())
+        .map(bytemuck::pod_read_unaligned)
+        .collect())
+}
@@ -1,5 +1,6 @@
 use crate::errors::VariableError;
 use core::fmt::Debug;
+use std::sync::OnceLock;
 use wgpu::BufferDescriptor;
 
 /// This trait is the entry point to make a Rust type GPU compatible
@@ -19,16 +20,16 @@ where
     ///
     /// It is useful to create the buffer, the bind group layouts and the ipelines which will be executed
     /// on the GPU
-    fn to_buffer_descriptor(&self) -> BufferDescriptor {
+    fn to_buffer_descriptor(&self) -> BufferDescriptor<'_> {
         let label = self.get_name();
-        return BufferDescriptor {
+        BufferDescriptor {
             label,
             mapped_at_creation: false,
             size: self.byte_size(),
             usage: wgpu::BufferUsages::STORAGE
                 | wgpu::BufferUsages::COPY_DST
                 | wgpu::BufferUsages::COPY_SRC,
-        };
+        }
     }
 
     /// Gets an optional name associated with the [`Variable`]
@@ -60,7 +61,12 @@ where
     /// the Variable
     /// The data is returned in the same way as it's written, so the same logic which is
     /// implemented on {`Variable::byte_data`} should be implemented here
-    fn read_data(&mut self, slice: &[u8]);
+    ///
+    /// # Errors
+    /// - if the `slice` can't be read into the [`Variable`], e.g. because it doesn't hold as many bytes as
+    ///   [`Variable::byte_size`] (see [`crate::errors::VariableError::SizeMismatch`]). The
+    ///   [`crate::algorithm::Algorithm`] gives the error back from the method reading the variable
+    fn read_data(&mut self, slice: &[u8]) -> Result<(), anyhow::Error>;
 
     /// This method is needed to better distribute the workload for the [`Variable`] calculation
     ///
@@ -69,6 +75,67 @@ where
     /// Each dimension will be associated with a workgroup id in the GPU allowing the parallel execution of the calculus
     fn dimension_sizes(&self) -> [u32; 3];
 
+    /// This method gives the size in bytes of a single element of the [`Variable`], if known
+    ///
+    /// It's an hint used by [`Variable::validate_self`] to check the consistency between
+    /// [`Variable::byte_size`] and [`Variable::dimension_sizes`].
+    /// By default it's `None`, which makes the check less strict.
+    fn element_size(&self) -> Option<u64> {
+        None
+    }
+
+    /// This method gives the WGSL type of the [`Variable`] as declared in the shader, if known
+    ///
+    /// It's an hint used by [`crate::algorithm::Function::new_checked`] to check the variable
+    /// against the type the shader expects (e.g. `array<f32>`).
+    /// By default it's `None`, which skips the type check.
+    fn wgsl_type(&self) -> Option<&str> {
+        None
+    }
+
+    /// This method gives the sizes of all the dimensions of the [`Variable`], for the ones which can have more than 3
+    ///
+    /// By default they're the [`Variable::dimension_sizes`]. A [`Variable`] with any number of dimensions (e.g. made from an
+    /// [`ndarray::ArrayD`], see [`crate::translator::GpuArrayD`]) gives all of them here, starting from the one of the x axis,
+    /// and its [`Variable::dimension_sizes`] are the first 3.
+    /// The workgroups have only 3 axes, so all the dimensions after the first 3 must be 1, which
+    /// [`Variable::validate_self`] checks.
+    fn dimensions(&self) -> Vec<u32> {
+        self.dimension_sizes().to_vec()
+    }
+
+    /// This method checks that [`Variable::byte_size`] is consistent with [`Variable::dimension_sizes`]
+    ///
+    /// If [`Variable::element_size`] is given the byte size must be exactly the product of the
+    /// dimensions by the element size, otherwise it must only be a multiple of the product of the dimensions.
+    /// An inconsistent [`Variable`] would make the dispatch cover a different extent than the buffer holds.
+    ///
+    /// # Errors
+    /// - if the [`Variable::dimensions`] after the first 3 aren't all 1, so they can't be mapped on the workgroups
+    /// - if the byte size doesn't match the dimensions of the variable
+    fn validate_self(&self) -> Result<(), anyhow::Error> {
+        let all_dimensions = self.dimensions();
+        if all_dimensions.iter().skip(3).any(|&dim| dim != 1) {
+            return Err(VariableError::DimensionError(all_dimensions).into());
+        }
+        let byte_size = self.byte_size();
+        let dimensions = self.dimension_sizes();
+        let elements: u64 = dimensions.iter().map(|&dim| dim as u64).product();
+
+        let consistent = match self.element_size() {
+            Some(size) => elements * size == byte_size,
+            None => elements != 0 && byte_size.is_multiple_of(elements),
+        };
+        if !consistent {
+            return Err(VariableError::<u32>::ByteSizeMismatch {
+                byte_size,
+                dimensions,
+            }
+            .into());
+        }
+        Ok(())
+    }
+
     /// This method defines the workgroup count for the object
     ///
     /// It takes the dimension of the object and counts how many groups are needed to calculate the
@@ -103,3 +170,241 @@ where
         Ok(workgroup)
     }
 }
+
+/// This macro implements the [`Variable`] trait for a struct holding its data in a flat field
+///
+/// Most [`Variable`]s are a `Vec` (or an array) of plain data, some dimensions and a name, and their implementation
+/// is always the same: the macro writes it given the names of the three fields.
+/// - `data` is a `Vec<T>` or `[T; N]` of a [`bytemuck::Pod`] type, sent to the GPU as it is
+/// - `dimensions` is a `[u32; 3]`, returned by [`Variable::dimension_sizes`]
+/// - `name` is an `Option<String>`, returned by [`Variable::get_name`]
+///
+/// The data read back from the GPU is copied over the one of the field, which keeps its length.
+/// The struct still needs to implement `PartialEq` and `Debug`, as required by the trait.
+///
+/// # Example
+/// ```
+/// use wgpu_calc::errors::VariableError;
+/// use wgpu_calc::impl_variable;
+/// use wgpu_calc::variable::Variable;
+///
+/// #[derive(Debug, PartialEq)]
+/// struct Particles {
+///     positions: Vec<[f32; 4]>,
+///     dims: [u32; 3],
+///     name: Option<String>,
+/// }
+/// impl_variable!(Particles { data: positions, dimensions: dims, name: name });
+///
+/// let mut particles = Particles {
+///     positions: vec![[0.; 4]; 2],
+///     dims: [2, 1, 1],
+///     name: Some("particles".to_owned()),
+/// };
+/// assert_eq!(particles.byte_size(), 32);
+/// assert_eq!(particles.dimension_sizes(), [2, 1, 1]);
+/// particles.read_data(bytemuck::cast_slice(&[1f32; 8])).unwrap();
+/// assert_eq!(particles.positions, vec![[1.; 4]; 2]);
+/// let error = particles.read_data(&[0; 4]).unwrap_err();
+/// assert!(matches!(
+///     error.downcast_ref::<VariableError<u32>>(),
+///     Some(VariableError::SizeMismatch { expected: 32, got: 4 })
+/// ));
+/// ```
+#[macro_export]
+macro_rules! impl_variable {
+    ($type:ty { data: $data:ident, dimensions: $dimensions:ident, name: $name:ident $(,)? }) => {
+        impl $crate::variable::Variable for $type {
+            fn get_name(&self) -> Option<&str> {
+                self.$name.as_deref()
+            }
+
+            fn byte_size(&self) -> u64 {
+                ::std::mem::size_of_val(&self.$data[..]) as u64
+            }
+
+            fn byte_data(&self) -> &[u8] {
+                $crate::bytemuck::cast_slice(&self.$data[..])
+            }
+
+            fn read_data(
+                &mut self,
+                slice: &[u8],
+            ) -> ::std::result::Result<(), $crate::anyhow::Error> {
+                let data: &mut [u8] = $crate::bytemuck::cast_slice_mut(&mut self.$data[..]);
+                if data.len() != slice.len() {
+                    return Err($crate::errors::VariableError::<u32>::SizeMismatch {
+                        expected: data.len() as u64,
+                        got: slice.len() as u64,
+                    }
+                    .into());
+                }
+                data.copy_from_slice(slice);
+                Ok(())
+            }
+
+            fn dimension_sizes(&self) -> [u32; 3] {
+                self.$dimensions
+            }
+        }
+    };
+}
+
+/// This trait is the entry point to make a Rust type usable on the GPU as a 2D texture
+///
+/// It's the counterpart of [`Variable`] for the data which is better laid out as an image than as a buffer,
+/// like the input and output of image kernels: the shader reads it with `textureLoad` and writes it
+/// with `textureStore`, at integer texel coordinates.
+/// A texture is bound to a [`crate::algorithm::Function`] with [`crate::algorithm::Function::with_texture`].
+pub trait TextureVariable
+where
+    Self: Debug + Send,
+{
+    /// This gets a texture descriptor from the [`TextureVariable`] itself
+    ///
+    /// The texture can be read in the shader as a `texture_2d`, written as a `texture_storage_2d`, and copied
+    /// both ways to the CPU.
+    fn to_texture_descriptor(&self) -> wgpu::TextureDescriptor<'_> {
+        let [width, height] = self.texture_size();
+        wgpu::TextureDescriptor {
+            label: self.get_name(),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: self.format(),
+            usage: wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::STORAGE_BINDING
+                | wgpu::TextureUsages::COPY_DST
+                | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        }
+    }
+
+    /// Gets an optional name associated with the [`TextureVariable`]
+    fn get_name(&self) -> Option<&str>;
+
+    /// This method gives the size of the texture in texels, as `[width, height]`
+    ///
+    /// The size is also used as the workgroup count of a [`crate::algorithm::Function`] which has no [`Variable`].
+    fn texture_size(&self) -> [u32; 2];
+
+    /// This method gives the format of the texels
+    ///
+    /// It must be a format which can be used as a storage texture, by default it's
+    /// [`wgpu::TextureFormat::R32Float`], a single `f32` channel, which is declared as `r32float` in the shader.
+    fn format(&self) -> wgpu::TextureFormat {
+        wgpu::TextureFormat::R32Float
+    }
+
+    /// This method is needed to pass the data to the GPU
+    ///
+    /// The data is given as the rows of texels one after the other, without any padding.
+    fn byte_data(&self) -> &[u8];
+
+    /// This is the opposite of [`TextureVariable::byte_data`] to get the data back, in the same layout
+    ///
+    /// # Errors
+    /// - if the `slice` can't be read into the [`TextureVariable`], e.g. because it doesn't hold as many bytes as
+    ///   the texture (see [`crate::errors::VariableError::SizeMismatch`])
+    fn read_data(&mut self, slice: &[u8]) -> Result<(), anyhow::Error>;
+}
+
+/// A [`Variable`] whose data is produced lazily by a closure
+///
+/// The closure is called only the first time the data is needed, i.e. when the buffer is written
+/// by [`crate::algorithm::Algorithm::upload_all`], and the produced bytes are cached from then on.
+/// This avoids materializing expensive inputs which could end up never being used.
+///
+/// Since the buffer needs to be allocated before the data exists, the byte size and the dimensions must be
+/// given upfront.
+pub struct LazyVariable {
+    producer: Box<dyn Fn() -> Vec<u8> + Send>,
+    data: OnceLock<Vec<u8>>,
+    byte_size: u64,
+    dimensions: [u32; 3],
+    name: Option<String>,
+}
+
+impl LazyVariable {
+    /// Creates a new [`LazyVariable`], which will get its data from `producer`
+    ///
+    /// # Arguments
+    /// * - `byte_size` - the size in bytes of the data which will be produced
+    /// * - `dimensions` - the dimensions of the variable, as in [`Variable::dimension_sizes`]
+    /// * - `name` - an optional name for debugging purposes
+    /// * - `producer` - the closure producing the data as a stream of bytes
+    pub fn new(
+        byte_size: u64,
+        dimensions: [u32; 3],
+        name: Option<&str>,
+        producer: impl Fn() -> Vec<u8> + Send + 'static,
+    ) -> Self {
+        LazyVariable {
+            producer: Box::new(producer),
+            data: OnceLock::new(),
+            byte_size,
+            dimensions,
+            name: name.map(str::to_string),
+        }
+    }
+
+    /// This method returns whether the data has already been produced (or read back from the GPU)
+    pub fn is_materialized(&self) -> bool {
+        self.data.get().is_some()
+    }
+}
+
+impl Debug for LazyVariable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LazyVariable")
+            .field("data", &self.data.get())
+            .field("byte_size", &self.byte_size)
+            .field("dimensions", &self.dimensions)
+            .field("name", &self.name)
+            .finish()
+    }
+}
+
+impl PartialEq for LazyVariable {
+    fn eq(&self, other: &Self) -> bool {
+        self.byte_size == other.byte_size
+            && self.dimensions == other.dimensions
+            && self.name == other.name
+            && self.data.get() == other.data.get()
+    }
+}
+
+impl Variable for LazyVariable {
+    fn get_name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    fn byte_size(&self) -> u64 {
+        self.byte_size
+    }
+
+    fn byte_data(&self) -> &[u8] {
+        self.data.get_or_init(|| (self.producer)())
+    }
+
+    fn read_data(&mut self, slice: &[u8]) -> Result<(), anyhow::Error> {
+        if slice.len() as u64 != self.byte_size {
+            return Err(VariableError::<u32>::SizeMismatch {
+                expected: self.byte_size,
+                got: slice.len() as u64,
+            }
+            .into());
+        }
+        self.data = OnceLock::from(slice.to_vec());
+        Ok(())
+    }
+
+    fn dimension_sizes(&self) -> [u32; 3] {
+        self.dimensions
+    }
+}
@@ -1,68 +1,19 @@
 extern crate wgpu_calc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-use bytemuck;
 use ndarray::{array, Array2};
-use wgpu_calc::algorithm::{Algorithm, Function, VariableBind};
+use wgpu_calc::algorithm::{
+    ops, Algorithm, BindingKind, BindingSchema, DeadlineReport, DispatchInfo, Function,
+    OperationInfo, PingPong, RunState, RunStats, TextureAccess, VariableBind, VariableView,
+    WORKGROUP_SIZE_PLACEHOLDER,
+};
 use wgpu_calc::coding::Shader;
-use wgpu_calc::variable::Variable;
-
-#[derive(Debug, PartialEq)]
-struct GpuArray2<'a> {
-    data: Vec<f32>,
-    n_rows: u64,
-    n_cols: u64,
-    name: &'a str,
-}
-
-impl<'a> GpuArray2<'a> {
-    fn new(array: Array2<f32>, name: &'a str) -> GpuArray2<'a> {
-        let (n_cols, n_rows) = array.dim();
-        let data = array.as_slice().unwrap().to_owned();
-        Self {
-            data,
-            n_rows: n_rows as u64,
-            n_cols: n_cols as u64,
-            name,
-        }
-    }
-
-    fn get_dims(&self) -> (usize, usize) {
-        (self.n_rows as usize, self.n_cols as usize)
-    }
-
-    fn to_array(&self) -> Array2<f32> {
-        return Array2::from_shape_vec(
-            (self.n_cols as usize, self.n_rows as usize),
-            self.data.clone(),
-        )
-        .unwrap();
-    }
-}
-
-impl Variable for GpuArray2<'_> {
-    fn byte_size(&self) -> u64 {
-        let base_size: u64 = std::mem::size_of::<f32>() as u64;
-        base_size * self.n_cols * self.n_rows
-    }
-
-    fn byte_data(&self) -> &[u8] {
-        bytemuck::cast_slice(&self.data)
-    }
-
-    fn dimension_sizes(&self) -> [u32; 3] {
-        [self.n_rows as u32, self.n_cols as u32, 1]
-    }
-
-    fn get_name(&self) -> Option<&str> {
-        Some(self.name)
-    }
-
-    fn read_data(&mut self, slice: &[u8]) {
-        let vec: Vec<f32> = bytemuck::cast_slice(slice).to_owned();
-        self.data = vec;
-    }
-}
+use wgpu_calc::errors::{LockError, VariableError};
+use wgpu_calc::interface::ExecutorOptions;
+use wgpu_calc::translator::{GpuArray1, GpuArray2, GpuArray3, GpuArrayD, GpuImage, GpuMatrix};
+use wgpu_calc::variable::{LazyVariable, TextureVariable, Variable};
 
 #[tokio::test]
 async fn add_1_test_new() {
@@ -70,7 +21,9 @@ async fn add_1_test_new() {
 
     let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
 
-    let var = Arc::new(Mutex::new(GpuArray2::new(array, "test array")));
+    let var = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(array, Some("test array")).unwrap(),
+    ));
 
     let shader = Shader::from_file_path("./tests/shaders/mat2calcs.wgsl").unwrap();
 
@@ -80,16 +33,15 @@ async fn add_1_test_new() {
 
     let function = Function::new(&shader, "add_1", bindings);
 
-    algorithm.add_fun(function);
+    algorithm.add_fun(function).unwrap();
 
-    // print!("{:?}", algorithm.get_operations())
     let output = Arc::clone(&var);
 
     algorithm.read_variable(&output).unwrap();
     algorithm.run().await.unwrap();
 
     let var_lock = var.lock().unwrap();
-    let result = var_lock.to_array();
+    let result = var_lock.to_ndarray().unwrap();
     print!("{:?}", result);
     let check = array![[1., 1., 1.], [2., 2., 2.], [3., 3., 3.]];
     assert_eq!(result, check)
@@ -100,8 +52,10 @@ async fn add_1_large_new() {
 
     let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
 
-    let var = Arc::new(Mutex::new(GpuArray2::new(array, "test array")));
-    let (nrows, ncols) = var.lock().unwrap().get_dims();
+    let var = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(array, Some("test array")).unwrap(),
+    ));
+    let (nrows, ncols) = var.lock().unwrap().dim();
 
     let mut shader = Shader::from_file_path("./tests/shaders/mat2calcs.pwgsl").unwrap();
     shader.replace("€ncol", ncols.to_string().as_str());
@@ -113,7 +67,7 @@ async fn add_1_large_new() {
 
     let function = Function::new(&shader, "add_1", bindings);
 
-    algorithm.add_fun(function);
+    algorithm.add_fun(function).unwrap();
 
     let output = Arc::clone(&var);
     algorithm.read_variable(&output).unwrap();
@@ -121,7 +75,7 @@ async fn add_1_large_new() {
     algorithm.run().await.unwrap();
 
     let var_lock = var.lock().unwrap();
-    let result = var_lock.to_array();
+    let result = var_lock.to_ndarray().unwrap();
     let check = Array2::ones((5000, 5000));
     assert_eq!(result, check)
 }
@@ -133,10 +87,14 @@ async fn add_1_two_buffers_new() {
 
     let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
 
-    let var_1 = Arc::new(Mutex::new(GpuArray2::new(array_1, "array_1")));
-    let var_2 = Arc::new(Mutex::new(GpuArray2::new(array_2, "array_1")));
+    let var_1 = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(array_1, Some("array_1")).unwrap(),
+    ));
+    let var_2 = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(array_2, Some("array_1")).unwrap(),
+    ));
 
-    let (nrows, ncols) = var_1.lock().unwrap().get_dims();
+    let (nrows, ncols) = var_1.lock().unwrap().dim();
 
     let mut shader = Shader::from_file_path("./tests/shaders/mat2calcs.pwgsl").unwrap();
     shader.replace("€ncol", ncols.to_string().as_str());
@@ -151,8 +109,8 @@ async fn add_1_two_buffers_new() {
     let function1 = Function::new(&shader, "add_1", bindings_1);
     let function2 = Function::new(&shader, "add_1", bindings_2);
 
-    algorithm.add_fun(function1);
-    algorithm.add_fun(function2);
+    algorithm.add_fun(function1).unwrap();
+    algorithm.add_fun(function2).unwrap();
 
     let output_1 = Arc::clone(&var_1);
     let output_2 = Arc::clone(&var_2);
@@ -165,8 +123,8 @@ async fn add_1_two_buffers_new() {
     let var_lock_1 = var_1.lock().unwrap();
     let var_lock_2 = var_2.lock().unwrap();
 
-    let result_1 = var_lock_1.to_array();
-    let result_2 = var_lock_2.to_array();
+    let result_1 = var_lock_1.to_ndarray().unwrap();
+    let result_2 = var_lock_2.to_ndarray().unwrap();
 
     let check = Array2::ones((500, 500));
     assert_eq!(result_1, check);
@@ -179,9 +137,11 @@ async fn add_1_two_binds_same_var_new() {
 
     let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
 
-    let var_1 = Arc::new(Mutex::new(GpuArray2::new(array_1, "array_1")));
+    let var_1 = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(array_1, Some("array_1")).unwrap(),
+    ));
 
-    let (nrows, ncols) = var_1.lock().unwrap().get_dims();
+    let (nrows, ncols) = var_1.lock().unwrap().dim();
 
     let mut shader = Shader::from_file_path("./tests/shaders/mat2calcs.pwgsl").unwrap();
     shader.replace("€ncol", ncols.to_string().as_str());
@@ -196,8 +156,8 @@ async fn add_1_two_binds_same_var_new() {
     let function1 = Function::new(&shader, "add_1", bindings_1);
     let function2 = Function::new(&shader, "add_1", bindings_2);
 
-    algorithm.add_fun(function1);
-    algorithm.add_fun(function2);
+    algorithm.add_fun(function1).unwrap();
+    algorithm.add_fun(function2).unwrap();
 
     let output_1 = Arc::clone(&var_1);
 
@@ -207,7 +167,7 @@ async fn add_1_two_binds_same_var_new() {
 
     let var_lock_1 = var_1.lock().unwrap();
 
-    let result_1 = var_lock_1.to_array();
+    let result_1 = var_lock_1.to_ndarray().unwrap();
 
     let check = Array2::ones((500, 500)) + 1.0;
     assert_eq!(result_1, check);
@@ -220,10 +180,14 @@ async fn add_matrices_new() {
 
     let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
 
-    let var_1 = Arc::new(Mutex::new(GpuArray2::new(array_1, "array_1")));
-    let var_2 = Arc::new(Mutex::new(GpuArray2::new(array_2, "array_1")));
+    let var_1 = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(array_1, Some("array_1")).unwrap(),
+    ));
+    let var_2 = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(array_2, Some("array_1")).unwrap(),
+    ));
 
-    let (nrows, ncols) = var_1.lock().unwrap().get_dims();
+    let (nrows, ncols) = var_1.lock().unwrap().dim();
 
     let mut shader = Shader::from_file_path("./tests/shaders/mat2calcs.pwgsl").unwrap();
     shader.replace("€ncol", ncols.to_string().as_str());
@@ -236,7 +200,7 @@ async fn add_matrices_new() {
 
     let function1 = Function::new(&shader, "add_matrices", bindings_1);
 
-    algorithm.add_fun(function1);
+    algorithm.add_fun(function1).unwrap();
 
     algorithm.read_variable(&var_1).unwrap();
     algorithm.read_variable(&var_2).unwrap();
@@ -246,11 +210,2920 @@ async fn add_matrices_new() {
     let var_lock_1 = var_1.lock().unwrap();
     let var_lock_2 = var_2.lock().unwrap();
 
-    let result_1 = var_lock_1.to_array();
-    let result_2 = var_lock_2.to_array();
+    let result_1 = var_lock_1.to_ndarray().unwrap();
+    let result_2 = var_lock_2.to_ndarray().unwrap();
 
     let check_2 = Array2::ones((500, 500));
     let check_1 = Array2::ones((500, 500)) + 1.0;
     assert_eq!(result_1, check_1);
     assert_eq!(result_2, check_2);
 }
+
+#[tokio::test]
+async fn function_outputs() {
+    let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
+
+    let output = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::ones((4, 4)), Some("output")).unwrap(),
+    ));
+    let input = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::ones((4, 4)), Some("input")).unwrap(),
+    ));
+
+    let mut shader = Shader::from_file_path("./tests/shaders/mat2calcs.pwgsl").unwrap();
+    shader.replace("€ncol", "4");
+    shader.replace("€nrow", "4");
+
+    let bindings = vec![
+        VariableBind::new(Arc::clone(&output), 0).as_output(),
+        VariableBind::new(Arc::clone(&input), 1),
+    ];
+    assert!(bindings[0].is_output() && !bindings[1].is_output());
+    let handle = algorithm
+        .add_fun(Function::new(&shader, "add_matrices", bindings))
+        .unwrap();
+    algorithm.read_outputs(handle).unwrap();
+    // only the output is read back
+    assert_eq!(
+        algorithm.get_operations().last(),
+        Some(&OperationInfo::ReadBuffer { variable: 0 })
+    );
+    algorithm.run().await.unwrap();
+    assert_eq!(
+        output.lock().unwrap().to_ndarray().unwrap(),
+        Array2::ones((4, 4)) + 1.0
+    );
+    // the function has been run, so it has no outputs left to read
+    assert!(algorithm.read_outputs(handle).is_err());
+
+    // a uniform can't be written by the shader
+    let bindings = vec![
+        VariableBind::new(Arc::clone(&output), 0),
+        VariableBind::new(Arc::clone(&input), 1)
+            .as_output()
+            .as_uniform(),
+    ];
+    let error = algorithm
+        .add_fun(Function::new(&shader, "add_matrices", bindings))
+        .unwrap_err();
+    assert!(error.to_string().contains("uniform"));
+}
+
+#[tokio::test]
+async fn accumulate_across_runs() {
+    let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
+
+    // the host data of the accumulator is never uploaded, so it doesn't matter
+    let accumulator = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::ones((3, 3)) * 5.0, Some("accumulator")).unwrap(),
+    ));
+    let input = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(
+            array![[0., 0., 0.], [1., 1., 1.], [2., 2., 2.]],
+            Some("input"),
+        )
+        .unwrap(),
+    ));
+
+    let shader = Shader::from_file_path("./tests/shaders/mat2calcs.wgsl").unwrap();
+
+    algorithm.accumulate_into(&accumulator).unwrap();
+
+    for _ in 0..3 {
+        let bindings = vec![
+            VariableBind::new(Arc::clone(&accumulator), 0),
+            VariableBind::new(Arc::clone(&input), 1),
+        ];
+        let function = Function::new(&shader, "add_matrices", bindings);
+        algorithm.add_fun(function).unwrap();
+        algorithm.run().await.unwrap();
+    }
+
+    algorithm.read_variable(&accumulator).unwrap();
+    algorithm.run().await.unwrap();
+
+    let check = array![[0., 0., 0.], [3., 3., 3.], [6., 6., 6.]];
+    assert_eq!(accumulator.lock().unwrap().to_ndarray().unwrap(), check);
+
+    algorithm.reset_accumulator(&accumulator).unwrap();
+    algorithm.read_variable(&accumulator).unwrap();
+    algorithm.run().await.unwrap();
+
+    assert_eq!(
+        accumulator.lock().unwrap().to_ndarray().unwrap(),
+        Array2::zeros((3, 3))
+    );
+    assert!(algorithm.reset_accumulator(&input).is_err());
+
+    // marked after being added, the host data not yet uploaded is dropped as well
+    let late = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::ones((3, 3)) * 5.0, Some("late")).unwrap(),
+    ));
+    let bindings = vec![
+        VariableBind::new(Arc::clone(&late), 0),
+        VariableBind::new(Arc::clone(&input), 1),
+    ];
+    algorithm
+        .add_fun(Function::new(&shader, "add_matrices", bindings))
+        .unwrap();
+    algorithm.accumulate_into(&late).unwrap();
+    algorithm.read_variable(&late).unwrap();
+    algorithm.run().await.unwrap();
+    assert_eq!(
+        late.lock().unwrap().to_ndarray().unwrap(),
+        input.lock().unwrap().to_ndarray().unwrap()
+    );
+}
+
+#[tokio::test]
+async fn run_stats() {
+    let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
+    assert_eq!(algorithm.last_run_stats(), &RunStats::default());
+
+    let var_1 = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::zeros((3, 3)), Some("array_1")).unwrap(),
+    ));
+    let var_2 = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::ones((3, 3)), Some("array_2")).unwrap(),
+    ));
+
+    let shader = Shader::from_file_path("./tests/shaders/mat2calcs.wgsl").unwrap();
+
+    let function_1 = Function::new(
+        &shader,
+        "add_1",
+        vec![VariableBind::new(Arc::clone(&var_1), 0)],
+    );
+    let function_2 = Function::new(
+        &shader,
+        "add_1",
+        vec![VariableBind::new(Arc::clone(&var_1), 0)],
+    );
+    let function_3 = Function::new(
+        &shader,
+        "add_matrices",
+        vec![
+            VariableBind::new(Arc::clone(&var_1), 0),
+            VariableBind::new(Arc::clone(&var_2), 1),
+        ],
+    );
+
+    algorithm.add_fun(function_1).unwrap();
+    algorithm.add_fun(function_2).unwrap();
+    algorithm.add_fun(function_3).unwrap();
+    algorithm.read_variable(&var_1).unwrap();
+    algorithm.run().await.unwrap();
+
+    let check = RunStats {
+        dispatches: 3,
+        workgroups: 27,
+        bytes_uploaded: 72,
+        bytes_read: 36,
+        pipelines: 2,
+        submissions: 3,
+    };
+    assert_eq!(algorithm.last_run_stats(), &check);
+
+    algorithm.run().await.unwrap();
+    assert_eq!(algorithm.last_run_stats(), &RunStats::default());
+}
+
+#[tokio::test]
+async fn runtime_labels() {
+    // the labels are copied, so they don't need to outlive the algorithms
+    let algorithm: Algorithm<GpuArray2> =
+        Algorithm::new(Some(&format!("run {}", 0))).await.unwrap();
+    let shader = Shader::from_file_path("./tests/shaders/mat2calcs.wgsl").unwrap();
+    for i in 1..3 {
+        let mut stage: Algorithm<GpuArray2> =
+            algorithm.with_shared_executor(Some(&format!("run {}", i)));
+        let var = Arc::new(Mutex::new(
+            GpuArray2::from_ndarray(Array2::zeros((3, 3)), Some("var")).unwrap(),
+        ));
+        let function = Function::new(
+            &shader,
+            "add_1",
+            vec![VariableBind::new(Arc::clone(&var), 0)],
+        );
+        stage.add_fun(function).unwrap();
+        stage.read_variable(&var).unwrap();
+        stage.run().await.unwrap();
+        assert_eq!(
+            var.lock().unwrap().to_ndarray().unwrap(),
+            Array2::ones((3, 3))
+        );
+    }
+}
+
+#[tokio::test]
+async fn repeat_function() {
+    let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
+    let var = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::zeros((3, 3)), Some("var")).unwrap(),
+    ));
+    let shader = Shader::from_file_path("./tests/shaders/mat2calcs.wgsl").unwrap();
+    let function = Function::new(
+        &shader,
+        "add_1",
+        vec![VariableBind::new(Arc::clone(&var), 0)],
+    );
+    algorithm.repeat(function, 10).unwrap();
+    algorithm.read_variable(&var).unwrap();
+    algorithm.run().await.unwrap();
+
+    assert_eq!(
+        var.lock().unwrap().to_ndarray().unwrap(),
+        Array2::ones((3, 3)) * 10.
+    );
+    assert_eq!(algorithm.last_run_stats().dispatches, 10);
+    assert_eq!(algorithm.last_run_stats().submissions, 1);
+    assert_eq!(algorithm.last_run_stats().pipelines, 1);
+
+    let function = Function::new(
+        &shader,
+        "add_1",
+        vec![VariableBind::new(Arc::clone(&var), 0)],
+    );
+    assert!(algorithm.repeat(function, 0).is_err());
+}
+
+#[tokio::test]
+async fn peek_intermediate_result() {
+    let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
+    let var = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::zeros((3, 3)), Some("var")).unwrap(),
+    ));
+    let shader = Shader::from_file_path("./tests/shaders/mat2calcs.wgsl").unwrap();
+    let add_1 = || {
+        Function::new(
+            &shader,
+            "add_1",
+            vec![VariableBind::new(Arc::clone(&var), 0)],
+        )
+    };
+    let first = algorithm.add_fun(add_1()).unwrap();
+    algorithm.add_fun(add_1()).unwrap();
+
+    algorithm.run_selected(&[first]).await.unwrap();
+    algorithm.read_variable(&var).unwrap();
+    // the second function is run before peeking, while the read is left to the next run
+    let peeked: Vec<f32> = bytemuck::cast_slice(&algorithm.peek(&var).await.unwrap()).to_vec();
+    assert_eq!(peeked, vec![2.; 9]);
+    // the variable isn't overwritten
+    assert_eq!(
+        var.lock().unwrap().to_ndarray().unwrap(),
+        Array2::zeros((3, 3))
+    );
+
+    // the read kept by the peek comes before the functions added afterwards
+    algorithm.add_fun(add_1()).unwrap();
+    algorithm.run().await.unwrap();
+    assert_eq!(
+        var.lock().unwrap().to_ndarray().unwrap(),
+        Array2::ones((3, 3)) * 2.
+    );
+}
+
+#[tokio::test]
+async fn fuse_serial_functions() {
+    let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
+    algorithm.set_fuse_serial(true);
+
+    let var = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::zeros((3, 3)), Some("var")).unwrap(),
+    ));
+    let shader = Shader::from_file_path("./tests/shaders/mat2calcs.wgsl").unwrap();
+    for _ in 0..10 {
+        let function = Function::new(
+            &shader,
+            "add_1",
+            vec![VariableBind::new(Arc::clone(&var), 0)],
+        );
+        algorithm.add_fun(function).unwrap();
+    }
+    algorithm.read_variable(&var).unwrap();
+    for _ in 0..5 {
+        let function = Function::new(
+            &shader,
+            "add_1",
+            vec![VariableBind::new(Arc::clone(&var), 0)],
+        );
+        algorithm.add_fun(function).unwrap();
+    }
+    algorithm.run().await.unwrap();
+
+    // one submission before the read back, and one for the functions after it
+    assert_eq!(algorithm.last_run_stats().submissions, 2);
+    assert_eq!(algorithm.last_run_stats().dispatches, 15);
+    assert_eq!(
+        var.lock().unwrap().to_ndarray().unwrap(),
+        Array2::ones((3, 3)) * 10.
+    );
+    algorithm.get_output_unmap(&var).await.unwrap();
+    assert_eq!(
+        var.lock().unwrap().to_ndarray().unwrap(),
+        Array2::ones((3, 3)) * 15.
+    );
+}
+
+#[tokio::test]
+async fn bind_variable_view() {
+    let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
+
+    // two rows of 64 f32, so that the second row starts at an aligned offset
+    let arena = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::zeros((2, 64)), Some("arena")).unwrap(),
+    ));
+
+    let shader = Shader::from_content(
+        "
+        @group(0) @binding(0)
+        var<storage,read_write>  a: array<f32>;
+
+        @compute @workgroup_size(1)
+        fn add_1 (@builtin(global_invocation_id) id: vec3<u32>) {
+            if (id.y == 0u) {
+                a[id.x] = a[id.x] + 1.0;
+            }
+        }",
+    );
+
+    let view = VariableView::new(Arc::clone(&arena), 256, 256).unwrap();
+    let function = Function::new(&shader, "add_1", vec![VariableBind::from_view(view, 0)]);
+    algorithm.add_fun(function).unwrap();
+
+    algorithm.read_variable(&arena).unwrap();
+    algorithm.run().await.unwrap();
+
+    let mut check = Array2::zeros((2, 64));
+    check.row_mut(1).fill(1.0);
+    assert_eq!(arena.lock().unwrap().to_ndarray().unwrap(), check);
+
+    assert!(VariableView::new(Arc::clone(&arena), 4, 256).is_err());
+    assert!(VariableView::new(Arc::clone(&arena), 256, 6).is_err());
+    assert!(VariableView::new(Arc::clone(&arena), 256, 512).is_err());
+}
+
+// a variable whose byte size says 100 floats while its dimensions say 10x20
+#[derive(Debug, PartialEq)]
+struct InconsistentVariable {
+    data: Vec<f32>,
+}
+
+impl Variable for InconsistentVariable {
+    fn byte_size(&self) -> u64 {
+        (std::mem::size_of::<f32>() * self.data.len()) as u64
+    }
+
+    fn byte_data(&self) -> &[u8] {
+        bytemuck::cast_slice(&self.data)
+    }
+
+    fn dimension_sizes(&self) -> [u32; 3] {
+        [10, 20, 1]
+    }
+
+    fn get_name(&self) -> Option<&str> {
+        Some("inconsistent")
+    }
+
+    fn element_size(&self) -> Option<u64> {
+        Some(std::mem::size_of::<f32>() as u64)
+    }
+
+    fn read_data(&mut self, slice: &[u8]) -> Result<(), anyhow::Error> {
+        self.data = bytemuck::cast_slice(slice).to_owned();
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn inconsistent_variable_size() {
+    let var = Arc::new(Mutex::new(InconsistentVariable {
+        data: vec![0.; 100],
+    }));
+    assert!(var.lock().unwrap().validate_self().is_err());
+
+    let consistent = GpuArray2::from_ndarray(Array2::zeros((10, 20)), Some("consistent")).unwrap();
+    assert!(consistent.validate_self().is_ok());
+
+    let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
+    let shader = Shader::from_file_path("./tests/shaders/mat2calcs.wgsl").unwrap();
+    let function = Function::new(&shader, "add_1", vec![VariableBind::new(var, 0)]);
+    assert!(algorithm.add_fun(function).is_err());
+}
+
+#[tokio::test]
+async fn get_outputs_unmap_per_variable() {
+    let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
+
+    let var_1 = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::zeros((3, 3)), Some("array_1")).unwrap(),
+    ));
+    let var_2 = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::zeros((3, 3)), Some("array_2")).unwrap(),
+    ));
+    let missing = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::zeros((3, 3)), Some("missing")).unwrap(),
+    ));
+
+    let shader = Shader::from_file_path("./tests/shaders/mat2calcs.wgsl").unwrap();
+    let function = Function::new(
+        &shader,
+        "add_matrices",
+        vec![
+            VariableBind::new(Arc::clone(&var_1), 0),
+            VariableBind::new(Arc::clone(&var_2), 1),
+        ],
+    );
+    algorithm.add_fun(function).unwrap();
+    algorithm.run().await.unwrap();
+
+    // the variable doesn't match its buffer size anymore
+    *var_2.lock().unwrap() =
+        GpuArray2::from_ndarray(Array2::zeros((2, 3)), Some("array_2")).unwrap();
+
+    let results = algorithm
+        .get_outputs_unmap(&[&var_2, &missing, &var_1])
+        .await;
+    assert!(results[0].is_err());
+    assert!(results[1].is_err());
+    assert!(results[2].is_ok());
+    assert_eq!(
+        var_1.lock().unwrap().to_ndarray().unwrap(),
+        Array2::zeros((3, 3))
+    );
+}
+
+#[tokio::test]
+async fn batched_read_variables() {
+    let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
+    let values: Vec<Array2<f32>> = (0..3)
+        .map(|k| Array2::from_shape_fn((4, 5), |(i, j)| (k * 20 + i * 5 + j) as f32))
+        .collect();
+    let vars: Vec<_> = values
+        .iter()
+        .map(|value| {
+            Arc::new(Mutex::new(
+                GpuArray2::from_ndarray(value.view(), None).unwrap(),
+            ))
+        })
+        .collect();
+    let missing = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::zeros((4, 5)), Some("missing")).unwrap(),
+    ));
+
+    for var in &vars {
+        ops::scale(&mut algorithm, var, 3.).unwrap();
+    }
+    algorithm.run().await.unwrap();
+
+    assert!(algorithm
+        .read_variables(&[&vars[0], &missing])
+        .await
+        .is_err());
+    // nothing is read if a variable is missing
+    assert_eq!(vars[0].lock().unwrap().to_ndarray().unwrap(), values[0]);
+
+    algorithm
+        .read_variables(&[&vars[2], &vars[0], &vars[1]])
+        .await
+        .unwrap();
+    for (var, value) in vars.iter().zip(&values) {
+        assert_eq!(var.lock().unwrap().to_ndarray().unwrap(), value * 3.);
+    }
+}
+
+#[tokio::test]
+async fn lazy_variable_upload() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&calls);
+    let lazy = LazyVariable::new(36, [3, 3, 1], Some("lazy"), move || {
+        counter.fetch_add(1, Ordering::SeqCst);
+        bytemuck::cast_slice(&[2.0f32; 9]).to_vec()
+    });
+    let var = Arc::new(Mutex::new(lazy));
+
+    let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
+    let shader = Shader::from_file_path("./tests/shaders/mat2calcs.wgsl").unwrap();
+    let function = Function::new(
+        &shader,
+        "add_1",
+        vec![VariableBind::new(Arc::clone(&var), 0)],
+    );
+
+    assert!(!var.lock().unwrap().is_materialized());
+    assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+    algorithm.add_fun(function).unwrap();
+    assert_eq!(calls.load(Ordering::SeqCst), 0);
+    algorithm.upload_all().unwrap();
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    algorithm.read_variable(&var).unwrap();
+    algorithm.run().await.unwrap();
+
+    assert_eq!(
+        bytemuck::cast_slice::<u8, f32>(var.lock().unwrap().byte_data()),
+        &[3.0; 9]
+    );
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+    // the bytes read back must be as many as the byte size
+    assert!(var.lock().unwrap().read_data(&[0; 32]).is_err());
+
+    // a producer giving fewer bytes than the byte size fails the upload
+    let short = Arc::new(Mutex::new(LazyVariable::new(36, [3, 3, 1], None, || {
+        vec![0; 32]
+    })));
+    let function = Function::new(
+        &shader,
+        "add_1",
+        vec![VariableBind::new(Arc::clone(&short), 0)],
+    );
+    algorithm.add_fun(function).unwrap();
+    let error = algorithm.run().await.unwrap_err().to_string();
+    assert!(error.contains("36"), "{}", error);
+}
+
+#[tokio::test]
+async fn same_shader_any_size() {
+    let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
+
+    let shader = Shader::from_content(
+        "
+        @group(0) @binding(0)
+        var<storage,read_write>  a: array<f32>;
+        @group(0) @binding(1)
+        var<uniform>  dims: vec4<u32>;
+
+        @compute @workgroup_size(1)
+        fn add_1 (@builtin(global_invocation_id) id: vec3<u32>) {
+            if (id.x < dims.x && id.y < dims.y) {
+                let index = id.x + id.y * dims.x;
+                a[index] = a[index] + 1.0;
+            }
+        }",
+    );
+
+    let shapes = [(3, 3), (4, 5), (7, 2)];
+    let vars: Vec<_> = shapes
+        .iter()
+        .map(|&shape| {
+            Arc::new(Mutex::new(
+                GpuArray2::from_ndarray(Array2::zeros(shape), Some("array")).unwrap(),
+            ))
+        })
+        .collect();
+
+    for var in &vars {
+        let function = Function::new(
+            &shader,
+            "add_1",
+            vec![VariableBind::new(Arc::clone(var), 0)],
+        )
+        .with_dimensions(1);
+        algorithm.add_fun(function).unwrap();
+        algorithm.read_variable(var).unwrap();
+    }
+    algorithm.run().await.unwrap();
+
+    for (var, &shape) in vars.iter().zip(&shapes) {
+        assert_eq!(
+            var.lock().unwrap().to_ndarray().unwrap(),
+            Array2::ones(shape)
+        );
+    }
+}
+
+#[test]
+fn array_from_rows() {
+    let rows = vec![vec![0., 1., 2.], vec![3., 4., 5.]];
+    let array = GpuArray2::from_rows(rows.into_iter(), 3, Some("array")).unwrap();
+    assert_eq!(
+        array.to_ndarray().unwrap(),
+        array![[0., 1., 2.], [3., 4., 5.]]
+    );
+
+    let rows = vec![vec![0., 1., 2.], vec![3., 4., 5.], vec![6., 7.]];
+    let error = GpuArray2::from_rows(rows.into_iter(), 3, Some("array")).unwrap_err();
+    assert!(error.to_string().contains("Row 2"), "{}", error);
+    assert!(GpuArray2::from_rows(std::iter::empty(), 3, None).is_err());
+}
+
+#[cfg(feature = "blocking")]
+#[test]
+fn blocking_wrappers() {
+    let mut algorithm = Algorithm::new_blocking(Some("Test algorithm")).unwrap();
+    let var = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::zeros((3, 3)), Some("var")).unwrap(),
+    ));
+    let shader = Shader::from_file_path("./tests/shaders/mat2calcs.wgsl").unwrap();
+    let function = Function::new(
+        &shader,
+        "add_1",
+        vec![VariableBind::new(Arc::clone(&var), 0)],
+    );
+    algorithm.add_fun(function).unwrap();
+    algorithm.run_blocking().unwrap();
+    algorithm.read_variable_blocking(&var).unwrap();
+    assert_eq!(
+        var.lock().unwrap().to_ndarray().unwrap(),
+        Array2::ones((3, 3))
+    );
+}
+
+#[tokio::test]
+async fn non_contiguous_array() {
+    let array = array![[0., 1., 2.], [3., 4., 5.], [6., 7., 8.], [9., 10., 11.]];
+    let transposed = array.t();
+    assert!(transposed.as_slice().is_none());
+    let stepped = array.slice(ndarray::s![..;2, ..]);
+    assert!(stepped.as_slice().is_none());
+
+    let var_t = GpuArray2::from_ndarray(transposed, Some("transposed")).unwrap();
+    assert_eq!(var_t.dim(), (3, 4));
+    assert_eq!(var_t.to_ndarray().unwrap(), transposed);
+    let var_s = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(stepped, Some("stepped")).unwrap(),
+    ));
+    assert!(GpuArray2::from_ndarray(Array2::<f32>::zeros((0, 3)), None).is_err());
+
+    let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
+    let shader = Shader::from_content(
+        "
+        @group(0) @binding(0)
+        var<storage,read_write>  a: array<f32>;
+        @group(0) @binding(1)
+        var<uniform>  dims: vec4<u32>;
+
+        @compute @workgroup_size(1)
+        fn add_1 (@builtin(global_invocation_id) id: vec3<u32>) {
+            if (id.x < dims.x && id.y < dims.y) {
+                let index = id.x + id.y * dims.x;
+                a[index] = a[index] + 1.0;
+            }
+        }",
+    );
+    let function = Function::new(
+        &shader,
+        "add_1",
+        vec![VariableBind::new(Arc::clone(&var_s), 0)],
+    )
+    .with_dimensions(1);
+    algorithm.add_fun(function).unwrap();
+    algorithm.read_variable(&var_s).unwrap();
+    algorithm.run().await.unwrap();
+
+    let check = array![[1., 2., 3.], [7., 8., 9.]];
+    assert_eq!(var_s.lock().unwrap().to_ndarray().unwrap(), check);
+}
+
+#[tokio::test]
+async fn function_schema_check() {
+    let var_1 = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::zeros((3, 3)), Some("array_1")).unwrap(),
+    ));
+    let var_2 = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::ones((3, 3)), Some("array_2")).unwrap(),
+    ));
+    let shader = Shader::from_file_path("./tests/shaders/mat2calcs.wgsl").unwrap();
+    let storage = |binding, wgsl_type: &str| BindingSchema {
+        group: 0,
+        binding,
+        kind: BindingKind::Storage { read_only: false },
+        wgsl_type: wgsl_type.to_owned(),
+    };
+    let binds = || {
+        vec![
+            VariableBind::new(Arc::clone(&var_1), 0),
+            VariableBind::new(Arc::clone(&var_2), 1),
+        ]
+    };
+
+    let schema = [storage(0, "array<f32>"), storage(1, "array< f32 >")];
+    let function = Function::new_checked(&shader, "add_matrices", &schema, binds()).unwrap();
+
+    let too_short = [storage(0, "array<f32>")];
+    assert!(Function::new_checked(&shader, "add_matrices", &too_short, binds()).is_err());
+    let swapped = [storage(1, "array<f32>"), storage(0, "array<f32>")];
+    assert!(Function::new_checked(&shader, "add_matrices", &swapped, binds()).is_err());
+    let wrong_type = [storage(0, "array<f32>"), storage(1, "array<u32>")];
+    assert!(Function::new_checked(&shader, "add_matrices", &wrong_type, binds()).is_err());
+    let mut uniform = schema.clone();
+    uniform[1].kind = BindingKind::Uniform;
+    assert!(Function::new_checked(&shader, "add_matrices", &uniform, binds()).is_err());
+    // a uniform binding matches only a variable bound as uniform
+    let uniform_binds = || {
+        vec![
+            VariableBind::new(Arc::clone(&var_1), 0),
+            VariableBind::new(Arc::clone(&var_2), 1).as_uniform(),
+        ]
+    };
+    assert!(Function::new_checked(&shader, "add_matrices", &uniform, uniform_binds()).is_ok());
+    let error = Function::new_checked(&shader, "add_matrices", &schema, uniform_binds())
+        .err()
+        .unwrap();
+    assert!(error.to_string().contains("bound as Uniform"));
+    let mut other_group = schema.clone();
+    other_group[1].group = 1;
+    assert!(Function::new_checked(&shader, "add_matrices", &other_group, binds()).is_err());
+
+    let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
+    algorithm.add_fun(function).unwrap();
+    algorithm.read_variable(&var_1).unwrap();
+    algorithm.run().await.unwrap();
+    assert_eq!(
+        var_1.lock().unwrap().to_ndarray().unwrap(),
+        Array2::ones((3, 3))
+    );
+}
+
+#[tokio::test]
+async fn labelled_bind_group() {
+    let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
+    // logging the binding decisions doesn't change the operations
+    algorithm.set_verbose(true);
+    let var = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::zeros((3, 3)), Some("array")).unwrap(),
+    ));
+    let shader = Shader::from_file_path("./tests/shaders/mat2calcs.wgsl").unwrap();
+    let function = Function::new(
+        &shader,
+        "add_1",
+        vec![VariableBind::new(Arc::clone(&var), 0)],
+    )
+    .with_group_label(0, "unused")
+    .with_group_label(0, "matrices");
+    algorithm.add_fun(function).unwrap();
+    algorithm.read_variable(&var).unwrap();
+    algorithm.run().await.unwrap();
+    assert_eq!(
+        var.lock().unwrap().to_ndarray().unwrap(),
+        Array2::ones((3, 3))
+    );
+}
+
+#[tokio::test]
+async fn copy_to_external_buffer() {
+    let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
+    let var = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::zeros((3, 3)), Some("array")).unwrap(),
+    ));
+    let missing = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::zeros((3, 3)), Some("missing")).unwrap(),
+    ));
+    let shader = Shader::from_file_path("./tests/shaders/mat2calcs.wgsl").unwrap();
+    let function = Function::new(
+        &shader,
+        "add_1",
+        vec![VariableBind::new(Arc::clone(&var), 0)],
+    );
+    algorithm.add_fun(function).unwrap();
+
+    let external = |size, usage| {
+        algorithm.executor().get_buffer(&wgpu::BufferDescriptor {
+            label: Some("external"),
+            size,
+            usage,
+            mapped_at_creation: false,
+        })
+    };
+    let usage = wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC;
+    let destination = external(64, usage);
+    let too_small = external(16, usage);
+    let not_copyable = external(64, wgpu::BufferUsages::COPY_SRC);
+
+    assert!(algorithm
+        .copy_variable_to_external(&missing, &destination)
+        .is_err());
+    assert!(algorithm
+        .copy_variable_to_external(&var, &too_small)
+        .is_err());
+    assert!(algorithm
+        .copy_variable_to_external(&var, &not_copyable)
+        .is_err());
+    algorithm
+        .copy_variable_to_external(&var, &destination)
+        .unwrap();
+    algorithm.run().await.unwrap();
+
+    let bytes = algorithm.executor().read_buffer(&destination).await;
+    let values: &[f32] = bytemuck::cast_slice(&bytes);
+    assert_eq!(&values[..9], &[1.; 9]);
+}
+
+#[tokio::test]
+async fn autotune_workgroup_size() {
+    let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
+    let var = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::zeros((64, 48)), Some("array")).unwrap(),
+    ));
+    let template = Shader::from_content(
+        "
+        @group(0) @binding(0)
+        var<storage,read_write>  a: array<f32>;
+        @group(0) @binding(1)
+        var<uniform>  dims: vec4<u32>;
+
+        @compute @workgroup_size(€workgroup_size)
+        fn add_1 (@builtin(global_invocation_id) id: vec3<u32>) {
+            if (id.x < dims.x && id.y < dims.y) {
+                let index = id.x + id.y * dims.x;
+                a[index] = a[index] + 1.0;
+            }
+        }",
+    );
+    let function = Function::new(
+        &template,
+        "add_1",
+        vec![VariableBind::new(Arc::clone(&var), 0)],
+    )
+    .with_dimensions(1);
+
+    assert!(algorithm.autotune(&function, &[]).is_err());
+    assert!(algorithm.autotune(&function, &[[0, 1, 1]]).is_err());
+
+    let candidates = [[1, 1, 1], [8, 8, 1], [16, 4, 1]];
+    let best = algorithm.autotune(&function, &candidates).unwrap();
+    assert!(candidates.contains(&best));
+    // the tuning happens only once for the same template
+    assert_eq!(algorithm.autotune(&function, &[[2, 2, 1]]).unwrap(), best);
+    // the variable is left untouched
+    assert_eq!(
+        var.lock().unwrap().to_ndarray().unwrap(),
+        Array2::zeros((64, 48))
+    );
+
+    let mut shader = template.clone();
+    shader.replace(
+        WORKGROUP_SIZE_PLACEHOLDER,
+        &format!("{}, {}, {}", best[0], best[1], best[2]),
+    );
+    let function = Function::new(
+        &shader,
+        "add_1",
+        vec![VariableBind::new(Arc::clone(&var), 0)],
+    )
+    .with_dimensions(1);
+    algorithm.add_fun(function).unwrap();
+    algorithm.read_variable(&var).unwrap();
+    algorithm.run().await.unwrap();
+    assert_eq!(
+        var.lock().unwrap().to_ndarray().unwrap(),
+        Array2::ones((64, 48))
+    );
+}
+
+#[tokio::test]
+async fn constant_mapped_at_creation() {
+    let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
+    let var = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::zeros((3, 3)), Some("array")).unwrap(),
+    ));
+    let table = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(
+            array![[1., 2., 3.], [4., 5., 6.], [7., 8., 9.]],
+            Some("table"),
+        )
+        .unwrap(),
+    ));
+    algorithm.add_constant_mapped(&table).unwrap();
+    assert!(algorithm.add_constant_mapped(&table).is_err());
+
+    let shader = Shader::from_file_path("./tests/shaders/mat2calcs.wgsl").unwrap();
+    for _ in 0..2 {
+        let function = Function::new(
+            &shader,
+            "add_matrices",
+            vec![
+                VariableBind::new(Arc::clone(&var), 0),
+                VariableBind::new(Arc::clone(&table), 1),
+            ],
+        );
+        algorithm.add_fun(function).unwrap();
+    }
+    algorithm.read_variable(&var).unwrap();
+    algorithm.run().await.unwrap();
+
+    // the table is uploaded only once, at its creation
+    assert_eq!(algorithm.last_run_stats().bytes_uploaded, 2 * 36);
+    assert_eq!(
+        var.lock().unwrap().to_ndarray().unwrap(),
+        array![[2., 4., 6.], [8., 10., 12.], [14., 16., 18.]]
+    );
+}
+
+#[tokio::test]
+async fn constant_mapped_is_read_only() {
+    let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
+    let var = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::zeros((3, 3)), Some("array")).unwrap(),
+    ));
+    let table = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::ones((3, 3)), Some("table")).unwrap(),
+    ));
+    algorithm.add_constant_mapped(&table).unwrap();
+
+    // the buffer of a constant has no COPY_DST usage, nothing can be written to it
+    assert!(algorithm.mark_dirty(&table).is_err());
+    assert!(algorithm.accumulate_into(&table).is_err());
+    assert!(algorithm.reset_accumulator(&table).is_err());
+
+    let shader = Shader::from_file_path("./tests/shaders/mat2calcs.wgsl").unwrap();
+    let mut stage = algorithm.with_shared_executor(Some("Test stage"));
+    stage
+        .add_fun(Function::new(
+            &shader,
+            "add_matrices",
+            vec![
+                VariableBind::new(Arc::clone(&var), 0),
+                VariableBind::new(Arc::clone(&table), 1),
+            ],
+        ))
+        .unwrap();
+    assert!(algorithm.append(stage).is_err());
+
+    algorithm
+        .add_fun(Function::new(
+            &shader,
+            "add_matrices",
+            vec![
+                VariableBind::new(Arc::clone(&var), 0),
+                VariableBind::new(Arc::clone(&table), 1),
+            ],
+        ))
+        .unwrap();
+    let mut compiled = algorithm.compile().unwrap();
+    assert!(compiled.mark_dirty(&table).is_err());
+    compiled.step().unwrap();
+    compiled.get_output(&var).await.unwrap();
+    assert_eq!(
+        var.lock().unwrap().to_ndarray().unwrap(),
+        Array2::ones((3, 3))
+    );
+}
+
+#[tokio::test]
+async fn operations_description() {
+    let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
+    let var_1 = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::zeros((3, 3)), Some("array_1")).unwrap(),
+    ));
+    let var_2 = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::ones((3, 3)), Some("array_2")).unwrap(),
+    ));
+    let shader = Shader::from_file_path("./tests/shaders/mat2calcs.wgsl").unwrap();
+
+    let function = Function::new(
+        &shader,
+        "add_1",
+        vec![VariableBind::new(Arc::clone(&var_2), 0)],
+    );
+    algorithm.add_fun(function).unwrap();
+    let function = Function::new(
+        &shader,
+        "add_matrices",
+        vec![
+            VariableBind::new(Arc::clone(&var_1), 0),
+            VariableBind::new(Arc::clone(&var_2), 1),
+        ],
+    );
+    algorithm.add_fun(function).unwrap();
+    algorithm.read_variable(&var_1).unwrap();
+
+    let expected = vec![
+        OperationInfo::Serial {
+            variables: vec![0],
+            dispatches: vec![DispatchInfo {
+                entry_point: "add_1".to_owned(),
+                bindings: vec![(0, 0)],
+                workgroups: [3, 3, 1],
+            }],
+        },
+        OperationInfo::Serial {
+            variables: vec![1, 0],
+            dispatches: vec![DispatchInfo {
+                entry_point: "add_matrices".to_owned(),
+                bindings: vec![(0, 1), (1, 0)],
+                workgroups: [3, 3, 1],
+            }],
+        },
+        OperationInfo::ReadBuffer { variable: 1 },
+    ];
+    assert_eq!(algorithm.get_operations(), expected);
+    assert_eq!(
+        algorithm.describe_operations(),
+        "0: serial on array_2 (0)\n    add_1 with workgroups [3, 3, 1], bindings 0: array_2 (0)\n\
+        1: serial on array_1 (1), array_2 (0)\n    \
+        add_matrices with workgroups [3, 3, 1], bindings 0: array_1 (1), 1: array_2 (0)\n\
+        2: read back array_1 (1)\n"
+    );
+
+    algorithm.run().await.unwrap();
+    assert!(algorithm.get_operations().is_empty());
+    assert!(algorithm.describe_operations().is_empty());
+}
+
+#[tokio::test]
+async fn remove_functions() {
+    let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
+    let var_1 = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::zeros((3, 3)), Some("array_1")).unwrap(),
+    ));
+    let var_2 = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::ones((3, 3)), Some("array_2")).unwrap(),
+    ));
+    let shader = Shader::from_file_path("./tests/shaders/mat2calcs.wgsl").unwrap();
+
+    let first = algorithm
+        .add_fun(Function::new(
+            &shader,
+            "add_1",
+            vec![VariableBind::new(Arc::clone(&var_1), 0)],
+        ))
+        .unwrap();
+    algorithm.read_variable(&var_1).unwrap();
+    let function = Function::new(
+        &shader,
+        "add_matrices",
+        vec![
+            VariableBind::new(Arc::clone(&var_1), 0),
+            VariableBind::new(Arc::clone(&var_2), 1),
+        ],
+    );
+    let second = algorithm.add_fun(function).unwrap();
+    assert_eq!(algorithm.pop_last_function().unwrap(), second);
+    assert_eq!(algorithm.get_operations().len(), 2);
+
+    // array_2 was only used by the removed function, so its index is free again
+    let var_3 = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::zeros((3, 3)), Some("array_3")).unwrap(),
+    ));
+    algorithm
+        .add_fun(Function::new(
+            &shader,
+            "add_1",
+            vec![VariableBind::new(Arc::clone(&var_3), 0)],
+        ))
+        .unwrap();
+    match &algorithm.get_operations()[2] {
+        OperationInfo::Serial { variables, .. } => assert_eq!(variables, &vec![1]),
+        operation => panic!("unexpected operation {:?}", operation),
+    }
+    algorithm.pop_last_function().unwrap();
+
+    algorithm.run().await.unwrap();
+    assert_eq!(
+        var_1.lock().unwrap().to_ndarray().unwrap(),
+        Array2::ones((3, 3))
+    );
+    assert!(algorithm.pop_last_function().is_err());
+    assert!(algorithm.run_selected(&[first]).await.is_err());
+
+    algorithm
+        .add_fun(Function::new(
+            &shader,
+            "add_1",
+            vec![VariableBind::new(Arc::clone(&var_1), 0)],
+        ))
+        .unwrap();
+    algorithm.read_variable(&var_1).unwrap();
+    algorithm.clear();
+    assert!(algorithm.get_operations().is_empty());
+    assert!(algorithm.read_variable(&var_1).is_err());
+    assert!(algorithm.rerun().await.is_err());
+}
+
+#[tokio::test]
+async fn poisoned_variable_lock() {
+    let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
+    let var = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::zeros((3, 3)), Some("poisoned")).unwrap(),
+    ));
+    let shader = Shader::from_file_path("./tests/shaders/mat2calcs.wgsl").unwrap();
+    algorithm
+        .add_fun(Function::new(
+            &shader,
+            "add_1",
+            vec![VariableBind::new(Arc::clone(&var), 0)],
+        ))
+        .unwrap();
+    algorithm.read_variable(&var).unwrap();
+
+    let poisoner = Arc::clone(&var);
+    std::thread::spawn(move || {
+        let _lock = poisoner.lock().unwrap();
+        panic!("a kernel failed while holding the lock");
+    })
+    .join()
+    .unwrap_err();
+    assert!(var.is_poisoned());
+
+    let error = algorithm.run().await.unwrap_err();
+    assert!(error.to_string().contains("poisoned"), "{}", error);
+    assert!(matches!(
+        error.downcast_ref::<LockError>(),
+        Some(LockError::Variable(Some(name))) if name == "poisoned"
+    ));
+    let error = algorithm
+        .add_fun(Function::new(
+            &shader,
+            "add_1",
+            vec![VariableBind::new(Arc::clone(&var), 0)],
+        ))
+        .unwrap_err();
+    assert!(error.to_string().contains("\"poisoned\""), "{}", error);
+}
+
+#[tokio::test]
+async fn failed_add_fun_stores_nothing() {
+    let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
+    let var = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::zeros((3, 3)), Some("orphan")).unwrap(),
+    ));
+    let input = Arc::new(Mutex::new(
+        GpuImage::new(vec![0.; 9], 3, 3, Some("input")).unwrap(),
+    ));
+    let output = Arc::new(Mutex::new(
+        GpuImage::new(vec![0.; 9], 3, 3, Some("output")).unwrap(),
+    ));
+    let poisoner = Arc::clone(&output);
+    std::thread::spawn(move || {
+        let _lock = poisoner.lock().unwrap();
+        panic!("a kernel failed while holding the lock");
+    })
+    .join()
+    .unwrap_err();
+
+    let shader = Shader::from_file_path("./tests/shaders/blur.wgsl").unwrap();
+    let function = Function::new(
+        &shader,
+        "blur",
+        vec![VariableBind::new(Arc::clone(&var), 2)],
+    )
+    .with_texture(Arc::clone(&input), 0, TextureAccess::Read)
+    .with_texture(Arc::clone(&output), 1, TextureAccess::Write);
+    let error = algorithm.add_fun(function).unwrap_err();
+    assert!(error.to_string().contains("poisoned"), "{}", error);
+
+    // neither the variable nor the first texture were left behind to be uploaded
+    assert!(algorithm.read_variable(&var).is_err());
+    algorithm.run().await.unwrap();
+    assert_eq!(algorithm.last_run_stats().bytes_uploaded, 0);
+}
+
+#[tokio::test]
+async fn failed_upload_keeps_functions() {
+    let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
+    let var = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::zeros((3, 3)), Some("retried")).unwrap(),
+    ));
+    let shader = Shader::from_file_path("./tests/shaders/mat2calcs.wgsl").unwrap();
+    algorithm
+        .add_fun(Function::new(
+            &shader,
+            "add_1",
+            vec![VariableBind::new(Arc::clone(&var), 0)],
+        ))
+        .unwrap();
+    algorithm.read_variable(&var).unwrap();
+
+    let poisoner = Arc::clone(&var);
+    std::thread::spawn(move || {
+        let _lock = poisoner.lock().unwrap();
+        panic!("a kernel failed while holding the lock");
+    })
+    .join()
+    .unwrap_err();
+    algorithm.run().await.unwrap_err();
+
+    var.clear_poison();
+    algorithm.run().await.unwrap();
+    assert_eq!(
+        var.lock().unwrap().to_ndarray().unwrap(),
+        Array2::<f32>::ones((3, 3))
+    );
+}
+
+#[tokio::test]
+async fn invert_matrix() {
+    let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
+
+    // the first pivot is zero, so the rows must be swapped
+    let matrix = array![[0., 2., 1.], [1., 1., 0.], [3., 0., 1.]];
+    let mut a = GpuArray2::from_ndarray(matrix.clone(), Some("a")).unwrap();
+    algorithm.invert(&mut a).await.unwrap();
+    let product = matrix.dot(&a.to_ndarray().unwrap());
+    for (value, expected) in product.iter().zip(Array2::<f32>::eye(3).iter()) {
+        assert!((value - expected).abs() < 1e-5, "{:?}", product);
+    }
+
+    let singular = array![[1., 2.], [2., 4.]];
+    let mut b = GpuArray2::from_ndarray(singular.clone(), Some("b")).unwrap();
+    assert!(algorithm.invert(&mut b).await.is_err());
+    assert_eq!(b.to_ndarray().unwrap(), singular);
+
+    let mut c = GpuArray2::from_ndarray(Array2::zeros((2, 3)), Some("c")).unwrap();
+    assert!(algorithm.invert(&mut c).await.is_err());
+}
+
+#[tokio::test]
+async fn elementwise_operations() {
+    let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
+    let a_values = Array2::from_shape_fn((30, 7), |(i, j)| (i * 7 + j) as f32);
+    let b_values = Array2::from_elem((30, 7), 2.);
+    let a = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(a_values.view(), Some("a")).unwrap(),
+    ));
+    let b = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(b_values.view(), Some("b")).unwrap(),
+    ));
+    let sum = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::zeros((30, 7)), Some("sum")).unwrap(),
+    ));
+    let quotient = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::zeros((30, 7)), Some("quotient")).unwrap(),
+    ));
+
+    ops::add(&mut algorithm, &a, &b, &sum).unwrap();
+    // in place on the sum
+    ops::mul_elementwise(&mut algorithm, &sum, &b, &sum).unwrap();
+    ops::sub(&mut algorithm, &sum, &a, &sum).unwrap();
+    ops::div(&mut algorithm, &a, &b, &quotient).unwrap();
+    algorithm.read_variable(&sum).unwrap();
+    algorithm.read_variable(&quotient).unwrap();
+    algorithm.run().await.unwrap();
+    assert_eq!(
+        sum.lock().unwrap().to_ndarray().unwrap(),
+        (&a_values + &b_values) * &b_values - &a_values
+    );
+    assert_eq!(
+        quotient.lock().unwrap().to_ndarray().unwrap(),
+        &a_values / &b_values
+    );
+
+    let transposed = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::zeros((7, 30)), Some("transposed")).unwrap(),
+    ));
+    let error = ops::add(&mut algorithm, &a, &transposed, &sum).unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<VariableError<u32>>(),
+        Some(VariableError::ShapeMismatch {
+            operation: "add",
+            ..
+        })
+    ));
+}
+
+#[tokio::test]
+async fn scalar_operations() {
+    let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
+    let values = Array2::from_shape_fn((9, 12), |(i, j)| (i * 12 + j) as f32);
+    let var = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(values.view(), Some("var")).unwrap(),
+    ));
+
+    ops::scale(&mut algorithm, &var, 0.5).unwrap();
+    ops::offset(&mut algorithm, &var, -3.).unwrap();
+    algorithm.read_variable(&var).unwrap();
+    algorithm.run().await.unwrap();
+    assert_eq!(var.lock().unwrap().to_ndarray().unwrap(), values * 0.5 - 3.);
+
+    // the scalar needs no variable of the same type, so any variable of f32 can be scaled
+    let mut algorithm = algorithm.with_shared_executor(Some("Vector algorithm"));
+    let vector = Arc::new(Mutex::new(
+        GpuArray1::from_ndarray(ndarray::Array1::ones(100).view(), Some("vector")).unwrap(),
+    ));
+    ops::scale(&mut algorithm, &vector, 4.).unwrap();
+    algorithm.read_variable(&vector).unwrap();
+    algorithm.run().await.unwrap();
+    assert_eq!(
+        vector.lock().unwrap().to_array1(),
+        ndarray::Array1::from_elem(100, 4.)
+    );
+}
+
+#[tokio::test]
+async fn parallel_uploads() {
+    let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
+    // 4 arrays of 512 KiB each, enough to be written from several threads
+    let values: Vec<Array2<f32>> = (0..4)
+        .map(|k| Array2::from_shape_fn((256, 512), |(i, j)| (k * 7 + i + j) as f32))
+        .collect();
+    let vars: Vec<_> = values
+        .iter()
+        .map(|value| {
+            Arc::new(Mutex::new(
+                GpuArray2::from_ndarray(value.view(), None).unwrap(),
+            ))
+        })
+        .collect();
+
+    for var in &vars {
+        ops::scale(&mut algorithm, var, 2.).unwrap();
+        algorithm.read_variable(var).unwrap();
+    }
+    algorithm.run().await.unwrap();
+    for (var, value) in vars.iter().zip(&values) {
+        assert_eq!(var.lock().unwrap().to_ndarray().unwrap(), value * 2.);
+    }
+    // the scalars are written with their functions, only the arrays are uploaded
+    assert_eq!(algorithm.last_run_stats().bytes_uploaded, 4 * 256 * 512 * 4);
+}
+
+#[tokio::test]
+async fn matrix_multiplication() {
+    let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
+    // the sizes aren't multiples of the tiles
+    let a_values = Array2::from_shape_fn((13, 10), |(i, j)| (i as f32 - j as f32) / 4.);
+    let b_values = Array2::from_shape_fn((10, 9), |(i, j)| ((i * j) % 5) as f32);
+    let a = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(a_values.view(), Some("a")).unwrap(),
+    ));
+    let b = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(b_values.view(), Some("b")).unwrap(),
+    ));
+    let out = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::zeros((13, 9)), Some("out")).unwrap(),
+    ));
+
+    ops::matmul(&mut algorithm, &a, &b, &out).unwrap();
+    algorithm.read_variable(&out).unwrap();
+    algorithm.run().await.unwrap();
+    let product = a_values.dot(&b_values);
+    let result = out.lock().unwrap().to_ndarray().unwrap();
+    for (value, expected) in result.iter().zip(product.iter()) {
+        assert!((value - expected).abs() < 1e-4, "{:?}", result);
+    }
+
+    let error = ops::matmul(&mut algorithm, &b, &a, &out).unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<VariableError<u32>>(),
+        Some(VariableError::ShapeMismatch {
+            operation: "matmul",
+            ..
+        })
+    ));
+    assert!(ops::matmul(&mut algorithm, &a, &b, &a).is_err());
+    let wrong_out = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::zeros((9, 13)), Some("wrong out")).unwrap(),
+    ));
+    assert!(ops::matmul(&mut algorithm, &a, &b, &wrong_out).is_err());
+}
+
+#[tokio::test]
+async fn transpose_matrix() {
+    let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
+    let values = Array2::from_shape_fn((11, 19), |(i, j)| (i * 19 + j) as f32);
+    let input = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(values.view(), Some("input")).unwrap(),
+    ));
+    let output = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::zeros((19, 11)), Some("output")).unwrap(),
+    ));
+
+    ops::transpose(&mut algorithm, &input, &output).unwrap();
+    algorithm.read_variable(&output).unwrap();
+    algorithm.run().await.unwrap();
+    assert_eq!(output.lock().unwrap().to_ndarray().unwrap(), values.t());
+
+    assert!(ops::transpose(&mut algorithm, &input, &input).is_err());
+    assert!(ops::transpose(&mut algorithm, &output, &output).is_err());
+    let square = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::zeros((11, 11)), Some("square")).unwrap(),
+    ));
+    assert!(ops::transpose(&mut algorithm, &input, &square).is_err());
+}
+
+#[tokio::test]
+async fn tiled_dispatch() {
+    let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
+    let var = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::zeros((7, 10)), Some("array")).unwrap(),
+    ));
+    let shader = Shader::from_content(
+        "
+        struct Dimensions {
+            size: vec4<u32>,
+            offset: vec4<u32>,
+        }
+        @group(0) @binding(0)
+        var<storage,read_write>  a: array<f32>;
+        @group(0) @binding(1)
+        var<uniform>  dims: Dimensions;
+
+        @compute @workgroup_size(1)
+        fn add_1 (@builtin(global_invocation_id) id: vec3<u32>) {
+            let x = id.x + dims.offset.x;
+            let y = id.y + dims.offset.y;
+            if (x < dims.size.x && y < dims.size.y) {
+                let index = x + y * dims.size.x;
+                a[index] = a[index] + 1.0;
+            }
+        }",
+    );
+    let binds = || vec![VariableBind::new(Arc::clone(&var), 0)];
+
+    assert!(algorithm
+        .add_function_tiled(Function::new(&shader, "add_1", binds()), [4, 4, 1])
+        .is_err());
+    let function = Function::new(&shader, "add_1", binds()).with_dimensions(1);
+    assert!(algorithm.add_function_tiled(function, [0, 4, 1]).is_err());
+
+    let function = Function::new(&shader, "add_1", binds()).with_dimensions(1);
+    algorithm.add_function_tiled(function, [4, 3, 1]).unwrap();
+    match &algorithm.get_operations()[0] {
+        OperationInfo::Serial { dispatches, .. } => {
+            let workgroups: Vec<[u32; 3]> = dispatches.iter().map(|d| d.workgroups).collect();
+            assert_eq!(
+                workgroups,
+                vec![
+                    [4, 3, 1],
+                    [4, 3, 1],
+                    [2, 3, 1],
+                    [4, 3, 1],
+                    [4, 3, 1],
+                    [2, 3, 1],
+                    [4, 1, 1],
+                    [4, 1, 1],
+                    [2, 1, 1]
+                ]
+            );
+        }
+        operation => panic!("unexpected operation {:?}", operation),
+    }
+
+    // only the bottom right corner, starting at column 5 and row 4
+    let function = Function::new(&shader, "add_1", binds())
+        .with_dimensions(1)
+        .with_dispatch_offset([5, 4, 0]);
+    algorithm.add_fun(function).unwrap();
+    algorithm.read_variable(&var).unwrap();
+    algorithm.run().await.unwrap();
+
+    let mut check = Array2::ones((7, 10));
+    check.slice_mut(ndarray::s![4.., 5..]).fill(2.);
+    assert_eq!(var.lock().unwrap().to_ndarray().unwrap(), check);
+}
+
+#[tokio::test]
+async fn read_result_rows() {
+    let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
+    let var = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(
+            array![[0., 0., 0.], [1., 1., 1.], [2., 2., 2.]],
+            Some("array"),
+        )
+        .unwrap(),
+    ));
+    let missing = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::zeros((3, 3)), Some("missing")).unwrap(),
+    ));
+    let shader = Shader::from_file_path("./tests/shaders/mat2calcs.wgsl").unwrap();
+    let function = Function::new(
+        &shader,
+        "add_1",
+        vec![VariableBind::new(Arc::clone(&var), 0)],
+    );
+    algorithm.add_fun(function).unwrap();
+    algorithm.run().await.unwrap();
+
+    assert!(algorithm.read_rows(&missing).await.is_err());
+    let mapped = algorithm.read_rows(&var).await.unwrap();
+    let view = mapped.view();
+    let rows: Vec<&[f32]> = view.rows().collect();
+    assert_eq!(rows, vec![&[1., 1., 1.], &[2., 2., 2.], &[3., 3., 3.]]);
+    // the variable itself is not read back
+    assert_eq!(
+        var.lock().unwrap().to_ndarray().unwrap(),
+        array![[0., 0., 0.], [1., 1., 1.], [2., 2., 2.]]
+    );
+}
+
+#[tokio::test]
+async fn run_selected_functions() {
+    let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
+    let var = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::zeros((3, 3)), Some("array")).unwrap(),
+    ));
+    let shader = Shader::from_file_path("./tests/shaders/mat2calcs.wgsl").unwrap();
+    let mut handles = Vec::new();
+    for _ in 0..3 {
+        let function = Function::new(
+            &shader,
+            "add_1",
+            vec![VariableBind::new(Arc::clone(&var), 0)],
+        );
+        handles.push(algorithm.add_fun(function).unwrap());
+    }
+    algorithm.read_variable(&var).unwrap();
+
+    algorithm
+        .run_selected(&[handles[0], handles[2]])
+        .await
+        .unwrap();
+    assert_eq!(
+        var.lock().unwrap().to_ndarray().unwrap(),
+        Array2::ones((3, 3)) * 2.
+    );
+    assert_eq!(algorithm.get_operations().len(), 1);
+    assert!(algorithm.run_selected(&[handles[0]]).await.is_err());
+
+    algorithm.read_variable(&var).unwrap();
+    algorithm.run_selected(&[handles[1]]).await.unwrap();
+    assert_eq!(
+        var.lock().unwrap().to_ndarray().unwrap(),
+        Array2::ones((3, 3)) * 3.
+    );
+    assert!(algorithm.get_operations().is_empty());
+}
+
+#[test]
+fn workgroup_error_message() {
+    let var = GpuArray2::from_ndarray(Array2::zeros((1, 70000)), Some("wide")).unwrap();
+    let error = var.get_workgroup().unwrap_err().to_string();
+    assert!(error.contains("dimension 0"), "{}", error);
+}
+
+#[tokio::test]
+async fn blur_image_texture() {
+    // wide enough for the rows to need padding when copied back
+    let (width, height) = (70, 3);
+    let pixels: Vec<f32> = (0..width * height).map(|i| (i % 7) as f32).collect();
+    let input = Arc::new(Mutex::new(
+        GpuImage::new(pixels.clone(), width, height, Some("input")).unwrap(),
+    ));
+    let output = Arc::new(Mutex::new(
+        GpuImage::new(vec![0.; pixels.len()], width, height, Some("output")).unwrap(),
+    ));
+    assert!(GpuImage::new(vec![0.; 5], 2, 2, None).is_err());
+    // the bytes read back must fill the whole image
+    assert!(output.lock().unwrap().read_data(&[0; 6]).is_err());
+
+    let mut algorithm: Algorithm<GpuArray2> = Algorithm::new(Some("Test algorithm")).await.unwrap();
+    let shader = Shader::from_file_path("./tests/shaders/blur.wgsl").unwrap();
+    let function = Function::new(&shader, "blur", Vec::new())
+        .with_texture(Arc::clone(&input), 0, TextureAccess::Read)
+        .with_texture(Arc::clone(&output), 1, TextureAccess::Write);
+    algorithm.add_fun(function).unwrap();
+    algorithm.run().await.unwrap();
+    algorithm.read_texture(&output).await.unwrap();
+
+    let mut expected = Vec::new();
+    for y in 0..height as i32 {
+        for x in 0..width as i32 {
+            let mut sum = 0.;
+            let mut count = 0.;
+            for (dx, dy) in (-1..=1).flat_map(|dy| (-1..=1).map(move |dx| (dx, dy))) {
+                let (nx, ny) = (x + dx, y + dy);
+                if (0..width as i32).contains(&nx) && (0..height as i32).contains(&ny) {
+                    sum += pixels[(ny * width as i32 + nx) as usize];
+                    count += 1.;
+                }
+            }
+            expected.push(sum / count);
+        }
+    }
+    let output = output.lock().unwrap();
+    for (got, expected) in output.data().iter().zip(&expected) {
+        assert!((got - expected).abs() < 1e-5, "{} != {}", got, expected);
+    }
+    assert_eq!(output.data().len(), expected.len());
+}
+
+#[tokio::test]
+async fn append_algorithms() {
+    let a = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::zeros((3, 3)), Some("a")).unwrap(),
+    ));
+    let b = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::from_elem((3, 3), 2.), Some("b")).unwrap(),
+    ));
+    let shader = Shader::from_file_path("./tests/shaders/mat2calcs.wgsl").unwrap();
+
+    let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
+    algorithm
+        .add_fun(Function::new(
+            &shader,
+            "add_1",
+            vec![VariableBind::new(Arc::clone(&a), 0)],
+        ))
+        .unwrap();
+
+    // the sub algorithm uses the result of the first stage, a = a + b, and adds 1 to b
+    let mut stage = algorithm.with_shared_executor(Some("Test stage"));
+    stage
+        .add_fun(Function::new(
+            &shader,
+            "add_matrices",
+            vec![
+                VariableBind::new(Arc::clone(&a), 0),
+                VariableBind::new(Arc::clone(&b), 1),
+            ],
+        ))
+        .unwrap();
+    let add_b = stage
+        .add_fun(Function::new(
+            &shader,
+            "add_1",
+            vec![VariableBind::new(Arc::clone(&b), 0)],
+        ))
+        .unwrap();
+
+    algorithm.append(stage).unwrap();
+    // only a copy in and a copy out are added for the shared variable, all the functions are kept
+    assert_eq!(algorithm.get_operations().len(), 5);
+    algorithm.run_selected(&[add_b]).await.unwrap();
+    algorithm.run().await.unwrap();
+    algorithm.get_output_unmap(&a).await.unwrap();
+    algorithm.get_output_unmap(&b).await.unwrap();
+
+    // add_b ran first, so `a` got the updated `b`
+    assert_eq!(
+        a.lock().unwrap().to_ndarray().unwrap(),
+        Array2::from_elem((3, 3), 4.)
+    );
+    assert_eq!(
+        b.lock().unwrap().to_ndarray().unwrap(),
+        Array2::from_elem((3, 3), 3.)
+    );
+}
+
+#[tokio::test]
+async fn append_shared_texture() {
+    let (width, height) = (4, 4);
+    let input = Arc::new(Mutex::new(
+        GpuImage::new(
+            vec![1.; width * height],
+            width as u32,
+            height as u32,
+            Some("input"),
+        )
+        .unwrap(),
+    ));
+    let output = Arc::new(Mutex::new(
+        GpuImage::new(
+            vec![0.; width * height],
+            width as u32,
+            height as u32,
+            Some("output"),
+        )
+        .unwrap(),
+    ));
+    let var = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::zeros((3, 3)), Some("var")).unwrap(),
+    ));
+    let blur = Shader::from_file_path("./tests/shaders/blur.wgsl").unwrap();
+    let shader = Shader::from_file_path("./tests/shaders/mat2calcs.wgsl").unwrap();
+    let blur_function = || {
+        Function::new(&blur, "blur", Vec::new())
+            .with_texture(Arc::clone(&input), 0, TextureAccess::Read)
+            .with_texture(Arc::clone(&output), 1, TextureAccess::Write)
+    };
+
+    let mut algorithm: Algorithm<GpuArray2> = Algorithm::new(Some("Test algorithm")).await.unwrap();
+    algorithm.add_fun(blur_function()).unwrap();
+    let mut stage = algorithm.with_shared_executor(Some("Test stage"));
+    stage.add_fun(blur_function()).unwrap();
+    stage
+        .add_fun(Function::new(
+            &shader,
+            "add_1",
+            vec![VariableBind::new(Arc::clone(&var), 0)],
+        ))
+        .unwrap();
+
+    algorithm.append(stage).unwrap();
+    // only the function using the shared textures gets the copies
+    assert_eq!(algorithm.get_operations().len(), 5);
+    algorithm.read_variable(&var).unwrap();
+    algorithm.run().await.unwrap();
+    algorithm.read_texture(&output).await.unwrap();
+    assert_eq!(output.lock().unwrap().data(), vec![1.; width * height]);
+    assert_eq!(
+        var.lock().unwrap().to_ndarray().unwrap(),
+        Array2::ones((3, 3))
+    );
+}
+
+#[tokio::test]
+async fn dispatch_from_workgroup_size() {
+    let shader = Shader::from_content(
+        "
+        @group(0) @binding(0)
+        var<storage,read_write>  a: array<f32>;
+        @group(0) @binding(1)
+        var<uniform>  dims: vec4<u32>;
+
+        @compute @workgroup_size(8, 4)
+        fn add_1 (@builtin(global_invocation_id) id: vec3<u32>) {
+            if (id.x < dims.x && id.y < dims.y) {
+                let index = id.x + id.y * dims.x;
+                a[index] = a[index] + 1.0;
+            }
+        }",
+    );
+    let info = shader.entry_point_info("add_1").unwrap();
+    assert_eq!(info.workgroup_size, [8, 4, 1]);
+    assert!(info.required_features.is_empty());
+    assert!(shader.entry_point_info("missing").is_err());
+    assert!(Shader::from_content("fn broken(")
+        .entry_point_info("broken")
+        .is_err());
+
+    let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
+    let var = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::zeros((10, 20)), Some("array")).unwrap(),
+    ));
+    let function = Function::new(
+        &shader,
+        "add_1",
+        vec![VariableBind::new(Arc::clone(&var), 0)],
+    )
+    .with_dimensions(1);
+    algorithm.add_fun(function).unwrap();
+    algorithm.read_variable(&var).unwrap();
+    algorithm.run().await.unwrap();
+
+    // 20 columns in 3 workgroups of 8 and 10 rows in 3 workgroups of 4
+    assert_eq!(algorithm.last_run_stats().workgroups, 9);
+    assert_eq!(
+        var.lock().unwrap().to_ndarray().unwrap(),
+        Array2::ones((10, 20))
+    );
+
+    // 70000 columns are over the limit of workgroups, but not once divided by the workgroup size
+    let wide = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::zeros((1, 70000)), Some("wide")).unwrap(),
+    ));
+    let function = Function::new(
+        &shader,
+        "add_1",
+        vec![VariableBind::new(Arc::clone(&wide), 0)],
+    )
+    .with_dimensions(1);
+    algorithm.add_fun(function).unwrap();
+    algorithm.read_variable(&wide).unwrap();
+    algorithm.run().await.unwrap();
+    assert_eq!(algorithm.last_run_stats().workgroups, 8750);
+    assert_eq!(
+        wide.lock().unwrap().to_ndarray().unwrap(),
+        Array2::ones((1, 70000))
+    );
+}
+
+#[tokio::test]
+async fn heat_diffusion_ping_pong() {
+    let shader = Shader::from_content(
+        "
+        @group(0) @binding(0)
+        var<storage,read_write>  current: array<f32>;
+        @group(0) @binding(1)
+        var<storage,read_write>  next: array<f32>;
+
+        @compute @workgroup_size(1)
+        fn diffuse (@builtin(global_invocation_id) id: vec3<u32>) {
+            let last = arrayLength(&current) - 1u;
+            // the ends of the rod are kept at a fixed temperature
+            if (id.x == 0u || id.x == last) {
+                next[id.x] = current[id.x];
+            } else {
+                next[id.x] = current[id.x]
+                    + 0.25 * (current[id.x - 1u] - 2.0 * current[id.x] + current[id.x + 1u]);
+            }
+        }",
+    );
+    let mut rod = Array2::zeros((1, 16));
+    rod[[0, 0]] = 100.;
+    let mut ping_pong = PingPong::new(
+        GpuArray2::from_ndarray(rod.clone(), Some("rod")).unwrap(),
+        GpuArray2::from_ndarray(rod.clone(), Some("rod next")).unwrap(),
+    )
+    .unwrap();
+    assert!(PingPong::new(
+        GpuArray2::from_ndarray(Array2::zeros((1, 2)), None).unwrap(),
+        GpuArray2::from_ndarray(Array2::zeros((2, 1)), None).unwrap(),
+    )
+    .is_err());
+
+    let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
+    let handles = algorithm
+        .add_iterations(&mut ping_pong, 11, |sides| {
+            Function::new(&shader, "diffuse", sides.binds(0, 1))
+        })
+        .unwrap();
+    assert_eq!(handles.len(), 11);
+    algorithm.run().await.unwrap();
+    algorithm
+        .get_output_unmap(ping_pong.read_side())
+        .await
+        .unwrap();
+
+    for _ in 0..11 {
+        let mut next = rod.clone();
+        for i in 1..15 {
+            next[[0, i]] =
+                rod[[0, i]] + 0.25 * (rod[[0, i - 1]] - 2. * rod[[0, i]] + rod[[0, i + 1]]);
+        }
+        rod = next;
+    }
+    let result = ping_pong.read_side().lock().unwrap().to_ndarray().unwrap();
+    for (got, expected) in result.iter().zip(&rod) {
+        assert!((got - expected).abs() < 1e-3, "{} != {}", got, expected);
+    }
+}
+
+#[tokio::test]
+async fn dependency_graph_dot() {
+    let a = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::zeros((3, 3)), Some("a")).unwrap(),
+    ));
+    let b = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::zeros((3, 3)), Some("b")).unwrap(),
+    ));
+    let shader = Shader::from_file_path("./tests/shaders/mat2calcs.wgsl").unwrap();
+    let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
+    algorithm
+        .add_fun(Function::new(
+            &shader,
+            "add_1",
+            vec![VariableBind::new(Arc::clone(&a), 0)],
+        ))
+        .unwrap();
+    algorithm
+        .add_fun(Function::new(
+            &shader,
+            "add_1",
+            vec![VariableBind::new(Arc::clone(&b), 0)],
+        ))
+        .unwrap();
+    algorithm
+        .add_fun(Function::new(
+            &shader,
+            "add_matrices",
+            vec![
+                VariableBind::new(Arc::clone(&a), 0),
+                VariableBind::new(Arc::clone(&b), 1),
+            ],
+        ))
+        .unwrap();
+    algorithm.read_variable(&a).unwrap();
+
+    let dot = algorithm.to_dot();
+    assert!(dot.starts_with("digraph \"Test algorithm\" {\n"));
+    assert!(dot.contains("op0 [label=\"add_1\", shape=box];"));
+    assert!(dot.contains("op2 [label=\"add_matrices\", shape=box];"));
+    assert!(dot.contains("op3 [label=\"read back\", shape=note];"));
+    assert!(dot.contains("op0 -> op2 [label=\"a\", style=solid];"));
+    assert!(dot.contains("op1 -> op2 [label=\"b\", style=solid];"));
+    assert!(dot.contains("op2 -> op3 [label=\"a\", style=solid];"));
+    // the two first functions are independent
+    assert!(!dot.contains("op0 -> op1"));
+    assert_eq!(dot.matches("->").count(), 3);
+}
+
+#[tokio::test]
+async fn run_with_deadline() {
+    let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
+    let var = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::zeros((3, 3)), Some("array")).unwrap(),
+    ));
+    let shader = Shader::from_file_path("./tests/shaders/mat2calcs.wgsl").unwrap();
+    let mut handles = Vec::new();
+    for _ in 0..3 {
+        let function = Function::new(
+            &shader,
+            "add_1",
+            vec![VariableBind::new(Arc::clone(&var), 0)],
+        );
+        handles.push(algorithm.add_fun(function).unwrap());
+    }
+
+    // a deadline already passed doesn't submit anything
+    let report = algorithm.run_with_deadline(Instant::now()).await.unwrap();
+    assert_eq!(
+        report,
+        DeadlineReport {
+            completed: Vec::new(),
+            skipped: handles.clone(),
+        }
+    );
+    assert_eq!(algorithm.get_operations().len(), 3);
+
+    let report = algorithm
+        .run_with_deadline(Instant::now() + Duration::from_secs(60))
+        .await
+        .unwrap();
+    assert_eq!(report.completed, handles);
+    assert!(report.skipped.is_empty());
+    algorithm.get_output_unmap(&var).await.unwrap();
+    assert_eq!(
+        var.lock().unwrap().to_ndarray().unwrap(),
+        Array2::from_elem((3, 3), 3.)
+    );
+}
+
+#[tokio::test]
+async fn read_variable_to_file() {
+    let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
+    let var = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(
+            array![[0., 0., 0.], [1., 1., 1.], [2., 2., 2.]],
+            Some("array"),
+        )
+        .unwrap(),
+    ));
+    let shader = Shader::from_file_path("./tests/shaders/mat2calcs.wgsl").unwrap();
+    let function = Function::new(
+        &shader,
+        "add_1",
+        vec![VariableBind::new(Arc::clone(&var), 0)],
+    );
+    algorithm.add_fun(function).unwrap();
+    algorithm.run().await.unwrap();
+
+    let path = std::env::temp_dir().join(format!("wgpu_calc_result_{}.bin", std::process::id()));
+    algorithm.read_variable_to_file(&var, &path).await.unwrap();
+    let bytes = std::fs::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let result: Vec<f32> = bytes
+        .chunks_exact(4)
+        .map(|chunk| f32::from_ne_bytes(chunk.try_into().unwrap()))
+        .collect();
+    assert_eq!(result, vec![1., 1., 1., 2., 2., 2., 3., 3., 3.]);
+}
+
+#[tokio::test]
+async fn verify_executed_sentinel() {
+    let shader = Shader::from_content(
+        "
+        @group(0) @binding(0)
+        var<storage,read_write>  a: array<f32>;
+        @group(0) @binding(1)
+        var<storage,read_write>  sentinel: u32;
+
+        @compute @workgroup_size(1)
+        fn add_1 (@builtin(global_invocation_id) id: vec3<u32>) {
+            sentinel = 1u;
+            a[id.x] = a[id.x] + 1.0;
+        }
+
+        @compute @workgroup_size(1)
+        fn forgetful (@builtin(global_invocation_id) id: vec3<u32>) {
+            a[id.x] = a[id.x] + 1.0;
+        }",
+    );
+    let var = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::zeros((1, 4)), Some("array")).unwrap(),
+    ));
+    let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
+    let function = Function::new(
+        &shader,
+        "add_1",
+        vec![VariableBind::new(Arc::clone(&var), 0)],
+    )
+    .with_sentinel(1);
+    algorithm.add_fun(function).unwrap();
+    algorithm.run().await.unwrap();
+    algorithm.verify_executed().await.unwrap();
+
+    let function = Function::new(
+        &shader,
+        "forgetful",
+        vec![VariableBind::new(Arc::clone(&var), 0)],
+    )
+    .with_sentinel(1);
+    algorithm.add_fun(function).unwrap();
+    // the function still scheduled is not checked
+    algorithm.verify_executed().await.unwrap();
+    algorithm.run().await.unwrap();
+    let error = algorithm.verify_executed().await.unwrap_err();
+    assert!(error.to_string().contains("forgetful"), "{}", error);
+}
+
+// a variable whose data changes after being uploaded, as if the upload was corrupted
+#[derive(Debug)]
+struct ChangingVariable {
+    calls: AtomicUsize,
+}
+
+impl PartialEq for ChangingVariable {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Variable for ChangingVariable {
+    fn get_name(&self) -> Option<&str> {
+        Some("changing")
+    }
+
+    fn byte_size(&self) -> u64 {
+        16
+    }
+
+    fn byte_data(&self) -> &[u8] {
+        const UPLOADED: [u8; 16] = [0; 16];
+        const CHANGED: [u8; 16] = [1; 16];
+        if self.calls.fetch_add(1, Ordering::SeqCst) == 0 {
+            &UPLOADED
+        } else {
+            &CHANGED
+        }
+    }
+
+    fn read_data(&mut self, _slice: &[u8]) -> Result<(), anyhow::Error> {
+        Ok(())
+    }
+
+    fn dimension_sizes(&self) -> [u32; 3] {
+        [4, 1, 1]
+    }
+}
+
+#[tokio::test]
+async fn verify_uploads() {
+    let shader = Shader::from_content(
+        "
+        @group(0) @binding(0)
+        var<storage,read_write>  a: array<f32>;
+
+        @compute @workgroup_size(1)
+        fn add_1 (@builtin(global_invocation_id) id: vec3<u32>) {
+            a[id.x] = a[id.x] + 1.0;
+        }",
+    );
+    let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
+    algorithm.debug_verify_uploads(true);
+    let var = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::zeros((1, 4)), Some("array")).unwrap(),
+    ));
+    let function = Function::new(
+        &shader,
+        "add_1",
+        vec![VariableBind::new(Arc::clone(&var), 0)],
+    );
+    algorithm.add_fun(function).unwrap();
+    algorithm.run().await.unwrap();
+
+    let mut algorithm = algorithm.with_shared_executor(Some("Test algorithm"));
+    algorithm.debug_verify_uploads(true);
+    let changing = Arc::new(Mutex::new(ChangingVariable {
+        calls: AtomicUsize::new(0),
+    }));
+    let function = Function::new(&shader, "add_1", vec![VariableBind::new(changing, 0)]);
+    algorithm.add_fun(function).unwrap();
+    let error = algorithm.run().await.unwrap_err();
+    assert!(error.to_string().contains("changing"), "{}", error);
+    assert_eq!(algorithm.poll_run(), RunState::Error(error.to_string()));
+}
+
+#[tokio::test]
+async fn compiled_algorithm_step() {
+    let shader = Shader::from_file_path("./tests/shaders/mat2calcs.wgsl").unwrap();
+    let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
+    let var = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::zeros((3, 3)), Some("array")).unwrap(),
+    ));
+    let bindings = || vec![VariableBind::new(Arc::clone(&var), 0)];
+    algorithm
+        .add_fun(Function::new(&shader, "add_1", bindings()))
+        .unwrap();
+    algorithm
+        .add_fun(Function::new(&shader, "add_1", bindings()))
+        .unwrap();
+    let mut reading = algorithm.with_shared_executor(Some("Reading algorithm"));
+    reading
+        .add_fun(Function::new(&shader, "add_1", bindings()))
+        .unwrap();
+    reading.read_variable(&var).unwrap();
+    let mut compiled = algorithm.compile().unwrap();
+
+    for _ in 0..5 {
+        compiled.step().unwrap();
+    }
+    compiled.get_output(&var).await.unwrap();
+    assert_eq!(
+        var.lock().unwrap().to_ndarray().unwrap(),
+        Array2::from_elem((3, 3), 10.)
+    );
+
+    // the host data goes to the GPU only when marked as changed
+    *var.lock().unwrap() = GpuArray2::from_ndarray(Array2::ones((3, 3)), Some("array")).unwrap();
+    compiled.step().unwrap();
+    compiled.get_output(&var).await.unwrap();
+    assert_eq!(
+        var.lock().unwrap().to_ndarray().unwrap(),
+        Array2::from_elem((3, 3), 12.)
+    );
+    *var.lock().unwrap() = GpuArray2::from_ndarray(Array2::ones((3, 3)), Some("array")).unwrap();
+    compiled.mark_dirty(&var).unwrap();
+    compiled.step().unwrap();
+    compiled.get_output(&var).await.unwrap();
+    assert_eq!(
+        var.lock().unwrap().to_ndarray().unwrap(),
+        Array2::from_elem((3, 3), 3.)
+    );
+
+    // data which doesn't fit the buffer is refused, and stays marked to upload
+    *var.lock().unwrap() = GpuArray2::from_ndarray(Array2::ones((4, 4)), Some("array")).unwrap();
+    compiled.mark_dirty(&var).unwrap();
+    let error = compiled.step().unwrap_err();
+    assert!(error.to_string().contains("byte size"), "{}", error);
+    *var.lock().unwrap() = GpuArray2::from_ndarray(Array2::ones((3, 3)), Some("array")).unwrap();
+    compiled.step().unwrap();
+    compiled.get_output(&var).await.unwrap();
+    assert_eq!(
+        var.lock().unwrap().to_ndarray().unwrap(),
+        Array2::from_elem((3, 3), 3.)
+    );
+
+    let error = reading.compile().unwrap_err();
+    assert!(error.to_string().contains("Reads"), "{}", error);
+}
+
+#[tokio::test]
+async fn zero_variable_between_runs() {
+    let shader = Shader::from_file_path("./tests/shaders/mat2calcs.wgsl").unwrap();
+    let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
+    let var = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::ones((3, 3)) * 5.0, Some("scratch")).unwrap(),
+    ));
+    let bindings = || vec![VariableBind::new(Arc::clone(&var), 0)];
+
+    for _ in 0..2 {
+        algorithm
+            .add_fun(Function::new(&shader, "add_1", bindings()))
+            .unwrap();
+        algorithm.zero_variable(&var).unwrap();
+        algorithm
+            .add_fun(Function::new(&shader, "add_1", bindings()))
+            .unwrap();
+        algorithm.read_variable(&var).unwrap();
+        algorithm.run().await.unwrap();
+        assert_eq!(
+            var.lock().unwrap().to_ndarray().unwrap(),
+            Array2::ones((3, 3))
+        );
+    }
+
+    let unknown = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::ones((3, 3)), Some("unknown")).unwrap(),
+    ));
+    assert!(algorithm.zero_variable(&unknown).is_err());
+}
+
+#[tokio::test]
+async fn fused_function_stages() {
+    let shader = Shader::from_file_path("./tests/shaders/mat2calcs.wgsl").unwrap();
+    let other_shader = Shader::from_content(
+        "
+        @group(0) @binding(0)
+        var<storage,read_write>  a: array<f32>;
+
+        @compute @workgroup_size(1,1,1)
+        fn double (@builtin(global_invocation_id) id: vec3<u32>) {
+            a[id.y * 3u + id.x] = a[id.y * 3u + id.x] * 2.0;
+        }
+
+        @compute @workgroup_size(8,8,1)
+        fn wide (@builtin(global_invocation_id) id: vec3<u32>) {
+        }",
+    );
+    let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
+    let var = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::ones((3, 3)), Some("array")).unwrap(),
+    ));
+    let bindings = || vec![VariableBind::new(Arc::clone(&var), 0)];
+
+    let function =
+        Function::fused(&[(&shader, "add_1"), (&other_shader, "double")], bindings()).unwrap();
+    algorithm.add_fun(function).unwrap();
+    algorithm.read_variable(&var).unwrap();
+    algorithm.run().await.unwrap();
+    assert_eq!(
+        var.lock().unwrap().to_ndarray().unwrap(),
+        Array2::from_elem((3, 3), 4.)
+    );
+    assert_eq!(algorithm.last_run_stats().dispatches, 2);
+    assert_eq!(algorithm.last_run_stats().pipelines, 2);
+
+    let function =
+        Function::fused(&[(&shader, "add_1"), (&other_shader, "wide")], bindings()).unwrap();
+    assert!(algorithm.add_fun(function).is_err());
+    assert!(Function::<GpuArray2>::fused(&[], bindings()).is_err());
+}
+
+#[tokio::test]
+async fn chunked_dispatch_over_limits() {
+    let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
+    // more columns than the workgroups allowed in a single dispatch
+    let var = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::zeros((2, 70000)), Some("array")).unwrap(),
+    ));
+    let shader = Shader::from_content(
+        "
+        struct Dimensions {
+            size: vec4<u32>,
+            offset: vec4<u32>,
+        }
+        @group(0) @binding(0)
+        var<storage,read_write>  a: array<f32>;
+        @group(0) @binding(1)
+        var<uniform>  dims: Dimensions;
+
+        @compute @workgroup_size(1)
+        fn add_1 (@builtin(global_invocation_id) id: vec3<u32>) {
+            let x = id.x + dims.offset.x;
+            let y = id.y + dims.offset.y;
+            if (x < dims.size.x && y < dims.size.y) {
+                let index = x + y * dims.size.x;
+                a[index] = a[index] + 1.0;
+            }
+        }",
+    );
+    let binds = || vec![VariableBind::new(Arc::clone(&var), 0)];
+
+    assert!(algorithm
+        .add_fun(Function::new(&shader, "add_1", binds()).with_dimensions(1))
+        .is_err());
+    assert!(algorithm
+        .add_function_chunked(Function::new(&shader, "add_1", binds()))
+        .is_err());
+
+    let function = Function::new(&shader, "add_1", binds()).with_dimensions(1);
+    algorithm.add_function_chunked(function).unwrap();
+    match &algorithm.get_operations()[0] {
+        OperationInfo::Serial { dispatches, .. } => assert_eq!(dispatches.len(), 2),
+        operation => panic!("unexpected operation {:?}", operation),
+    }
+    algorithm.read_variable(&var).unwrap();
+    algorithm.run().await.unwrap();
+    assert_eq!(
+        var.lock().unwrap().to_ndarray().unwrap(),
+        Array2::ones((2, 70000))
+    );
+}
+
+#[tokio::test]
+async fn poll_run_state() {
+    let shader = Shader::from_file_path("./tests/shaders/mat2calcs.wgsl").unwrap();
+    let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
+    assert_eq!(algorithm.poll_run(), RunState::Done);
+
+    let var = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::zeros((3, 3)), Some("array")).unwrap(),
+    ));
+    let function = Function::new(
+        &shader,
+        "add_1",
+        vec![VariableBind::new(Arc::clone(&var), 0)],
+    );
+    algorithm.add_fun(function).unwrap();
+    algorithm.run().await.unwrap();
+
+    let start = Instant::now();
+    loop {
+        match algorithm.poll_run() {
+            RunState::Done => break,
+            RunState::Pending => assert!(start.elapsed() < Duration::from_secs(10)),
+            RunState::Error(error) => panic!("unexpected error {}", error),
+        }
+        tokio::task::yield_now().await;
+    }
+}
+
+#[tokio::test]
+async fn immutable_read_only_binding() {
+    let shader = Shader::from_content(
+        "
+        @group(0) @binding(0)
+        var<storage,read_write>  a: array<f32>;
+        @group(0) @binding(1)
+        var<storage,read>  b: array<f32>;
+
+        @compute @workgroup_size(1,1,1)
+        fn add_matrices (@builtin(global_invocation_id) id: vec3<u32>) {
+            a[id.y * 3u + id.x] = a[id.y * 3u + id.x] + b[id.y * 3u + id.x];
+        }",
+    );
+    let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
+    let var_1 = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::ones((3, 3)), Some("array_1")).unwrap(),
+    ));
+    let var_2 = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::ones((3, 3)) * 2., Some("array_2")).unwrap(),
+    ));
+
+    let immutable = VariableBind::new(Arc::clone(&var_2), 1).set_immutable();
+    assert!(!immutable.is_mutable());
+    let function = Function::new(
+        &shader,
+        "add_matrices",
+        vec![VariableBind::new(Arc::clone(&var_1), 0)],
+    )
+    .with_immutable(immutable);
+    algorithm.add_fun(function).unwrap();
+    algorithm.read_variable(&var_1).unwrap();
+    algorithm.read_variable(&var_2).unwrap();
+    // the read back of the immutable variable doesn't depend on the function
+    let dot = algorithm.to_dot();
+    assert!(
+        dot.contains("op0 -> op1 [label=\"array_1\", style=solid]"),
+        "{}",
+        dot
+    );
+    assert!(!dot.contains("label=\"array_2\""), "{}", dot);
+    algorithm.run().await.unwrap();
+    assert_eq!(
+        var_1.lock().unwrap().to_ndarray().unwrap(),
+        Array2::from_elem((3, 3), 3.)
+    );
+}
+
+#[tokio::test]
+async fn multiple_bind_groups() {
+    let shader = Shader::from_content(
+        "
+        @group(0) @binding(0)
+        var<storage,read_write>  a: array<f32>;
+        @group(2) @binding(0)
+        var<storage,read_write>  b: array<f32>;
+
+        @compute @workgroup_size(1,1,1)
+        fn add_matrices (@builtin(global_invocation_id) id: vec3<u32>) {
+            a[id.y * 3u + id.x] = a[id.y * 3u + id.x] + b[id.y * 3u + id.x];
+        }",
+    );
+    let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
+    let var_1 = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::ones((3, 3)), Some("array_1")).unwrap(),
+    ));
+    let var_2 = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::ones((3, 3)) * 2., Some("array_2")).unwrap(),
+    ));
+
+    let function = Function::new(
+        &shader,
+        "add_matrices",
+        vec![
+            VariableBind::new(Arc::clone(&var_1), 0),
+            VariableBind::new(Arc::clone(&var_2), 0).in_group(2),
+        ],
+    )
+    .with_group_label(2, "constants");
+    algorithm.add_fun(function).unwrap();
+    algorithm.read_variable(&var_1).unwrap();
+    algorithm.run().await.unwrap();
+    assert_eq!(
+        var_1.lock().unwrap().to_ndarray().unwrap(),
+        Array2::from_elem((3, 3), 3.)
+    );
+}
+
+#[tokio::test]
+async fn explicit_workgroup_count() {
+    let shader = Shader::from_file_path("./tests/shaders/mat2calcs.wgsl").unwrap();
+    let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
+    let var = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::zeros((3, 3)), Some("array")).unwrap(),
+    ));
+
+    // only the first row is covered by the dispatch
+    let function = Function::new(
+        &shader,
+        "add_1",
+        vec![VariableBind::new(Arc::clone(&var), 0)],
+    )
+    .with_workgroups([1, 3, 1]);
+    algorithm.add_fun(function).unwrap();
+    algorithm.read_variable(&var).unwrap();
+    algorithm.run().await.unwrap();
+    assert_eq!(
+        var.lock().unwrap().to_ndarray().unwrap(),
+        array![[1., 1., 1.], [0., 0., 0.], [0., 0., 0.]]
+    );
+    assert_eq!(algorithm.last_run_stats().workgroups, 3);
+
+    let function = Function::new(
+        &shader,
+        "add_1",
+        vec![VariableBind::new(Arc::clone(&var), 0)],
+    )
+    .with_workgroups([u32::MAX, 1, 1]);
+    assert!(algorithm.add_fun(function).is_err());
+}
+
+#[tokio::test]
+async fn shader_include_directive() {
+    let dir = std::env::temp_dir().join(format!("wgpu_calc_include_{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("common")).unwrap();
+    std::fs::write(
+        dir.join("common").join("helpers.wgsl"),
+        "
+        fn add_one(x: f32) -> f32 {
+            return x + 1.0;
+        }",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("add_1.wgsl"),
+        "
+        //!include \"common/helpers.wgsl\"
+        @group(0) @binding(0)
+        var<storage,read_write>  a: array<f32>;
+
+        @compute @workgroup_size(64)
+        fn add_1 (@builtin(global_invocation_id) id: vec3<u32>) {
+            if (id.x < arrayLength(&a)) {
+                a[id.x] = add_one(a[id.x]);
+            }
+        }",
+    )
+    .unwrap();
+    std::fs::write(dir.join("cycle.wgsl"), "//!include \"cycle.wgsl\"").unwrap();
+    std::fs::write(dir.join("malformed.wgsl"), "//!include helpers.wgsl").unwrap();
+
+    let shader = Shader::from_file_path(dir.join("add_1.wgsl").to_str().unwrap()).unwrap();
+    assert!(Shader::from_file_path(dir.join("cycle.wgsl").to_str().unwrap()).is_err());
+    assert!(Shader::from_file_path(dir.join("malformed.wgsl").to_str().unwrap()).is_err());
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
+    let input = ndarray::Array1::range(0., 10., 1.);
+    let var = Arc::new(Mutex::new(
+        GpuArray1::from_ndarray(input.view(), Some("vector")).unwrap(),
+    ));
+    let function = Function::new(
+        &shader,
+        "add_1",
+        vec![VariableBind::new(Arc::clone(&var), 0)],
+    );
+    algorithm.add_fun(function).unwrap();
+    algorithm.read_variable(&var).unwrap();
+    algorithm.run().await.unwrap();
+    assert_eq!(var.lock().unwrap().to_array1(), input + 1.);
+}
+
+#[test]
+fn shader_reload() {
+    let path = std::env::temp_dir().join(format!("wgpu_calc_reload_{}.wgsl", std::process::id()));
+    std::fs::write(&path, "const SCALE: f32 = €scale;").unwrap();
+    let mut shader = Shader::from_file_path(path.to_str().unwrap()).unwrap();
+    assert!(!shader.reload().unwrap());
+
+    // the templating is lost
+    shader.replace("€scale", "2.0");
+    assert!(shader.reload().unwrap());
+    assert_eq!(shader.get_content(), "const SCALE: f32 = €scale;\n");
+
+    std::fs::write(&path, "const SCALE: f32 = 3.0;").unwrap();
+    assert!(shader.reload().unwrap());
+    assert_eq!(shader.get_content(), "const SCALE: f32 = 3.0;\n");
+
+    // a missing file leaves the shader as it is
+    std::fs::remove_file(&path).unwrap();
+    assert!(shader.reload().is_err());
+    assert_eq!(shader.get_content(), "const SCALE: f32 = 3.0;\n");
+    assert!(Shader::from_content("").reload().is_err());
+}
+
+#[tokio::test]
+async fn invalid_shader_error() {
+    let shader = Shader::from_content(
+        "
+        @group(0) @binding(0)
+        var<storage,read_write>  a: array<f32>;
+
+        @compute @workgroup_size(64)
+        fn add_1 (@builtin(global_invocation_id) id: vec3<u32>) {
+            a[id.x] = a[id.x] + 1u;
+        }",
+    );
+    let error = shader.validate().unwrap_err().to_string();
+    assert!(error.contains("wgsl:7:"), "{}", error);
+
+    let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
+    let var = Arc::new(Mutex::new(
+        GpuArray1::from_ndarray(ndarray::Array1::zeros(10).view(), Some("vector")).unwrap(),
+    ));
+    let function = Function::new(
+        &shader,
+        "add_1",
+        vec![VariableBind::new(Arc::clone(&var), 0)],
+    );
+    assert!(algorithm.add_fun(function).is_err());
+
+    // a typo in the entry point lists the existing ones
+    let shader = Shader::from_content(
+        "
+        @group(0) @binding(0)
+        var<storage,read_write>  a: array<f32>;
+
+        @compute @workgroup_size(64)
+        fn add_1 (@builtin(global_invocation_id) id: vec3<u32>) {
+            a[id.x] = a[id.x] + 1.0;
+        }",
+    );
+    assert_eq!(shader.entry_points().unwrap(), vec!["add_1"]);
+    let function = Function::new(
+        &shader,
+        "add1",
+        vec![VariableBind::new(Arc::clone(&var), 0)],
+    );
+    let error = algorithm.add_fun(function).unwrap_err().to_string();
+    assert!(error.contains("[\"add_1\"]"), "{}", error);
+
+    // a binding used by the entry point must be given a variable
+    let shader = Shader::from_content(
+        "
+        @group(0) @binding(0)
+        var<storage,read_write>  a: array<f32>;
+        @group(0) @binding(1)
+        var<storage,read>  b: array<f32>;
+
+        @compute @workgroup_size(64)
+        fn add (@builtin(global_invocation_id) id: vec3<u32>) {
+            a[id.x] = a[id.x] + b[id.x];
+        }",
+    );
+    let function = Function::new(&shader, "add", vec![VariableBind::new(Arc::clone(&var), 0)]);
+    let error = algorithm.add_fun(function).unwrap_err().to_string();
+    assert!(error.contains("binding 1 of group 0"), "{}", error);
+}
+
+#[tokio::test]
+async fn add_vectors_array1() {
+    let shader = Shader::from_content(
+        "
+        @group(0) @binding(0)
+        var<storage,read_write>  a: array<f32>;
+        @group(0) @binding(1)
+        var<storage,read_write>  b: array<f32>;
+
+        @compute @workgroup_size(64)
+        fn add_vectors (@builtin(global_invocation_id) id: vec3<u32>) {
+            if (id.x < arrayLength(&a)) {
+                a[id.x] = a[id.x] + b[id.x];
+            }
+        }",
+    );
+    let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
+    let input = ndarray::Array1::range(0., 100., 1.);
+    let var_1 = Arc::new(Mutex::new(
+        GpuArray1::from_ndarray(input.view(), Some("vector_1")).unwrap(),
+    ));
+    // a view with a step is copied contiguously
+    let var_2 = Arc::new(Mutex::new(
+        GpuArray1::from_ndarray(
+            ndarray::Array1::ones(200).slice(ndarray::s![..;2]),
+            Some("vector_2"),
+        )
+        .unwrap(),
+    ));
+    assert_eq!(var_1.lock().unwrap().dimension_sizes(), [100, 1, 1]);
+    assert!(GpuArray1::from_ndarray(ndarray::Array1::<f32>::zeros(0), None).is_err());
+    // the bytes read back must fill the whole array
+    assert!(var_2.lock().unwrap().read_data(&[0; 6]).is_err());
+
+    let function = Function::new(
+        &shader,
+        "add_vectors",
+        vec![
+            VariableBind::new(Arc::clone(&var_1), 0),
+            VariableBind::new(Arc::clone(&var_2), 1),
+        ],
+    );
+    algorithm.add_fun(function).unwrap();
+    algorithm.read_variable(&var_1).unwrap();
+    algorithm.run().await.unwrap();
+    assert_eq!(var_1.lock().unwrap().to_array1(), input + 1.);
+}
+
+#[tokio::test]
+async fn add_1_volume_array3() {
+    let shader = Shader::from_content(
+        "
+        @group(0) @binding(0)
+        var<storage,read_write>  a: array<f32>;
+
+        @compute @workgroup_size(1,1,1)
+        fn add_1 (@builtin(global_invocation_id) id: vec3<u32>) {
+            let index = id.x + 4u * (id.y + 3u * id.z);
+            a[index] = a[index] + 1.0;
+        }",
+    );
+    let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
+    let volume =
+        ndarray::Array3::from_shape_fn((2, 3, 4), |(i, j, k)| (k + 4 * (j + 3 * i)) as f32);
+    let var = Arc::new(Mutex::new(
+        GpuArray3::from_ndarray(volume.view(), Some("volume")).unwrap(),
+    ));
+    assert_eq!(var.lock().unwrap().dimension_sizes(), [4, 3, 2]);
+
+    let function = Function::new(
+        &shader,
+        "add_1",
+        vec![VariableBind::new(Arc::clone(&var), 0)],
+    );
+    algorithm.add_fun(function).unwrap();
+    algorithm.read_variable(&var).unwrap();
+    algorithm.run().await.unwrap();
+    let result = var.lock().unwrap().to_ndarray().unwrap();
+    assert_eq!(result.dim(), (2, 3, 4));
+    assert_eq!(result, volume + 1.);
+}
+
+#[tokio::test]
+async fn add_1_dynamic_arrayd() {
+    let shader = Shader::from_content(
+        "
+        @group(0) @binding(0)
+        var<storage,read_write>  a: array<f32>;
+
+        @compute @workgroup_size(1,1,1)
+        fn add_1 (@builtin(global_invocation_id) id: vec3<u32>) {
+            let index = id.x + 4u * (id.y + 3u * id.z);
+            a[index] = a[index] + 1.0;
+        }",
+    );
+    let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
+    let array = ndarray::ArrayD::from_shape_fn(vec![1, 2, 3, 4], |index| {
+        (index[3] + 4 * (index[2] + 3 * index[1])) as f32
+    });
+    let var = Arc::new(Mutex::new(
+        GpuArrayD::from_ndarray(array.view(), Some("array")).unwrap(),
+    ));
+    assert_eq!(var.lock().unwrap().dimension_sizes(), [4, 3, 2]);
+    assert_eq!(var.lock().unwrap().dimensions(), vec![4, 3, 2, 1]);
+    // the leading axes can't be mapped on the workgroups
+    assert!(
+        GpuArrayD::from_ndarray(ndarray::ArrayD::<f32>::zeros(vec![2, 2, 3, 4]), None).is_err()
+    );
+
+    let function = Function::new(
+        &shader,
+        "add_1",
+        vec![VariableBind::new(Arc::clone(&var), 0)],
+    );
+    algorithm.add_fun(function).unwrap();
+    algorithm.read_variable(&var).unwrap();
+    algorithm.run().await.unwrap();
+    let result = var.lock().unwrap().to_ndarray().unwrap();
+    assert_eq!(result.shape(), &[1, 2, 3, 4]);
+    assert_eq!(result, array + 1.);
+}
+
+#[tokio::test]
+async fn integer_matrices() {
+    let shader = Shader::from_content(
+        "
+        @group(0) @binding(0)
+        var<storage,read_write>  counts: array<u32>;
+        @group(0) @binding(1)
+        var<storage,read_write>  offsets: array<i32>;
+
+        @compute @workgroup_size(1,1,1)
+        fn count (@builtin(global_invocation_id) id: vec3<u32>) {
+            let index = id.y * 3u + id.x;
+            counts[index] = counts[index] + index;
+            offsets[index] = offsets[index] - i32(index);
+        }",
+    );
+    let mut algorithm = Algorithm::<GpuMatrix<u32>>::new(Some("Test algorithm"))
+        .await
+        .unwrap();
+    let counts = Arc::new(Mutex::new(
+        GpuMatrix::from_ndarray(Array2::<u32>::ones((2, 3)), Some("counts")).unwrap(),
+    ));
+    assert_eq!(counts.lock().unwrap().wgsl_type(), Some("array<u32>"));
+    // the variables of an algorithm have one type, the i32 buffer is read back as u32 bytes
+    let offsets = Arc::new(Mutex::new(
+        GpuMatrix::from_ndarray(Array2::<u32>::zeros((2, 3)), Some("offsets")).unwrap(),
+    ));
+
+    let function = Function::new(
+        &shader,
+        "count",
+        vec![
+            VariableBind::new(Arc::clone(&counts), 0),
+            VariableBind::new(Arc::clone(&offsets), 1),
+        ],
+    );
+    algorithm.add_fun(function).unwrap();
+    algorithm.read_variable(&counts).unwrap();
+    algorithm.read_variable(&offsets).unwrap();
+    algorithm.run().await.unwrap();
+    assert_eq!(
+        counts.lock().unwrap().to_ndarray().unwrap(),
+        array![[1, 2, 3], [4, 5, 6]]
+    );
+    let offsets = offsets.lock().unwrap().to_ndarray().unwrap();
+    assert_eq!(
+        offsets.mapv(|offset| offset as i32),
+        array![[0, -1, -2], [-3, -4, -5]]
+    );
+
+    let signed = GpuMatrix::from_ndarray(array![[-1i32, 2]], None).unwrap();
+    assert_eq!(signed.wgsl_type(), Some("array<i32>"));
+    assert_eq!(signed.byte_data(), bytemuck::cast_slice(&[-1i32, 2]));
+}
+
+#[tokio::test]
+async fn uniform_constants() {
+    let shader = Shader::from_content(
+        "
+        @group(0) @binding(0)
+        var<storage,read_write>  a: array<f32>;
+        @group(0) @binding(1)
+        var<uniform>  params: vec4<f32>;
+
+        @compute @workgroup_size(1,1,1)
+        fn scale (@builtin(global_invocation_id) id: vec3<u32>) {
+            let index = id.y * 3u + id.x;
+            a[index] = a[index] * params.x + params.y;
+        }",
+    );
+    let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
+    let var = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::ones((3, 3)), Some("array")).unwrap(),
+    ));
+    let params = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(array![[3., 1., 0., 0.]], Some("params")).unwrap(),
+    ));
+
+    let function = Function::new(
+        &shader,
+        "scale",
+        vec![
+            VariableBind::new(Arc::clone(&var), 0),
+            VariableBind::new(Arc::clone(&params), 1).as_uniform(),
+        ],
+    );
+    algorithm.add_fun(function).unwrap();
+    algorithm.read_variable(&var).unwrap();
+    algorithm.run().await.unwrap();
+    assert_eq!(
+        var.lock().unwrap().to_ndarray().unwrap(),
+        Array2::from_elem((3, 3), 4.)
+    );
+
+    // the buffer of `var` was made without the uniform usage
+    let function = Function::new(
+        &shader,
+        "scale",
+        vec![
+            VariableBind::new(Arc::clone(&params), 0),
+            VariableBind::new(Arc::clone(&var), 1).as_uniform(),
+        ],
+    );
+    assert!(algorithm.add_fun(function).is_err());
+}
+
+#[tokio::test]
+async fn push_constant_scale() {
+    let shader = Shader::from_content(
+        "
+        @group(0) @binding(0)
+        var<storage,read_write>  a: array<f32>;
+        var<push_constant> scale: f32;
+
+        @compute @workgroup_size(1,1,1)
+        fn scale_by (@builtin(global_invocation_id) id: vec3<u32>) {
+            let index = id.y * 3u + id.x;
+            a[index] = a[index] * scale;
+        }",
+    );
+    let options = ExecutorOptions {
+        features: wgpu::Features::PUSH_CONSTANTS,
+        limits: wgpu::Limits {
+            max_push_constant_size: 16,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut algorithm = match Algorithm::with_options(Some("Test algorithm"), options).await {
+        Ok(algorithm) => algorithm,
+        // push constants are a native only feature, not supported by every adapter
+        Err(error) => return println!("Skipping push constants test: {}", error),
+    };
+    let var = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::ones((3, 3)), Some("array")).unwrap(),
+    ));
+    let bindings = || vec![VariableBind::new(Arc::clone(&var), 0)];
+
+    for factor in [2f32, 3.] {
+        let function = Function::new(&shader, "scale_by", bindings())
+            .with_push_constants(bytemuck::bytes_of(&factor));
+        algorithm.add_fun(function).unwrap();
+    }
+    algorithm.read_variable(&var).unwrap();
+    algorithm.run().await.unwrap();
+    assert_eq!(
+        var.lock().unwrap().to_ndarray().unwrap(),
+        Array2::from_elem((3, 3), 6.)
+    );
+
+    let function = Function::new(&shader, "scale_by", bindings()).with_push_constants(&[0; 5]);
+    assert!(algorithm.add_fun(function).is_err());
+    let function = Function::new(&shader, "scale_by", bindings()).with_push_constants(&[0; 32]);
+    assert!(algorithm.add_fun(function).is_err());
+}
+
+#[tokio::test]
+async fn function_timings() {
+    let shader = Shader::from_file_path("./tests/shaders/mat2calcs.wgsl").unwrap();
+    let options = ExecutorOptions {
+        features: wgpu::Features::TIMESTAMP_QUERY,
+        ..Default::default()
+    };
+    // without timestamp queries nothing is measured
+    let (mut algorithm, supported) =
+        match Algorithm::with_options(Some("Test algorithm"), options).await {
+            Ok(algorithm) => (algorithm, true),
+            Err(_) => (Algorithm::new(Some("Test algorithm")).await.unwrap(), false),
+        };
+    let var = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::zeros((3, 3)), Some("array")).unwrap(),
+    ));
+    assert!(algorithm.timings().is_empty());
+
+    for entry_point in ["add_1", "add_matrices"] {
+        let bindings = vec![
+            VariableBind::new(Arc::clone(&var), 0),
+            VariableBind::new(Arc::clone(&var), 1),
+        ];
+        algorithm
+            .add_fun(Function::new(&shader, entry_point, bindings))
+            .unwrap();
+    }
+    algorithm.run().await.unwrap();
+    let timings = algorithm.timings();
+    if supported {
+        let names: Vec<&str> = timings.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["add_1", "add_matrices"]);
+    } else {
+        assert!(timings.is_empty());
+    }
+}
+
+#[tokio::test]
+async fn rerun_with_new_inputs() {
+    let shader = Shader::from_file_path("./tests/shaders/mat2calcs.wgsl").unwrap();
+    let mut algorithm = Algorithm::new(Some("Test algorithm")).await.unwrap();
+    let var = Arc::new(Mutex::new(
+        GpuArray2::from_ndarray(Array2::zeros((3, 3)), Some("array")).unwrap(),
+    ));
+    assert!(algorithm.rerun().await.is_err());
+
+    let function = Function::new(
+        &shader,
+        "add_1",
+        vec![VariableBind::new(Arc::clone(&var), 0)],
+    );
+    algorithm.add_fun(function).unwrap();
+    algorithm.read_variable(&var).unwrap();
+    algorithm.run().await.unwrap();
+    assert_eq!(
+        var.lock().unwrap().to_ndarray().unwrap(),
+        Array2::ones((3, 3))
+    );
+    let run_stats = algorithm.last_run_stats().clone();
+
+    // without marking it dirty the buffer keeps the result of the last run
+    algorithm.rerun().await.unwrap();
+    assert_eq!(
+        var.lock().unwrap().to_ndarray().unwrap(),
+        Array2::from_elem((3, 3), 2.)
+    );
+    assert_eq!(
+        algorithm.last_run_stats(),
+        &RunStats {
+            bytes_uploaded: 0,
+            ..run_stats.clone()
+        }
+    );
+
+    *var.lock().unwrap() =
+        GpuArray2::from_ndarray(Array2::from_elem((3, 3), 5.), Some("array")).unwrap();
+    algorithm.mark_dirty(&var).unwrap();
+    algorithm.rerun().await.unwrap();
+    assert_eq!(
+        var.lock().unwrap().to_ndarray().unwrap(),
+        Array2::from_elem((3, 3), 6.)
+    );
+    assert_eq!(algorithm.last_run_stats(), &run_stats);
+
+    // a run with other operations than functions and reads can't be repeated
+    algorithm
+        .add_fun(Function::new(
+            &shader,
+            "add_1",
+            vec![VariableBind::new(Arc::clone(&var), 0)],
+        ))
+        .unwrap();
+    algorithm.zero_variable(&var).unwrap();
+    algorithm.run().await.unwrap();
+    let error = algorithm.rerun().await.unwrap_err();
+    assert_eq!(algorithm.poll_run(), RunState::Error(error.to_string()));
+}
@@ -0,0 +1,23 @@
+// 3x3 box blur of a grayscale image, the texels at the border average only their neighbours inside the image
+@group(0) @binding(0)
+var input: texture_2d<f32>;
+@group(0) @binding(1)
+var output: texture_storage_2d<r32float, write>;
+
+@compute @workgroup_size(1)
+fn blur(@builtin(global_invocation_id) id: vec3<u32>) {
+    let size = vec2<i32>(textureDimensions(input));
+    let texel = vec2<i32>(id.xy);
+    var sum = 0.0;
+    var count = 0.0;
+    for (var dy = -1; dy <= 1; dy++) {
+        for (var dx = -1; dx <= 1; dx++) {
+            let neighbour = texel + vec2<i32>(dx, dy);
+            if all(neighbour >= vec2<i32>(0)) && all(neighbour < size) {
+                sum += textureLoad(input, neighbour, 0).x;
+                count += 1.0;
+            }
+        }
+    }
+    textureStore(output, texel, vec4<f32>(sum / count, 0.0, 0.0, 1.0));
+}